@@ -0,0 +1,344 @@
+use scrypto::prelude::*;
+use crate::core_math::{self, Allocation};
+use crate::{
+    to_utc_date_time, ActusState, BusinessDayConvention, ContractPerformance, ContractRole, ContractTerms, DayCountConvention,
+    FeeBasis, PrepaymentFee, PrepaymentTerms, RoundingConvention, SECONDS_PER_DAY,
+};
+
+// This module expresses the contract's lifecycle as ACTUS state-transition and payoff
+// functions over explicit state variables. The component keeps its own accrual rules
+// (rate steps, capitalization, caps) and accrues up to an event before applying it, so
+// the accrual step below is then a no-op; it matters when the functions are replayed
+// off-ledger against an ACTUS engine. It also writes terms and state in the ACTUS JSON
+// dictionary.
+
+/// The ACTUS state variables of a Call Money contract.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateVariables {
+    pub status_date: i64,              // Sd: the date the state is valid at
+    pub nominal_value: Decimal,        // Nt: outstanding principal
+    pub accrued_interest: PreciseDecimal, // Ipac: interest accrued but not yet paid
+    pub accrued_penalties: PreciseDecimal, // Penalties accrued but not yet paid
+    pub fee_accrued: Decimal,          // Feac: fees charged but not yet paid
+    pub nominal_interest_rate: Decimal, // Ipnr: annual interest rate
+    pub maturity_date: Option<i64>,    // Md: when the balance falls due, once known
+}
+
+/// The contract terms the state-transition and payoff functions depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActusTerms {
+    pub day_count: DayCountConvention, // IPCB/DCC: how periods are converted to year fractions
+    pub rounding: RoundingConvention,  // How precise balances are rounded when settled
+}
+
+/// A contract event the state-transition and payoff functions are evaluated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActusEvent {
+    Call { date: i64, due_date: Option<i64> }, // XD: the lender calls; the due date is unknown until acknowledged
+    Penalty { date: i64, charge: PreciseDecimal }, // PY: a penalty is charged on the overdue balance
+    Repayment { date: i64, amount: Decimal }, // PP/MD: a payment is applied to the balances
+}
+
+impl ActusEvent {
+    /// The date the event takes effect.
+    pub fn date(&self) -> i64 {
+        match self {
+            ActusEvent::Call { date, .. } | ActusEvent::Penalty { date, .. } | ActusEvent::Repayment { date, .. } => *date,
+        }
+    }
+}
+
+/// Returns the state after an event: interest accrues from the status date to the
+/// event date at the nominal rate, then the event applies its own change.
+pub fn state_transition(event: &ActusEvent, state: &StateVariables, terms: &ActusTerms) -> StateVariables {
+    let mut next = accrue(state, event.date(), terms);
+    match *event {
+        ActusEvent::Call { due_date, .. } => {
+            next.maturity_date = due_date;
+        }
+        ActusEvent::Penalty { charge, .. } => {
+            next.accrued_penalties += charge;
+        }
+        ActusEvent::Repayment { .. } => {
+            let allocation = payoff(event, &next, terms);
+            next.accrued_penalties = settle(next.accrued_penalties, allocation.penalties_paid, terms);
+            next.fee_accrued -= allocation.fees_paid;
+            next.accrued_interest = settle(next.accrued_interest, allocation.interest_paid, terms);
+            next.nominal_value -= allocation.principal_paid;
+        }
+    }
+    next
+}
+
+/// Returns the cash flow an event produces for the lender, split by the balance it
+/// settles: penalties first, then fees, then interest, then principal. Only repayments pay out.
+pub fn payoff(event: &ActusEvent, state: &StateVariables, terms: &ActusTerms) -> Allocation {
+    match *event {
+        ActusEvent::Repayment { date, amount } => {
+            let state = accrue(state, date, terms);
+            core_math::allocate_payment(
+                amount,
+                round(state.accrued_penalties, terms),
+                state.fee_accrued,
+                round(state.accrued_interest, terms),
+                state.nominal_value,
+            )
+        }
+        ActusEvent::Call { .. } | ActusEvent::Penalty { .. } => Allocation {
+            penalties_paid: Decimal::ZERO,
+            fees_paid: Decimal::ZERO,
+            interest_paid: Decimal::ZERO,
+            principal_paid: Decimal::ZERO,
+            excess: Decimal::ZERO,
+        },
+    }
+}
+
+/// Accrues interest from the status date to `date`, which becomes the new status date.
+fn accrue(state: &StateVariables, date: i64, terms: &ActusTerms) -> StateVariables {
+    let mut next = *state;
+    if date > state.status_date {
+        next.accrued_interest += core_math::compute_interest(
+            state.nominal_value,
+            state.nominal_interest_rate,
+            state.status_date,
+            date,
+            terms.day_count,
+        );
+        next.status_date = date;
+    }
+    next
+}
+
+/// Reduces a precise balance by a payment. Paying the whole rounded amount settles the
+/// balance, sub-unit remainder included.
+fn settle(balance: PreciseDecimal, paid: Decimal, terms: &ActusTerms) -> PreciseDecimal {
+    if paid >= round(balance, terms) {
+        PreciseDecimal::ZERO
+    } else {
+        balance - PreciseDecimal::from(paid)
+    }
+}
+
+/// Rounds a precise balance to a payable amount under the rounding convention.
+fn round(value: PreciseDecimal, terms: &ActusTerms) -> Decimal {
+    value.checked_truncate(terms.rounding.mode()).unwrap()
+}
+
+/// Serializes the terms as an ACTUS CLM attribute dictionary, in JSON.
+///
+/// # Arguments
+/// * `terms` - The terms currently in force
+/// * `notional_principal` - The principal exchanged at the initial exchange date
+/// * `status_date` - The date the terms are reported at
+/// * `maturity_date` - When the balance falls due, once known
+/// * `rate_benchmark` - The market object code rate resets are read from, if any
+pub fn terms_json(
+    terms: &ContractTerms,
+    notional_principal: Decimal,
+    status_date: i64,
+    maturity_date: Option<i64>,
+    rate_benchmark: Option<&str>,
+) -> String {
+    let (creator, counterparty) = match terms.contract_role {
+        ContractRole::RealPositionAsset => (&terms.reference.lender_lei, &terms.reference.borrower_lei),
+        ContractRole::RealPositionLiability => (&terms.reference.borrower_lei, &terms.reference.lender_lei),
+    };
+    to_json(&[
+        ("contractType", Some("CLM".to_string())),
+        ("contractID", terms.contract_id.clone()),
+        ("contractRole", Some(contract_role_code(terms.contract_role).to_string())),
+        ("creatorID", Some(creator.clone())),
+        ("counterpartyID", Some(counterparty.clone())),
+        ("currency", Some(terms.reference.currency_code.clone())),
+        ("statusDate", Some(date(status_date))),
+        ("initialExchangeDate", Some(date(terms.start_date))),
+        ("maturityDate", maturity_date.map(date)),
+        ("notionalPrincipal", Some(notional_principal.to_string())),
+        ("nominalInterestRate", Some(terms.interest_rate.to_string())),
+        ("dayCountConvention", Some(day_count_code(terms.day_count).to_string())),
+        ("businessDayConvention", Some(business_day_code(terms.calendar.convention).to_string())),
+        ("calendar", Some(calendar_code(terms.calendar.convention).to_string())),
+        ("xDayNotice", Some(period(terms.notice_period))),
+        ("cycleOfInterestPayment", terms.interest_payment_cycle.map(cycle)),
+        ("cycleAnchorDateOfInterestPayment", terms.interest_payment_anchor.map(date)),
+        ("cycleOfRateReset", terms.rate_reset.map(|reset| cycle(reset.cycle))),
+        ("rateSpread", terms.rate_reset.map(|reset| reset.spread.to_string())),
+        ("rateMultiplier", terms.rate_reset.map(|reset| reset.multiplier.to_string())),
+        ("lifeCap", terms.rate_cap.map(|cap| cap.to_string())),
+        ("lifeFloor", terms.rate_floor.map(|floor| floor.to_string())),
+        ("marketObjectCodeOfRateReset", rate_benchmark.map(str::to_string)),
+        ("penaltyType", Some(prepayment_code(terms.prepayment).to_string())),
+        ("penaltyRate", terms.prepayment.map(|prepayment| match prepayment.fee {
+            PrepaymentFee::Absolute(amount) | PrepaymentFee::Relative(amount) => amount.to_string(),
+        })),
+        ("feeBasis", terms.fees.map(|fees| fee_basis_code(fees.fee_basis).to_string())),
+        ("feeRate", terms.fees.map(|fees| fees.fee_rate.to_string())),
+        ("cycleOfFee", terms.fees.map(|fees| cycle(fees.fee_cycle))),
+        ("gracePeriod", Some(period(terms.grace_period))),
+        ("delinquencyRate", Some(terms.penalty_rate.to_string())),
+    ])
+}
+
+/// Serializes the state variables under their ACTUS names, in JSON.
+///
+/// # Arguments
+/// * `state` - The state at its status date
+/// * `maturity_date` - When the balance falls due, once known
+pub fn state_json(state: &ActusState, maturity_date: Option<i64>) -> String {
+    to_json(&[
+        ("statusDate", Some(date(state.status_date))),
+        ("contractPerformance", Some(performance_code(state.contract_performance).to_string())),
+        ("notionalPrincipal", Some(state.nominal_value.to_string())),
+        ("accruedInterest", Some(state.accrued_interest.to_string())),
+        ("feeAccrued", Some(state.fee_accrued.to_string())),
+        ("nominalInterestRate", Some(state.nominal_interest_rate.to_string())),
+        ("maturityDate", maturity_date.map(date)),
+    ])
+}
+
+/// Writes attributes as a JSON object with string values, as ACTUS engines accept them.
+/// Attributes without a value are left out.
+fn to_json(attributes: &[(&str, Option<String>)]) -> String {
+    let fields: Vec<String> = attributes
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| format!("\"{}\":\"{}\"", key, escape(value))))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Escapes a value for use inside a JSON string.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats a Unix timestamp as an ACTUS date-time, e.g. "2024-01-31T00:00:00".
+fn date(timestamp: i64) -> String {
+    let date_time = to_utc_date_time(Instant::new(timestamp));
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        date_time.year(),
+        date_time.month(),
+        date_time.day_of_month(),
+        date_time.hour(),
+        date_time.minute(),
+        date_time.second()
+    )
+}
+
+/// Formats a number of seconds as an ISO 8601 period, in whole days where possible.
+fn period(seconds: i64) -> String {
+    if seconds % SECONDS_PER_DAY == 0 {
+        format!("P{}D", seconds / SECONDS_PER_DAY)
+    } else {
+        format!("PT{}S", seconds)
+    }
+}
+
+/// Formats a cycle length as an ACTUS cycle with a short final stub, e.g. "P30DL1".
+fn cycle(seconds: i64) -> String {
+    format!("{}L1", period(seconds))
+}
+
+/// The ACTUS code of a contract role.
+fn contract_role_code(role: ContractRole) -> &'static str {
+    match role {
+        ContractRole::RealPositionAsset => "RPA",
+        ContractRole::RealPositionLiability => "RPL",
+    }
+}
+
+/// The ACTUS code of a day count convention.
+fn day_count_code(day_count: DayCountConvention) -> &'static str {
+    match day_count {
+        DayCountConvention::ActualActual => "AA",
+        DayCountConvention::Actual360 => "A360",
+        DayCountConvention::Actual365 => "A365",
+        DayCountConvention::ThirtyE360 => "30E360",
+    }
+}
+
+/// The ACTUS code of a business-day convention, shifting both schedule and calculation dates.
+fn business_day_code(convention: BusinessDayConvention) -> &'static str {
+    match convention {
+        BusinessDayConvention::Unadjusted => "NOS",
+        BusinessDayConvention::Following => "SCF",
+        BusinessDayConvention::ModifiedFollowing => "SCMF",
+        BusinessDayConvention::Preceding => "SCP",
+    }
+}
+
+/// The ACTUS calendar code: Monday to Friday whenever dates are adjusted.
+fn calendar_code(convention: BusinessDayConvention) -> &'static str {
+    match convention {
+        BusinessDayConvention::Unadjusted => "NC",
+        _ => "MF",
+    }
+}
+
+/// The ACTUS penalty type of the prepayment terms: none, absolute or relative.
+fn prepayment_code(prepayment: Option<PrepaymentTerms>) -> &'static str {
+    match prepayment.map(|prepayment| prepayment.fee) {
+        None => "N",
+        Some(PrepaymentFee::Absolute(_)) => "A",
+        Some(PrepaymentFee::Relative(_)) => "R",
+    }
+}
+
+/// The ACTUS code of a fee basis.
+fn fee_basis_code(basis: FeeBasis) -> &'static str {
+    match basis {
+        FeeBasis::Absolute => "A",
+        FeeBasis::Notional => "N",
+    }
+}
+
+/// The ACTUS code of a contract performance.
+fn performance_code(performance: ContractPerformance) -> &'static str {
+    match performance {
+        ContractPerformance::Performant => "PF",
+        ContractPerformance::Delayed => "DL",
+        ContractPerformance::Delinquent => "DQ",
+        ContractPerformance::Default => "DF",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_json_uses_the_actus_names() {
+        // An active contract of 1,000 at 5%, 30 days after its 1 June 2023 start
+        let start = 1_685_577_600;
+        let status_date = start + 30 * SECONDS_PER_DAY;
+        let interest = core_math::compute_interest(Decimal::from(1_000), dec!("0.05"), start, status_date, DayCountConvention::Actual365);
+        let accrued_interest = interest.checked_truncate(RoundingMode::ToZero).unwrap();
+        let state = ActusState {
+            status_date,
+            contract_performance: ContractPerformance::Performant,
+            nominal_value: Decimal::from(1_000),
+            accrued_interest,
+            fee_accrued: Decimal::ZERO,
+            nominal_interest_rate: dec!("0.05"),
+        };
+        assert_eq!(
+            state_json(&state, None),
+            format!(
+                "{{\"statusDate\":\"2023-07-01T00:00:00\",\"contractPerformance\":\"PF\",\"notionalPrincipal\":\"1000\",\
+                 \"accruedInterest\":\"{}\",\"feeAccrued\":\"0\",\"nominalInterestRate\":\"0.05\"}}",
+                accrued_interest
+            )
+        );
+        assert!(accrued_interest > Decimal::ZERO && accrued_interest < Decimal::from(5));
+    }
+}
@@ -0,0 +1,213 @@
+use scrypto::prelude::*;
+use crate::{ensure, invalid_terms, to_utc_date_time, SECONDS_PER_DAY};
+
+// This module holds the business-day calendar: which days are business days and how
+// due dates, interest payment dates and grace-period ends are shifted off the others
+// under the ACTUS business-day conventions. Like `core_math`, it is free of ledger state.
+
+/// Returns the midnight UTC starting the day that contains `timestamp`.
+pub(crate) fn day_boundary(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(SECONDS_PER_DAY)
+}
+
+/// How a date falling on a weekend or holiday is shifted to a business day.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BusinessDayConvention {
+    #[default]
+    Unadjusted,                        // Keep the date as it is
+    Following,                         // Move to the next business day
+    ModifiedFollowing,                 // Move to the next business day, unless that is in the next month
+    Preceding,                         // Move to the previous business day
+}
+
+/// A simple business-day calendar: weekends plus a list of holidays.
+#[derive(ScryptoSbor, Clone, Debug, Default)]
+pub struct BusinessDayCalendar {
+    pub convention: BusinessDayConvention, // How due dates, interest payment dates and grace-period ends are shifted off non-business days
+    pub holidays: Vec<i64>,            // Holidays as Unix timestamps of their midnight UTC, ascending
+    pub accrue_business_days_only: bool, // Whether interest and the grace period only run on business days
+}
+
+impl BusinessDayCalendar {
+    /// Checks that the holidays are ascending day boundaries.
+    pub(crate) fn validate(&self) {
+        for (index, holiday) in self.holidays.iter().enumerate() {
+            ensure(*holiday >= 0 && holiday % SECONDS_PER_DAY == 0, invalid_terms("holidays must be midnight UTC timestamps"));
+            ensure(index == 0 || self.holidays[index - 1] < *holiday, invalid_terms("holidays must be ascending"));
+        }
+    }
+
+    /// Whether the day containing `timestamp` is neither a weekend nor a holiday.
+    pub(crate) fn is_business_day(&self, timestamp: i64) -> bool {
+        let day = timestamp.div_euclid(SECONDS_PER_DAY);
+        // 1970-01-01 was a Thursday; 0 is Monday
+        let weekday = (day + 3).rem_euclid(7);
+        weekday < 5 && self.holidays.binary_search(&(day * SECONDS_PER_DAY)).is_err()
+    }
+
+    /// Shifts a date off non-business days according to the convention, keeping the time of day.
+    pub(crate) fn adjust(&self, timestamp: i64) -> i64 {
+        let following = |mut date: i64| {
+            while !self.is_business_day(date) {
+                date += SECONDS_PER_DAY;
+            }
+            date
+        };
+        let preceding = |mut date: i64| {
+            while !self.is_business_day(date) {
+                date -= SECONDS_PER_DAY;
+            }
+            date
+        };
+        match self.convention {
+            BusinessDayConvention::Unadjusted => timestamp,
+            BusinessDayConvention::Following => following(timestamp),
+            BusinessDayConvention::Preceding => preceding(timestamp),
+            BusinessDayConvention::ModifiedFollowing => {
+                let shifted = following(timestamp);
+                let month = |date: i64| to_utc_date_time(Instant::new(date)).month();
+                if month(shifted) == month(timestamp) { shifted } else { preceding(timestamp) }
+            }
+        }
+    }
+
+    /// The business day a notice period given at `notice_given` runs out on.
+    pub(crate) fn notice_due_date(&self, notice_given: i64, notice_period: i64) -> i64 {
+        self.adjust(notice_given + notice_period)
+    }
+
+    /// Counts the seconds between two dates that fall on business days.
+    pub(crate) fn business_seconds_between(&self, from: i64, to: i64) -> i64 {
+        let mut total = 0;
+        let mut day_start = day_boundary(from);
+        while day_start < to {
+            let day_end = day_start + SECONDS_PER_DAY;
+            if self.is_business_day(day_start) {
+                total += day_end.min(to) - day_start.max(from);
+            }
+            day_start = day_end;
+        }
+        total
+    }
+
+    /// Advances a date by a number of seconds counted on business days only.
+    pub(crate) fn add_business_seconds(&self, from: i64, seconds: i64) -> i64 {
+        let mut date = from;
+        let mut remaining = seconds;
+        while remaining > 0 {
+            let day_end = (date.div_euclid(SECONDS_PER_DAY) + 1) * SECONDS_PER_DAY;
+            if self.is_business_day(date) {
+                if remaining <= day_end - date {
+                    return date + remaining;
+                }
+                remaining -= day_end - date;
+            }
+            date = day_end;
+        }
+        date
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRI_2023_09_29: i64 = 1_695_945_600;
+    const SAT_2023_09_30: i64 = 1_696_032_000;
+    const SUN_2023_10_01: i64 = 1_696_118_400;
+    const MON_2023_10_02: i64 = 1_696_204_800;
+    const FRI_2023_12_22: i64 = 1_703_203_200;
+    const SAT_2023_12_23: i64 = 1_703_289_600;
+    const MON_2023_12_25: i64 = 1_703_462_400;
+    const TUE_2023_12_26: i64 = 1_703_548_800;
+
+    fn calendar(convention: BusinessDayConvention, holidays: Vec<i64>) -> BusinessDayCalendar {
+        BusinessDayCalendar { convention, holidays, accrue_business_days_only: false }
+    }
+
+    #[test]
+    fn day_boundaries_are_midnight_utc() {
+        assert_eq!(day_boundary(FRI_2023_09_29), FRI_2023_09_29);
+        assert_eq!(day_boundary(FRI_2023_09_29 + 1), FRI_2023_09_29);
+        assert_eq!(day_boundary(SAT_2023_09_30 - 1), FRI_2023_09_29);
+        assert_eq!(day_boundary(SAT_2023_09_30), SAT_2023_09_30);
+        assert_eq!(day_boundary(0), 0);
+    }
+
+    #[test]
+    fn weekends_and_holidays_are_not_business_days() {
+        let calendar = calendar(BusinessDayConvention::Following, vec![MON_2023_12_25]);
+        assert!(calendar.is_business_day(FRI_2023_09_29));
+        assert!(!calendar.is_business_day(SAT_2023_09_30));
+        assert!(!calendar.is_business_day(SUN_2023_10_01 + 43_200));
+        assert!(calendar.is_business_day(MON_2023_10_02));
+        assert!(!calendar.is_business_day(MON_2023_12_25 + 3_600));
+        assert!(calendar.is_business_day(TUE_2023_12_26));
+    }
+
+    #[test]
+    fn following_and_modified_following_differ_across_a_month_end() {
+        let noon = SECONDS_PER_DAY / 2;
+        // Saturday 30 September: the next business day is in October
+        assert_eq!(calendar(BusinessDayConvention::Following, vec![]).adjust(SAT_2023_09_30 + noon), MON_2023_10_02 + noon);
+        assert_eq!(
+            calendar(BusinessDayConvention::ModifiedFollowing, vec![]).adjust(SAT_2023_09_30 + noon),
+            FRI_2023_09_29 + noon,
+        );
+        // Sunday 1 October is already in October, so both move forward
+        assert_eq!(calendar(BusinessDayConvention::ModifiedFollowing, vec![]).adjust(SUN_2023_10_01), MON_2023_10_02);
+        assert_eq!(calendar(BusinessDayConvention::Unadjusted, vec![]).adjust(SAT_2023_09_30), SAT_2023_09_30);
+    }
+
+    #[test]
+    fn preceding_moves_back_to_the_last_business_day() {
+        let calendar = calendar(BusinessDayConvention::Preceding, vec![]);
+        assert_eq!(calendar.adjust(SUN_2023_10_01), FRI_2023_09_29);
+        assert_eq!(calendar.adjust(MON_2023_10_02), MON_2023_10_02);
+    }
+
+    #[test]
+    fn a_holiday_next_to_a_weekend_extends_the_shift() {
+        let following = calendar(BusinessDayConvention::Following, vec![MON_2023_12_25]);
+        assert_eq!(following.adjust(SAT_2023_12_23), TUE_2023_12_26);
+        assert_eq!(following.adjust(MON_2023_12_25), TUE_2023_12_26);
+        let preceding = calendar(BusinessDayConvention::Preceding, vec![MON_2023_12_25]);
+        assert_eq!(preceding.adjust(MON_2023_12_25), FRI_2023_12_22);
+    }
+
+    #[test]
+    fn business_seconds_skip_weekends_and_holidays() {
+        let calendar = calendar(BusinessDayConvention::Following, vec![MON_2023_12_25]);
+        // Friday noon to Tuesday noon: half of Friday and half of Tuesday
+        let noon = SECONDS_PER_DAY / 2;
+        assert_eq!(calendar.business_seconds_between(FRI_2023_12_22 + noon, TUE_2023_12_26 + noon), SECONDS_PER_DAY);
+        assert_eq!(calendar.add_business_seconds(FRI_2023_12_22 + noon, SECONDS_PER_DAY), TUE_2023_12_26 + noon);
+        assert_eq!(calendar.add_business_seconds(SAT_2023_12_23, 3_600), TUE_2023_12_26 + 3_600);
+    }
+
+    #[test]
+    #[should_panic]
+    fn holidays_must_be_ascending() {
+        calendar(BusinessDayConvention::Following, vec![MON_2023_12_25, FRI_2023_12_22]).validate();
+    }
+
+    #[test]
+    #[should_panic]
+    fn holidays_must_be_day_boundaries() {
+        calendar(BusinessDayConvention::Following, vec![MON_2023_12_25 + 1]).validate();
+    }
+
+    #[test]
+    fn notice_runs_from_the_call_or_the_acknowledgment() {
+        let calendar = calendar(BusinessDayConvention::Following, vec![]);
+        let notice_period = 2 * SECONDS_PER_DAY;
+        let called_at = FRI_2023_09_29 - SECONDS_PER_DAY;
+        // From the call, Thursday's two-day notice runs out on Saturday and moves to Monday,
+        // however late the borrower acknowledges it
+        assert_eq!(calendar.notice_due_date(called_at, notice_period), MON_2023_10_02);
+        // From an acknowledgment on Friday, before the nominal due date, it runs out on Monday too
+        assert_eq!(calendar.notice_due_date(FRI_2023_09_29, notice_period), MON_2023_10_02);
+        // From an acknowledgment on Tuesday, after the nominal due date, it runs out on Thursday
+        assert_eq!(calendar.notice_due_date(MON_2023_10_02 + SECONDS_PER_DAY, notice_period), MON_2023_10_02 + 3 * SECONDS_PER_DAY);
+    }
+}
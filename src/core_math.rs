@@ -0,0 +1,923 @@
+use scrypto::prelude::*;
+use crate::{ContractEvent, DayCountConvention, LedgerEntry, PrepaymentFee};
+
+// This module holds the contract's pure arithmetic: accrual, the payment waterfall
+// and ledger replay. It lives outside the blueprint so it can be exercised without
+// a ledger.
+
+/// How a payment is split across the outstanding balances.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    pub penalties_paid: Decimal,       // Applied to accrued penalties
+    pub fees_paid: Decimal,            // Applied to accrued fees
+    pub interest_paid: Decimal,        // Applied to accrued interest
+    pub principal_paid: Decimal,       // Applied to principal
+    pub excess: Decimal,               // Left over once everything is paid
+}
+
+/// The balances of a contract, as re-derived from its ledger.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Balances {
+    pub principal: Decimal,            // Outstanding principal
+    pub accrued_interest: Decimal,     // Interest accrued but not yet paid
+    pub accrued_fees: Decimal,         // Fees charged but not yet paid
+    pub accrued_penalties: Decimal,    // Penalties accrued but not yet paid
+}
+
+/// Number of seconds in a day.
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Calculates simple interest on a balance at an annual rate over a year fraction.
+/// The result is kept at full precision; callers round when they book it.
+pub fn simple_interest(balance: Decimal, rate: Decimal, year_fraction: PreciseDecimal) -> PreciseDecimal {
+    PreciseDecimal::from(balance) * PreciseDecimal::from(rate) * year_fraction
+}
+
+/// Calculates the simple interest on a principal at an annual rate between two Unix
+/// timestamps under a day count convention, at full precision.
+pub fn compute_interest(principal: Decimal, rate: Decimal, from: i64, to: i64, convention: DayCountConvention) -> PreciseDecimal {
+    simple_interest(principal, rate, year_fraction(from, to, convention))
+}
+
+/// Converts the period between two Unix timestamps into a year fraction under a day
+/// count convention. The period is measured to the second, in UTC, so accrual never
+/// drops the part of a day between two calls.
+pub fn year_fraction(start: i64, end: i64, convention: DayCountConvention) -> PreciseDecimal {
+    match convention {
+        DayCountConvention::Actual360 | DayCountConvention::Actual365 => {
+            PreciseDecimal::from(end - start) / PreciseDecimal::from(convention.basis() * SECONDS_PER_DAY)
+        }
+        DayCountConvention::ThirtyE360 => {
+            // Whole days follow the 30E/360 calendar; the time of day counts as a fraction of a day.
+            // The 30th and 31st are the same day, so in a 31-day month time only runs on the 31st.
+            let thirty_e = |timestamp: i64| {
+                let days = timestamp.div_euclid(SECONDS_PER_DAY);
+                let (year, month, day) = civil_from_days(days);
+                let time_of_day = if day == 30 && civil_from_days(days + 1).2 == 31 {
+                    0
+                } else {
+                    timestamp.rem_euclid(SECONDS_PER_DAY)
+                };
+                (360 * year + 30 * month + day.min(30)) * SECONDS_PER_DAY + time_of_day
+            };
+            PreciseDecimal::from(thirty_e(end) - thirty_e(start)) / PreciseDecimal::from(360 * SECONDS_PER_DAY)
+        }
+        DayCountConvention::ActualActual => {
+            // Each calendar year's seconds count over that year's length
+            let mut fraction = PreciseDecimal::ZERO;
+            let mut period_start = start;
+            while period_start < end {
+                let (year, _, _) = civil_from_days(period_start.div_euclid(SECONDS_PER_DAY));
+                let next_year = days_from_civil(year + 1, 1, 1) * SECONDS_PER_DAY;
+                let period_end = end.min(next_year);
+                let year_length = next_year - days_from_civil(year, 1, 1) * SECONDS_PER_DAY;
+                fraction += PreciseDecimal::from(period_end - period_start) / PreciseDecimal::from(year_length);
+                period_start = period_end;
+            }
+            fraction
+        }
+    }
+}
+
+/// Converts days since the Unix epoch into a (year, month, day) civil date.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Converts a (year, month, day) civil date into days since the Unix epoch.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns what may still be charged under a cap once `charged` has been, never less than zero.
+pub fn headroom(cap: Decimal, charged: Decimal) -> Decimal {
+    (cap - charged).max(Decimal::ZERO)
+}
+
+/// Returns the tighter of two headrooms, or None when neither cap applies.
+pub fn tighter_headroom(first: Option<Decimal>, second: Option<Decimal>) -> Option<Decimal> {
+    match (first, second) {
+        (Some(first), Some(second)) => Some(first.min(second)),
+        (first, second) => first.or(second),
+    }
+}
+
+/// Splits a period into the sub-periods each rate in a rate history was in effect for,
+/// each rate applying from its effective date until the next one takes effect.
+///
+/// # Returns
+/// A vector of non-empty (period_start, period_end, rate) rows, in date order
+pub fn rate_periods(history: &[(Instant, Decimal)], from: i64, to: i64) -> Vec<(i64, i64, Decimal)> {
+    let mut periods = Vec::new();
+    for (index, (effective_date, rate)) in history.iter().enumerate() {
+        let period_start = effective_date.seconds_since_unix_epoch.max(from);
+        let period_end = history.get(index + 1).map_or(to, |(next_date, _)| next_date.seconds_since_unix_epoch.min(to));
+        if period_end > period_start {
+            periods.push((period_start, period_end, *rate));
+        }
+    }
+    periods
+}
+
+/// Splits a period at each scheduled rate step within it: `rate` applies up to the first
+/// step, each scheduled rate up to the next.
+///
+/// # Returns
+/// A vector of (period_start, period_end, rate) rows covering the whole period, in date order
+pub fn scheduled_periods(rate: Decimal, schedule: &[(Instant, Decimal)], from: i64, to: i64) -> Vec<(i64, i64, Decimal)> {
+    let mut periods = Vec::new();
+    let (mut rate, mut period_start) = (rate, from);
+    for (effective_date, scheduled_rate) in schedule {
+        let effective_date = effective_date.seconds_since_unix_epoch;
+        if effective_date <= from {
+            continue;
+        }
+        if effective_date >= to {
+            break;
+        }
+        periods.push((period_start, effective_date, rate));
+        period_start = effective_date;
+        rate = *scheduled_rate;
+    }
+    periods.push((period_start, to, rate));
+    periods
+}
+
+/// Limits a charge to the remaining headroom, if any cap applies.
+pub fn clamp_to_headroom(charge: PreciseDecimal, headroom: Option<Decimal>) -> PreciseDecimal {
+    headroom.map_or(charge, |headroom| charge.min(PreciseDecimal::from(headroom)))
+}
+
+/// Works out how much of the time since a deadline is penalized. The deadline is pushed
+/// back by `excluded_seconds` spent in dispute or paused, and charging resumes from
+/// `penalized_until` when penalties were already charged past it.
+///
+/// # Returns
+/// The (overdue_from, charge_from) pair, or None while nothing new is overdue at `current_date`
+pub fn penalty_period(deadline: i64, excluded_seconds: i64, penalized_until: Option<i64>, current_date: i64) -> Option<(i64, i64)> {
+    let overdue_from = deadline + excluded_seconds;
+    let charge_from = penalized_until.map_or(overdue_from, |until| until.max(overdue_from));
+    (current_date > charge_from).then_some((overdue_from, charge_from))
+}
+
+/// Picks the penalty rate for the bracket `days_overdue` falls into, from
+/// (days_overdue_threshold, rate) tiers in ascending order. Below the first
+/// threshold, or without tiers, the flat rate applies.
+pub fn tiered_rate(tiers: &[(i64, Decimal)], flat_rate: Decimal, days_overdue: i64) -> Decimal {
+    tiers
+        .iter()
+        .rev()
+        .find(|(threshold, _)| days_overdue >= *threshold)
+        .map_or(flat_rate, |(_, rate)| *rate)
+}
+
+/// Returns the share of `amount` that `part` makes up of `whole`.
+pub fn pro_rata(amount: Decimal, part: Decimal, whole: Decimal) -> Decimal {
+    amount * part / whole
+}
+
+/// Takes a prepayment fee out of what a payment has left for principal. Less principal
+/// is repaid where the fee would otherwise not be covered, so principal, fee and excess
+/// always add up to what was left; a payment too small to cover an absolute fee repays
+/// no principal and is returned.
+///
+/// # Returns
+/// The (principal_paid, fee, excess) split
+pub fn split_prepayment_fee(principal_paid: Decimal, excess: Decimal, fee: PrepaymentFee) -> (Decimal, Decimal, Decimal) {
+    let available = principal_paid + excess;
+    let (principal_paid, fee) = match fee {
+        PrepaymentFee::Absolute(amount) if available <= amount => (Decimal::ZERO, Decimal::ZERO),
+        PrepaymentFee::Absolute(amount) => (principal_paid.min(available - amount), amount),
+        PrepaymentFee::Relative(rate) => {
+            let principal_paid = principal_paid.min(available / (Decimal::ONE + rate));
+            (principal_paid, (principal_paid * rate).min(available - principal_paid))
+        }
+    };
+    (principal_paid, fee, available - principal_paid - fee)
+}
+
+/// Splits an interest-only payment into the interest it settles and the overage handed
+/// back, so servicing interest never reduces principal.
+///
+/// # Returns
+/// The (interest_paid, returned) split
+pub fn split_interest_payment(amount: Decimal, interest_due: Decimal) -> (Decimal, Decimal) {
+    let interest_paid = amount.min(interest_due);
+    (interest_paid, amount - interest_paid)
+}
+
+/// Works out the interest a settlement quote forgives: the gap between the amount due and
+/// the quote, but no more than the interest accrued since the quote was given nor the
+/// interest outstanding, so fees or penalties charged since are still owed.
+pub fn quote_forgiveness(total_due: Decimal, quoted: Decimal, interest_since_quote: Decimal, interest_due: Decimal) -> Decimal {
+    (total_due - quoted).min(interest_since_quote).min(interest_due).max(Decimal::ZERO)
+}
+
+/// Splits the credit a negative rate gives the borrower into the part that reduces
+/// accrued interest and the part that reduces principal, which it never exceeds.
+///
+/// # Returns
+/// The (interest_reduction, principal_reduction) split
+pub fn split_negative_interest(credit: Decimal, accrued_interest: Decimal, principal: Decimal) -> (Decimal, Decimal) {
+    let interest_reduction = credit.min(accrued_interest);
+    (interest_reduction, (credit - interest_reduction).min(principal))
+}
+
+/// Splits a payment in a settlement resource, converted at `conversion_rate` units of
+/// the denomination per unit, into the part kept and the part handed back, given the
+/// excess of the converted amount over what it settled.
+///
+/// # Returns
+/// The (kept, returned) split, in the settlement resource
+pub fn split_settlement(payment: Decimal, conversion_rate: Decimal, excess: Decimal) -> (Decimal, Decimal) {
+    let returned = excess / conversion_rate;
+    (payment - returned, returned)
+}
+
+/// Splits a payment across the balances: penalties first, then fees, then interest, then principal.
+pub fn allocate_payment(
+    amount: Decimal,
+    accrued_penalties: Decimal,
+    accrued_fees: Decimal,
+    accrued_interest: Decimal,
+    principal: Decimal,
+) -> Allocation {
+    let mut remaining = amount;
+    let penalties_paid = remaining.min(accrued_penalties);
+    remaining -= penalties_paid;
+    let fees_paid = remaining.min(accrued_fees);
+    remaining -= fees_paid;
+    let interest_paid = remaining.min(accrued_interest);
+    remaining -= interest_paid;
+    let principal_paid = remaining.min(principal);
+
+    Allocation {
+        penalties_paid,
+        fees_paid,
+        interest_paid,
+        principal_paid,
+        excess: remaining - principal_paid,
+    }
+}
+
+impl Balances {
+    /// Zero balances, before the initiation entry.
+    pub fn zero() -> Self {
+        Balances {
+            principal: Decimal::ZERO,
+            accrued_interest: Decimal::ZERO,
+            accrued_fees: Decimal::ZERO,
+            accrued_penalties: Decimal::ZERO,
+        }
+    }
+
+    /// Everything outstanding: principal, interest, fees and penalties.
+    pub fn total(&self) -> Decimal {
+        self.principal + self.accrued_interest + self.accrued_fees + self.accrued_penalties
+    }
+
+    /// Whether every balance is within `tolerance` of the other's.
+    pub fn matches(&self, other: &Balances, tolerance: Decimal) -> bool {
+        let within_tolerance = |a: Decimal, b: Decimal| (a - b).checked_abs().unwrap() <= tolerance;
+        within_tolerance(self.principal, other.principal)
+            && within_tolerance(self.accrued_interest, other.accrued_interest)
+            && within_tolerance(self.accrued_fees, other.accrued_fees)
+            && within_tolerance(self.accrued_penalties, other.accrued_penalties)
+    }
+
+    /// Folds one ledger entry into the balances, so they can be re-derived entry by
+    /// entry as the ledger grows, and returns the typed event for it.
+    ///
+    /// # Arguments
+    /// * `id` - The sequence the entry is recorded under
+    /// * `entry` - The ledger entry to apply
+    ///
+    /// # Returns
+    /// The entry as a contract event, carrying the total outstanding after it
+    pub fn apply(&mut self, id: u64, entry: &LedgerEntry) -> ContractEvent {
+        apply_entry(self, entry);
+        let (kind, amount) = entry.kind_and_amount();
+        ContractEvent {
+            id,
+            timestamp: entry.timestamp().seconds_since_unix_epoch,
+            kind,
+            amount,
+            balance_after: self.total(),
+        }
+    }
+}
+
+/// Applies one ledger entry to the balances.
+fn apply_entry(balances: &mut Balances, entry: &LedgerEntry) {
+    match entry {
+        LedgerEntry::Initiated { principal, accrued_interest, .. } => {
+            balances.principal = *principal;
+            balances.accrued_interest = *accrued_interest;
+            balances.accrued_fees = Decimal::ZERO;
+            balances.accrued_penalties = Decimal::ZERO;
+        }
+        LedgerEntry::InterestAccrued { amount, .. } => balances.accrued_interest += *amount,
+        LedgerEntry::NegativeInterest { interest_reduction, principal_reduction, .. } => {
+            balances.accrued_interest -= *interest_reduction;
+            balances.principal -= *principal_reduction;
+        }
+        LedgerEntry::PenaltyApplied { amount, .. } => balances.accrued_penalties += *amount,
+        LedgerEntry::Payment { penalties_paid, fees_paid, interest_paid, principal_paid, .. } => {
+            balances.accrued_penalties -= *penalties_paid;
+            balances.accrued_fees -= *fees_paid;
+            balances.accrued_interest -= *interest_paid;
+            balances.principal -= *principal_paid;
+        }
+        LedgerEntry::CommitmentFeeAccrued { amount, .. } => balances.accrued_interest += *amount,
+        LedgerEntry::FeeAccrued { amount, .. } => balances.accrued_fees += *amount,
+        LedgerEntry::Drawdown { amount, .. } => balances.principal += *amount,
+        LedgerEntry::InterestForgiven { amount, .. } => balances.accrued_interest -= *amount,
+        LedgerEntry::ReserveDrawn { amount, .. } => balances.accrued_interest -= *amount,
+        LedgerEntry::InterestCapitalized { amount, .. } => {
+            balances.accrued_interest -= *amount;
+            balances.principal += *amount;
+        }
+        LedgerEntry::SplitOff { principal, interest, .. } => {
+            balances.principal -= *principal;
+            balances.accrued_interest -= *interest;
+        }
+        LedgerEntry::WrittenOff { penalties, fees, interest, principal, .. } => {
+            balances.accrued_penalties -= *penalties;
+            balances.accrued_fees -= *fees;
+            balances.accrued_interest -= *interest;
+            balances.principal -= *principal;
+        }
+    }
+}
+
+/// Averages the rates of a set of periods, each weighted by its length.
+///
+/// # Returns
+/// The time-weighted rate, or None when the periods cover no time
+pub fn time_weighted_rate(periods: &[(i64, i64, Decimal)]) -> Option<Decimal> {
+    let mut weighted = Decimal::ZERO;
+    let mut total_seconds: i64 = 0;
+    for (period_start, period_end, rate) in periods {
+        let seconds = period_end - period_start;
+        weighted += *rate * Decimal::from(seconds);
+        total_seconds += seconds;
+    }
+    (total_seconds > 0).then(|| weighted / Decimal::from(total_seconds))
+}
+
+/// Annualizes a charge on a balance over a year fraction: the inverse of
+/// `simple_interest`, so a balance accruing at rate r yields exactly r.
+pub fn annualized_yield(charged: Decimal, balance: Decimal, year_fraction: PreciseDecimal) -> Decimal {
+    if balance == Decimal::ZERO || year_fraction <= PreciseDecimal::ZERO {
+        return Decimal::ZERO;
+    }
+    (PreciseDecimal::from(charged) / (PreciseDecimal::from(balance) * year_fraction))
+        .checked_truncate(RoundingMode::ToZero)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small seeded generator, so every run exercises the same sequences.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            // xorshift64*
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            self.next() % bound
+        }
+
+        /// An amount between 0 and `bound`, in cents.
+        fn amount(&mut self, bound: u64) -> Decimal {
+            Decimal::from(self.below(bound * 100 + 1)) / Decimal::from(100)
+        }
+    }
+
+    const SEEDS: [u64; 4] = [1, 42, 2024, 0x9e37_79b9_7f4a_7c15];
+    const CONVENTIONS: [DayCountConvention; 4] = [
+        DayCountConvention::ActualActual,
+        DayCountConvention::Actual360,
+        DayCountConvention::Actual365,
+        DayCountConvention::ThirtyE360,
+    ];
+
+    fn round(interest: PreciseDecimal) -> Decimal {
+        interest.checked_truncate(RoundingMode::ToZero).unwrap()
+    }
+
+    #[test]
+    fn allocate_payment_conserves_the_amount_and_respects_the_order() {
+        for seed in SEEDS {
+            let mut rng = Rng(seed);
+            for _ in 0..500 {
+                let amount = rng.amount(2_000);
+                let balances = [rng.amount(100), rng.amount(100), rng.amount(500), rng.amount(1_000)];
+                let allocation = allocate_payment(amount, balances[0], balances[1], balances[2], balances[3]);
+                let paid = [
+                    allocation.penalties_paid,
+                    allocation.fees_paid,
+                    allocation.interest_paid,
+                    allocation.principal_paid,
+                ];
+
+                assert_eq!(paid.iter().fold(allocation.excess, |total, part| total + *part), amount);
+                assert!(allocation.excess >= Decimal::ZERO);
+                for (index, (part, balance)) in paid.iter().zip(balances).enumerate() {
+                    assert!(*part >= Decimal::ZERO && *part <= balance);
+                    // A balance is only paid once everything ahead of it is settled
+                    if *part > Decimal::ZERO {
+                        assert!(paid[..index].iter().zip(balances).all(|(earlier, owed)| *earlier == owed));
+                    }
+                }
+                if allocation.excess > Decimal::ZERO {
+                    assert_eq!(paid, balances);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn interest_is_monotonic_in_elapsed_time() {
+        for seed in SEEDS {
+            let mut rng = Rng(seed);
+            for _ in 0..200 {
+                let principal = rng.amount(1_000_000);
+                let rate = Decimal::from(rng.below(5_000)) / Decimal::from(10_000);
+                let from = 1_600_000_000 + rng.below(400 * SECONDS_PER_DAY as u64) as i64;
+                let mut previous = [PreciseDecimal::ZERO; 4];
+                let mut to = from;
+                for _ in 0..40 {
+                    to += rng.below(10 * SECONDS_PER_DAY as u64) as i64;
+                    for (convention, previous) in CONVENTIONS.iter().zip(previous.iter_mut()) {
+                        let interest = compute_interest(principal, rate, from, to, *convention);
+                        assert!(interest >= *previous, "{:?} interest fell from {} to {}", convention, previous, interest);
+                        *previous = interest;
+                    }
+                }
+            }
+        }
+    }
+
+    fn date(year: i64, month: i64, day: i64) -> i64 {
+        days_from_civil(year, month, day) * SECONDS_PER_DAY
+    }
+
+    fn fraction(numerator: i64, denominator: i64) -> PreciseDecimal {
+        PreciseDecimal::from(numerator) / PreciseDecimal::from(denominator)
+    }
+
+    #[test]
+    fn actual_conventions_count_every_second() {
+        let start = date(2023, 3, 1);
+        assert_eq!(year_fraction(start, start + 90 * SECONDS_PER_DAY, DayCountConvention::Actual360), fraction(1, 4));
+        assert_eq!(year_fraction(start, start + 73 * SECONDS_PER_DAY, DayCountConvention::Actual365), fraction(1, 5));
+        assert_eq!(year_fraction(start, start + 3_600, DayCountConvention::Actual365), fraction(3_600, 365 * SECONDS_PER_DAY));
+        assert_eq!(year_fraction(start, start + 3_600, DayCountConvention::Actual360), fraction(3_600, 360 * SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn thirty_e_360_counts_thirty_day_months() {
+        let convention = DayCountConvention::ThirtyE360;
+        assert_eq!(year_fraction(date(2023, 1, 15), date(2023, 7, 15), convention), fraction(1, 2));
+        // The 31st counts as the 30th
+        assert_eq!(year_fraction(date(2023, 1, 30), date(2023, 3, 31), convention), fraction(60, 360));
+        assert_eq!(year_fraction(date(2023, 1, 31), date(2023, 2, 28), convention), fraction(28, 360));
+        assert_eq!(year_fraction(date(2023, 2, 28), date(2023, 3, 1), convention), fraction(3, 360));
+        // Within a day the time still counts, but only once across the 30th and 31st
+        assert_eq!(
+            year_fraction(date(2023, 3, 1), date(2023, 3, 2) + SECONDS_PER_DAY / 2, convention),
+            fraction(3, 720),
+        );
+        assert_eq!(year_fraction(date(2023, 3, 30), date(2023, 3, 30) + 3_600, convention), PreciseDecimal::ZERO);
+        assert_eq!(year_fraction(date(2023, 3, 30), date(2023, 4, 1), convention), fraction(1, 360));
+    }
+
+    #[test]
+    fn actual_actual_splits_at_the_leap_year_boundary() {
+        let convention = DayCountConvention::ActualActual;
+        assert_eq!(year_fraction(date(2024, 1, 1), date(2025, 1, 1), convention), PreciseDecimal::ONE);
+        assert_eq!(year_fraction(date(2023, 1, 1), date(2024, 1, 1), convention), PreciseDecimal::ONE);
+        assert_eq!(
+            year_fraction(date(2023, 12, 31), date(2024, 1, 2), convention),
+            fraction(1, 365) + fraction(1, 366),
+        );
+        assert_eq!(
+            year_fraction(date(2024, 12, 31), date(2025, 1, 2), convention),
+            fraction(1, 366) + fraction(1, 365),
+        );
+        assert_eq!(
+            year_fraction(date(2024, 2, 28), date(2024, 3, 1), convention),
+            fraction(2, 366),
+        );
+    }
+
+    #[test]
+    fn intraday_periods_accrue_interest() {
+        let start = date(2023, 6, 1);
+        let rate = Decimal::from(5) / Decimal::from(100);
+        for convention in CONVENTIONS {
+            assert!(compute_interest(Decimal::from(1_000_000), rate, start, start + 60, convention) > PreciseDecimal::ZERO);
+        }
+    }
+
+    #[test]
+    fn settlement_payments_return_only_the_converted_excess() {
+        // 100 units at 2.5 settle 250 owed exactly
+        assert_eq!(split_settlement(Decimal::from(100), dec!("2.5"), Decimal::ZERO), (Decimal::from(100), Decimal::ZERO));
+        // 100 units at 2.5 against 200 owed: the 50 excess is handed back as 20 units
+        let (kept, returned) = split_settlement(Decimal::from(100), dec!("2.5"), Decimal::from(50));
+        assert_eq!((kept, returned), (Decimal::from(80), Decimal::from(20)));
+        assert_eq!(kept * dec!("2.5"), Decimal::from(200));
+        // A rate below one converts the excess into more settlement units
+        assert_eq!(split_settlement(Decimal::from(100), dec!("0.5"), Decimal::from(10)), (Decimal::from(80), Decimal::from(20)));
+    }
+
+    #[test]
+    fn a_dispute_interval_is_excluded_from_penalties() {
+        let deadline = date(2023, 6, 1);
+        let disputed = 3 * 86_400;
+        // Still inside the disputed interval: nothing is overdue yet
+        assert_eq!(penalty_period(deadline, disputed, None, deadline + 86_400), None);
+        assert_eq!(penalty_period(deadline, disputed, None, deadline + disputed), None);
+        // Penalties resume once the disputed time is made up, counted from the shifted deadline
+        let now = deadline + 10 * 86_400;
+        assert_eq!(penalty_period(deadline, disputed, None, now), Some((deadline + disputed, deadline + disputed)));
+        let rate = Decimal::from(10) / Decimal::from(100);
+        let (_, charge_from) = penalty_period(deadline, disputed, None, now).unwrap();
+        assert_eq!(
+            compute_interest(Decimal::from(1_000), rate, charge_from, now, DayCountConvention::Actual365),
+            compute_interest(Decimal::from(1_000), rate, deadline, now - disputed, DayCountConvention::Actual365),
+        );
+    }
+
+    #[test]
+    fn penalties_resume_from_the_last_charge() {
+        let deadline = date(2023, 6, 1);
+        let charged = deadline + 5 * 86_400;
+        assert_eq!(penalty_period(deadline, 0, Some(charged), deadline + 7 * 86_400), Some((deadline, charged)));
+        assert_eq!(penalty_period(deadline, 0, Some(charged), charged), None);
+        // A charge recorded before a later dispute does not bring the deadline forward
+        let resumed = deadline + 6 * 86_400;
+        assert_eq!(penalty_period(deadline, 6 * 86_400, Some(charged), deadline + 7 * 86_400), Some((resumed, resumed)));
+    }
+
+    #[test]
+    fn the_tighter_cap_applies() {
+        assert_eq!(headroom(Decimal::from(50), Decimal::from(20)), Decimal::from(30));
+        assert_eq!(headroom(Decimal::from(50), Decimal::from(70)), Decimal::ZERO);
+        assert_eq!(tighter_headroom(Some(Decimal::from(30)), Some(Decimal::from(10))), Some(Decimal::from(10)));
+        assert_eq!(tighter_headroom(None, Some(Decimal::from(10))), Some(Decimal::from(10)));
+        assert_eq!(tighter_headroom(Some(Decimal::from(30)), None), Some(Decimal::from(30)));
+        assert_eq!(tighter_headroom(None, None), None);
+    }
+
+    #[test]
+    fn penalties_plateau_at_the_caps() {
+        // Penalties at 10% a year on 1,000 charged daily for two years, capped at 5% of
+        // principal, with a total-cost cap of 100 of which interest already used 40
+        let principal = Decimal::from(1_000);
+        let rate = Decimal::from(10) / Decimal::from(100);
+        let penalty_cap = principal * Decimal::from(5) / Decimal::from(100);
+        let interest = Decimal::from(40);
+        let due_date = date(2023, 6, 1);
+        let penalties_under = |total_cost_cap: Decimal| {
+            let mut penalties = Decimal::ZERO;
+            for day in 0..730 {
+                let from = due_date + day * 86_400;
+                let charge = compute_interest(principal, rate, from, from + 86_400, DayCountConvention::Actual365);
+                let room = tighter_headroom(Some(headroom(penalty_cap, penalties)), Some(headroom(total_cost_cap, interest + penalties)));
+                penalties += round(clamp_to_headroom(charge, room));
+            }
+            penalties
+        };
+        assert_eq!(penalties_under(Decimal::from(100)), penalty_cap);
+        // A tighter total-cost cap stops penalties first
+        assert_eq!(interest + penalties_under(Decimal::from(60)), Decimal::from(60));
+    }
+
+    #[test]
+    fn splitting_conserves_principal_and_interest() {
+        for seed in SEEDS {
+            let mut rng = Rng(seed);
+            for _ in 0..200 {
+                let principal = rng.amount(1_000_000) + Decimal::ONE;
+                let accrued_interest = rng.amount(10_000);
+                let moved = principal * Decimal::from(rng.below(999) + 1) / Decimal::from(1_000);
+                let interest_moved = pro_rata(accrued_interest, moved, principal);
+                let timestamp = Instant::new(1_700_000_000);
+
+                let mut original = Balances::zero();
+                original.apply(0, &LedgerEntry::Initiated { timestamp, principal, accrued_interest });
+                original.apply(1, &LedgerEntry::SplitOff { timestamp, principal: moved, interest: interest_moved });
+                let mut carved_out = Balances::zero();
+                carved_out.apply(0, &LedgerEntry::Initiated { timestamp, principal: moved, accrued_interest: interest_moved });
+
+                assert_eq!(original.principal + carved_out.principal, principal);
+                assert_eq!(original.accrued_interest + carved_out.accrued_interest, accrued_interest);
+                assert!(original.accrued_interest >= Decimal::ZERO && interest_moved <= accrued_interest);
+            }
+        }
+    }
+
+    #[test]
+    fn a_rate_change_splits_the_period() {
+        let (five, six) = (Decimal::from(5) / Decimal::from(100), Decimal::from(6) / Decimal::from(100));
+        let history = [(Instant::new(date(2023, 1, 1)), five), (Instant::new(date(2023, 3, 1)), six)];
+        // Straddling the change gives one row per rate
+        assert_eq!(
+            rate_periods(&history, date(2023, 2, 1), date(2023, 4, 1)),
+            vec![(date(2023, 2, 1), date(2023, 3, 1), five), (date(2023, 3, 1), date(2023, 4, 1), six)]
+        );
+        // A period within one rate gives a single row, and an empty period none
+        assert_eq!(rate_periods(&history, date(2023, 3, 5), date(2023, 3, 6)).len(), 1);
+        assert!(rate_periods(&history, date(2023, 3, 5), date(2023, 3, 5)).is_empty());
+        // Nothing accrues before the first rate took effect
+        assert_eq!(rate_periods(&history, date(2022, 12, 1), date(2023, 1, 2))[0].0, date(2023, 1, 1));
+    }
+
+    #[test]
+    fn every_entry_kind_replays_onto_its_balance() {
+        let timestamp = Instant::new(1_700_000_000);
+        let amount = Decimal::from(5);
+        let mut balances = Balances::zero();
+        balances.apply(0, &LedgerEntry::Initiated { timestamp, principal: Decimal::from(1_000), accrued_interest: Decimal::from(10) });
+        let mut expect = |entry: LedgerEntry, change: [i64; 4]| {
+            let before = balances;
+            balances.apply(1, &entry);
+            let change = change.map(|units| amount * Decimal::from(units));
+            assert_eq!(balances.principal - before.principal, change[0], "{:?}", entry);
+            assert_eq!(balances.accrued_interest - before.accrued_interest, change[1], "{:?}", entry);
+            assert_eq!(balances.accrued_fees - before.accrued_fees, change[2], "{:?}", entry);
+            assert_eq!(balances.accrued_penalties - before.accrued_penalties, change[3], "{:?}", entry);
+        };
+        let rate = Decimal::ZERO;
+        expect(LedgerEntry::InterestAccrued { timestamp, rate, amount }, [0, 1, 0, 0]);
+        expect(LedgerEntry::CommitmentFeeAccrued { timestamp, rate, amount }, [0, 1, 0, 0]);
+        expect(LedgerEntry::FeeAccrued { timestamp, amount }, [0, 0, 1, 0]);
+        expect(LedgerEntry::PenaltyApplied { timestamp, rate, amount }, [0, 0, 0, 1]);
+        expect(LedgerEntry::Drawdown { timestamp, amount }, [1, 0, 0, 0]);
+        expect(LedgerEntry::InterestCapitalized { timestamp, amount }, [1, -1, 0, 0]);
+        expect(LedgerEntry::InterestForgiven { timestamp, amount }, [0, -1, 0, 0]);
+        expect(LedgerEntry::ReserveDrawn { timestamp, amount }, [0, -1, 0, 0]);
+        expect(LedgerEntry::NegativeInterest { timestamp, rate, interest_reduction: amount, principal_reduction: amount }, [-1, -1, 0, 0]);
+        expect(LedgerEntry::SplitOff { timestamp, principal: amount, interest: amount }, [-1, -1, 0, 0]);
+        expect(
+            LedgerEntry::Payment {
+                timestamp,
+                penalties_paid: amount,
+                fees_paid: amount,
+                interest_paid: amount,
+                principal_paid: amount,
+                late: true,
+            },
+            [-1, -1, -1, -1],
+        );
+        expect(
+            LedgerEntry::WrittenOff { timestamp, penalties: Decimal::ZERO, fees: Decimal::ZERO, interest: amount, principal: amount },
+            [-1, -1, 0, 0],
+        );
+    }
+
+    #[test]
+    fn replayed_balances_match_within_the_tolerance() {
+        let tolerance = Decimal::from(1) / Decimal::from(1_000_000_000);
+        let stored = Balances {
+            principal: Decimal::from(1_000),
+            accrued_interest: Decimal::from(10),
+            accrued_fees: Decimal::from(2),
+            accrued_penalties: Decimal::from(1),
+        };
+        assert!(stored.matches(&stored, tolerance));
+        let noisy = Balances { accrued_interest: stored.accrued_interest + tolerance, ..stored };
+        assert!(noisy.matches(&stored, tolerance));
+        let drifted = Balances { accrued_penalties: stored.accrued_penalties - tolerance * Decimal::from(2), ..stored };
+        assert!(!drifted.matches(&stored, tolerance));
+        assert!(!stored.matches(&drifted, tolerance));
+    }
+
+    #[test]
+    fn the_interest_reserve_covers_accruals_until_it_runs_out() {
+        // 100,000 at 12% a year with 1,500 prefunded: a month's interest is about 986
+        let principal = Decimal::from(100_000);
+        let rate = Decimal::from(12) / Decimal::from(100);
+        let start = date(2023, 1, 1);
+        let mut reserve = Decimal::from(1_500);
+        let mut balances = Balances::zero();
+        balances.apply(0, &LedgerEntry::Initiated { timestamp: Instant::new(start), principal, accrued_interest: Decimal::ZERO });
+        let mut accrue_and_draw = |id: u64, from: i64, to: i64| {
+            let amount = round(compute_interest(principal, rate, from, to, DayCountConvention::Actual365));
+            balances.apply(id, &LedgerEntry::InterestAccrued { timestamp: Instant::new(to), rate, amount });
+            let drawn = balances.accrued_interest.min(reserve);
+            reserve -= drawn;
+            balances.apply(id + 1, &LedgerEntry::ReserveDrawn { timestamp: Instant::new(to), amount: drawn });
+            (amount, drawn, balances.accrued_interest)
+        };
+
+        // The first accrual is covered in full
+        let (first, drawn, outstanding) = accrue_and_draw(1, start, date(2023, 1, 31));
+        assert_eq!(drawn, first);
+        assert_eq!(outstanding, Decimal::ZERO);
+        // The second drains the rest of the reserve and leaves the shortfall accrued
+        let (accrued, drawn, outstanding) = accrue_and_draw(3, date(2023, 1, 31), date(2023, 3, 2));
+        assert_eq!(drawn, Decimal::from(1_500) - first);
+        assert_eq!(outstanding, accrued - drawn);
+        assert!(outstanding > Decimal::ZERO);
+        assert_eq!(reserve, Decimal::ZERO);
+    }
+
+    #[test]
+    fn penalties_escalate_through_the_tiers() {
+        let percent = |rate: i64| Decimal::from(rate) / Decimal::from(100);
+        let tiers = [(5, percent(10)), (30, percent(20))];
+        assert_eq!(tiered_rate(&tiers, percent(5), 0), percent(5));
+        assert_eq!(tiered_rate(&tiers, percent(5), 4), percent(5));
+        assert_eq!(tiered_rate(&tiers, percent(5), 5), percent(10));
+        assert_eq!(tiered_rate(&tiers, percent(5), 29), percent(10));
+        assert_eq!(tiered_rate(&tiers, percent(5), 30), percent(20));
+        assert_eq!(tiered_rate(&tiers, percent(5), 400), percent(20));
+        assert_eq!(tiered_rate(&[], percent(5), 400), percent(5));
+
+        // Ten days into the second tier, 1,000 overdue accrues at 20%
+        let due_date = date(2023, 6, 1);
+        let now = due_date + 40 * 86_400;
+        let rate = tiered_rate(&tiers, percent(5), (now - due_date) / 86_400);
+        let penalty = compute_interest(Decimal::from(1_000), rate, due_date, now, DayCountConvention::Actual365);
+        assert_eq!(round(penalty), round(PreciseDecimal::from(1_000 * 20 * 40) / PreciseDecimal::from(100 * 365)));
+    }
+
+    #[test]
+    fn a_scheduled_step_splits_the_accrual() {
+        let (five, seven) = (Decimal::from(5) / Decimal::from(100), Decimal::from(7) / Decimal::from(100));
+        let schedule = [(Instant::new(date(2023, 3, 1)), seven)];
+        // Accruing across the step: 5% up to it, 7% after
+        assert_eq!(
+            scheduled_periods(five, &schedule, date(2023, 2, 1), date(2023, 4, 1)),
+            vec![(date(2023, 2, 1), date(2023, 3, 1), five), (date(2023, 3, 1), date(2023, 4, 1), seven)]
+        );
+        // A step at either end of the period does not split it
+        let (before, after) = ((date(2023, 2, 1), date(2023, 3, 1)), (date(2023, 3, 1), date(2023, 4, 1)));
+        assert_eq!(scheduled_periods(five, &schedule, before.0, before.1), vec![(before.0, before.1, five)]);
+        assert_eq!(scheduled_periods(seven, &schedule, after.0, after.1), vec![(after.0, after.1, seven)]);
+        // Without a schedule the current rate applies throughout
+        assert_eq!(scheduled_periods(five, &[], before.0, after.1), vec![(before.0, after.1, five)]);
+    }
+
+    #[test]
+    fn the_waterfall_settles_penalties_fees_interest_then_principal() {
+        let waterfall = |amount: i64| {
+            allocate_payment(Decimal::from(amount), Decimal::from(10), Decimal::from(5), Decimal::from(20), Decimal::from(1_000))
+        };
+        let allocation = waterfall(500);
+        assert_eq!(
+            (allocation.penalties_paid, allocation.fees_paid, allocation.interest_paid, allocation.principal_paid, allocation.excess),
+            (Decimal::from(10), Decimal::from(5), Decimal::from(20), Decimal::from(465), Decimal::ZERO)
+        );
+        // Too little for the interest: principal is untouched
+        let allocation = waterfall(25);
+        assert_eq!((allocation.interest_paid, allocation.principal_paid), (Decimal::from(10), Decimal::ZERO));
+        // An overpayment settles everything and returns the rest
+        assert_eq!(waterfall(1_100).excess, Decimal::from(65));
+
+        // A prepayment fee comes out of what the payment had left for principal
+        let allocation = waterfall(500);
+        let (principal_paid, fee, excess) =
+            split_prepayment_fee(allocation.principal_paid, allocation.excess, PrepaymentFee::Absolute(Decimal::from(15)));
+        assert_eq!((principal_paid, fee, excess), (Decimal::from(450), Decimal::from(15), Decimal::ZERO));
+        let (principal_paid, fee, excess) =
+            split_prepayment_fee(allocation.principal_paid, allocation.excess, PrepaymentFee::Relative(Decimal::from(1) / Decimal::from(100)));
+        assert_eq!(principal_paid + fee + excess, Decimal::from(465));
+        assert!(fee <= principal_paid / Decimal::from(100));
+        // A payment that cannot cover an absolute fee is handed back
+        assert_eq!(
+            split_prepayment_fee(Decimal::from(10), Decimal::ZERO, PrepaymentFee::Absolute(Decimal::from(15))),
+            (Decimal::ZERO, Decimal::ZERO, Decimal::from(10))
+        );
+    }
+
+    #[test]
+    fn economics_match_hand_computed_figures() {
+        let percent = |rate: i64| Decimal::from(rate) / Decimal::from(100);
+        // 5% for the first 73 days of a 365-day year, 10% for the remaining 292
+        let start = date(2023, 1, 1);
+        let step = start + 73 * 86_400;
+        let end = start + 365 * 86_400;
+        let history = [(Instant::new(start), percent(5)), (Instant::new(step), percent(10))];
+        let periods = rate_periods(&history, start, end);
+        assert_eq!(time_weighted_rate(&periods), Some(percent(9)));
+        assert_eq!(time_weighted_rate(&rate_periods(&history, start, start)), None);
+
+        // Interest on 1,000 is 10 for the first period and 80 for the second; with 5 of
+        // penalties and 5 of fees, 100 was charged over the year
+        let interest: Vec<Decimal> = periods
+            .iter()
+            .map(|(from, to, rate)| round(compute_interest(Decimal::from(1_000), *rate, *from, *to, DayCountConvention::Actual365)))
+            .collect();
+        assert_eq!(interest, [Decimal::from(10), Decimal::from(80)]);
+        let charged = interest[0] + interest[1] + Decimal::from(5) + Decimal::from(5);
+        let elapsed = year_fraction(start, end, DayCountConvention::Actual365);
+        assert_eq!(annualized_yield(charged, Decimal::from(1_000), elapsed), percent(10));
+        // Over half the time, the same charges annualize to twice the yield
+        let half = year_fraction(start, start + 365 * 86_400 / 2, DayCountConvention::Actual365);
+        assert_eq!(annualized_yield(charged, Decimal::from(1_000), half), percent(20));
+        assert_eq!(annualized_yield(charged, Decimal::ZERO, elapsed), Decimal::ZERO);
+    }
+
+    #[test]
+    fn an_accrual_hitting_the_interest_ceiling_is_clamped() {
+        // 1,000 at 10% with a ceiling of 2% of principal and 15 already outstanding
+        let principal = Decimal::from(1_000);
+        let ceiling = principal * Decimal::from(2) / Decimal::from(100);
+        let outstanding = Decimal::from(15);
+        let start = date(2023, 1, 1);
+        let interest = |days: i64| {
+            compute_interest(principal, Decimal::from(10) / Decimal::from(100), start, start + days * 86_400, DayCountConvention::Actual365)
+        };
+        let accrue = |days: i64| round(clamp_to_headroom(interest(days), Some(headroom(ceiling, outstanding))));
+        // Ten days accrue about 2.74, within the ceiling
+        assert_eq!(accrue(10), round(interest(10)));
+        // Thirty days would accrue about 8.22; the ceiling stops it at 5
+        assert_eq!(outstanding + accrue(30), ceiling);
+        // Paying interest down frees headroom again
+        assert_eq!(headroom(ceiling, Decimal::from(5)), Decimal::from(15));
+    }
+
+    #[test]
+    fn servicing_interest_never_touches_principal() {
+        let interest_due = Decimal::from(25);
+        assert_eq!(split_interest_payment(Decimal::from(25), interest_due), (Decimal::from(25), Decimal::ZERO));
+        assert_eq!(split_interest_payment(Decimal::from(40), interest_due), (Decimal::from(25), Decimal::from(15)));
+        assert_eq!(split_interest_payment(Decimal::from(10), interest_due), (Decimal::from(10), Decimal::ZERO));
+
+        // Replayed, an over-interest payment clears the interest and leaves the principal as it was
+        let timestamp = Instant::new(date(2023, 6, 1));
+        let mut balances = Balances::zero();
+        balances.apply(0, &LedgerEntry::Initiated { timestamp, principal: Decimal::from(1_000), accrued_interest: interest_due });
+        let (interest_paid, _) = split_interest_payment(Decimal::from(40), interest_due);
+        balances.apply(1, &LedgerEntry::Payment {
+            timestamp,
+            penalties_paid: Decimal::ZERO,
+            fees_paid: Decimal::ZERO,
+            interest_paid,
+            principal_paid: Decimal::ZERO,
+            late: false,
+        });
+        assert_eq!((balances.principal, balances.accrued_interest), (Decimal::from(1_000), Decimal::ZERO));
+    }
+
+    #[test]
+    fn a_negative_rate_shrinks_the_balance_over_time() {
+        let principal = Decimal::from(1_000);
+        let start = date(2024, 1, 1);
+        let interest = compute_interest(principal, dec!("-0.05"), start, date(2025, 1, 1), DayCountConvention::Actual365);
+        assert!(interest < PreciseDecimal::ZERO);
+        let credit = round(-interest);
+        assert_eq!(credit, dec!("50.136986301369863013"));
+        // With no interest accrued, the whole credit comes off principal
+        let (interest_reduction, principal_reduction) = split_negative_interest(credit, Decimal::ZERO, principal);
+        assert_eq!((interest_reduction, principal_reduction), (Decimal::ZERO, credit));
+        // Accrued interest absorbs the credit first
+        assert_eq!(split_negative_interest(credit, Decimal::from(20), principal), (Decimal::from(20), credit - Decimal::from(20)));
+        assert_eq!(split_negative_interest(credit, Decimal::from(80), principal), (credit, Decimal::ZERO));
+        // The credit never takes the principal below zero
+        assert_eq!(split_negative_interest(credit, Decimal::ONE, Decimal::from(10)), (Decimal::ONE, Decimal::from(10)));
+        // A zero rate leaves the balance where it was
+        assert_eq!(compute_interest(principal, Decimal::ZERO, start, date(2025, 1, 1), DayCountConvention::Actual365), PreciseDecimal::ZERO);
+    }
+
+    #[test]
+    fn a_quote_only_forgives_interest_accrued_since_it_was_given() {
+        let quoted = Decimal::from(1_010);
+        // Only interest accrued since the quote: the whole gap is forgiven
+        assert_eq!(quote_forgiveness(Decimal::from(1_012), quoted, Decimal::from(2), Decimal::from(12)), Decimal::from(2));
+        // A fee of 5 charged since the quote is still owed
+        assert_eq!(quote_forgiveness(Decimal::from(1_017), quoted, Decimal::from(2), Decimal::from(12)), Decimal::from(2));
+        // Never more than the interest outstanding, e.g. once it was capitalized
+        assert_eq!(quote_forgiveness(Decimal::from(1_012), quoted, Decimal::from(2), Decimal::ONE), Decimal::ONE);
+        // Nothing to forgive when the amount due has not grown past the quote
+        assert_eq!(quote_forgiveness(Decimal::from(1_005), quoted, Decimal::from(2), Decimal::from(12)), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,142 @@
+use scrypto::prelude::*;
+use crate::call_money::CallMoney;
+use crate::{ContractStatus, ContractTerms};
+
+// This module defines a factory blueprint that originates Call Money contracts. It gives
+// originators a single entry point, numbers contracts sequentially and indexes them by party.
+#[blueprint]
+mod call_money_factory {
+    /// The CallMoneyFactory struct holds the contracts it has originated and their indexes.
+    struct CallMoneyFactory {
+        prefix: String,                    // Prefix of the contract ids assigned by this factory
+        next_sequence: u64,                // Sequence number of the next originated contract
+        contracts: KeyValueStore<u64, Global<CallMoney>>, // Originated contracts keyed by sequence number
+        by_lender: KeyValueStore<ResourceAddress, Vec<u64>>, // Sequence numbers per original lender
+        by_borrower: KeyValueStore<ResourceAddress, Vec<u64>>, // Sequence numbers per original borrower
+    }
+
+    impl CallMoneyFactory {
+        /// Instantiates a new factory that has originated no contracts.
+        ///
+        /// # Arguments
+        /// * `prefix` - Prefix of the contract ids, e.g. "CLM"; ids take the form "<prefix>-000001"
+        ///
+        /// # Returns
+        /// The globalized factory component
+        pub fn instantiate_factory(prefix: String) -> Global<CallMoneyFactory> {
+            assert!(!prefix.is_empty(), "Contract id prefix cannot be empty");
+            Self {
+                prefix,
+                next_sequence: 1,
+                contracts: KeyValueStore::new(),
+                by_lender: KeyValueStore::new(),
+                by_borrower: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        /// Originates a new contract with the next sequential contract id.
+        ///
+        /// The terms are validated by `CallMoney::instantiate_call_money`, so invalid terms
+        /// abort the transaction before a sequence number is used up.
+        ///
+        /// # Arguments
+        /// * `terms` - The contract terms; the factory assigns the contract id and never enables test mode
+        /// * `principal_funds` - The funds lent, in the terms' currency
+        ///
+        /// # Returns
+        /// A tuple of the sequence number, the new contract, its creditor note and its owner badge, for the lender
+        pub fn originate(&mut self, mut terms: ContractTerms, principal_funds: Bucket) -> (u64, Global<CallMoney>, Bucket, Bucket) {
+            let sequence = self.next_sequence;
+            terms.contract_id = Some(format!("{}-{:06}", self.prefix, sequence));
+            terms.test_mode = false;
+            let (lender, borrower) = (terms.lender, terms.borrower);
+            let (contract, creditor_note, owner_badge) = Blueprint::<CallMoney>::instantiate_call_money(terms, principal_funds);
+
+            self.contracts.insert(sequence, contract.clone());
+            Self::index(&self.by_lender, lender, sequence);
+            Self::index(&self.by_borrower, borrower, sequence);
+            self.next_sequence += 1;
+            (sequence, contract, creditor_note, owner_badge)
+        }
+
+        /// Looks up an originated contract.
+        ///
+        /// # Arguments
+        /// * `sequence` - The sequence number assigned at origination
+        ///
+        /// # Returns
+        /// The contract, or `None` if no contract has this sequence number
+        pub fn get_contract(&self, sequence: u64) -> Option<Global<CallMoney>> {
+            self.contracts.get(&sequence).map(|contract| contract.clone())
+        }
+
+        /// Lists the contracts originated for a lender.
+        ///
+        /// Lender rights travel with the creditor note, so this reports the original lender.
+        ///
+        /// # Arguments
+        /// * `lender` - ResourceAddress of the lender's badge at origination
+        ///
+        /// # Returns
+        /// A vector of (sequence, contract) pairs, in origination order
+        pub fn contracts_by_lender(&self, lender: ResourceAddress) -> Vec<(u64, Global<CallMoney>)> {
+            self.lookup(&self.by_lender, lender)
+        }
+
+        /// Lists the contracts originated for a borrower.
+        ///
+        /// A novated contract stays listed under the borrower it was originated for.
+        ///
+        /// # Arguments
+        /// * `borrower` - ResourceAddress of the borrower's badge at origination
+        ///
+        /// # Returns
+        /// A vector of (sequence, contract) pairs, in origination order
+        pub fn contracts_by_borrower(&self, borrower: ResourceAddress) -> Vec<(u64, Global<CallMoney>)> {
+            self.lookup(&self.by_borrower, borrower)
+        }
+
+        /// Lists the originated contracts currently in a given status.
+        ///
+        /// Status changes inside each contract, so it is read live rather than indexed.
+        ///
+        /// # Arguments
+        /// * `status` - The status to filter by
+        ///
+        /// # Returns
+        /// A vector of (sequence, contract) pairs, in origination order
+        pub fn contracts_by_status(&self, status: ContractStatus) -> Vec<(u64, Global<CallMoney>)> {
+            let mut matches = Vec::new();
+            for sequence in 1..self.next_sequence {
+                let Some(contract) = self.get_contract(sequence) else {
+                    continue;
+                };
+                if contract.get_details().status == status {
+                    matches.push((sequence, contract));
+                }
+            }
+            matches
+        }
+
+        /// Appends a sequence number to a party's index entry.
+        fn index(store: &KeyValueStore<ResourceAddress, Vec<u64>>, party: ResourceAddress, sequence: u64) {
+            let mut sequences = store.get(&party).map(|entry| entry.clone()).unwrap_or_default();
+            sequences.push(sequence);
+            store.insert(party, sequences);
+        }
+
+        /// Resolves a party's index entry to its contracts.
+        fn lookup(&self, store: &KeyValueStore<ResourceAddress, Vec<u64>>, party: ResourceAddress) -> Vec<(u64, Global<CallMoney>)> {
+            store
+                .get(&party)
+                .map(|entry| entry.clone())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|sequence| self.get_contract(sequence).map(|contract| (sequence, contract)))
+                .collect()
+        }
+    }
+}
@@ -1,254 +1,6121 @@
 use scrypto::prelude::*;
 
+mod actus;
+mod calendar;
+mod core_math;
+mod factory;
+mod liquidation;
+mod oracle;
+mod portfolio;
+mod registry;
+mod schedule;
+
+pub use calendar::{BusinessDayCalendar, BusinessDayConvention};
+
+/// Number of seconds in a day, used to key daily balance snapshots.
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// 3000-01-01T00:00:00Z. Later timestamps are assumed to be milliseconds passed by mistake.
+const MAX_TIMESTAMP: i64 = 32_503_680_000;
+
+/// A failure mode of the contract, with a stable numeric code so integrators can
+/// tell failures apart programmatically. Failures panic with the error formatted
+/// as `CLM_ERR[<code>]: <Name>(<field>=<value>, ...)`.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub enum CallMoneyError {
+    InvalidPrincipal,                  // 1: principal is not positive
+    InvalidRate { field: String },     // 2: a rate is negative or not below 1
+    InvalidPeriod { field: String },   // 3: a period is negative
+    InvalidAmount { field: String },   // 4: an amount, cap or ratio is out of range
+    InvalidTerms { reason: String },   // 5: terms are inconsistent or incomplete
+    InvalidTimestamp { supplied: i64 }, // 10: timestamp is not in seconds between 1970 and 3000
+    BackdatedTimestamp { supplied: i64, minimum: i64 }, // 11: date precedes the last interest calculation
+    NotActive { current: ContractStatus }, // 12: the contract must be Active
+    NotCalled { current: ContractStatus }, // 13: the contract must have been called
+    AlreadyRepaid,                     // 14: the contract is already repaid
+    BelowMinimumRepayment { amount: Decimal, minimum: Decimal }, // 15: partial payment below the minimum
+    DueDatePassed { due_date: i64 },   // 16: the called amount has already fallen due
+    PenaltiesApplied,                  // 17: penalties have already been applied to the call
+    ContractClosed { status: ContractStatus }, // 18: the contract has reached a terminal status
+    InvalidTransition { from: ContractStatus, to: ContractStatus }, // 19: the status change is not allowed
+    WrongResource { expected: ResourceAddress, got: ResourceAddress }, // 20: bucket holds the wrong resource
+    UnacceptedCollateral { resource: ResourceAddress }, // 21: resource is not accepted as collateral
+    CollateralLocked,                  // 22: collateral cannot be removed while the loan is outstanding
+    RateResetNotDue { next_reset: i64 }, // 23: the next rate reset date has not been reached
+    StaleOracleData { published_at: i64, max_age: i64 }, // 24: the oracle rate or price is older than allowed
+    RepaymentNoticeGiven { termination_date: i64 }, // 25: terms are locked until the borrower's notice runs out
+    NotOverdue,                        // 26: nothing is overdue past the grace period
+    Paused,                            // 27: the contract is paused by its owner
+    Frozen { reason: String },         // 28: the contract's current state blocks the operation
+    NotAParty { resource: ResourceAddress }, // 29: the proof is of neither party's badge
+}
+
+impl CallMoneyError {
+    /// The stable numeric code of the error.
+    pub fn code(&self) -> u16 {
+        match self {
+            CallMoneyError::InvalidPrincipal => 1,
+            CallMoneyError::InvalidRate { .. } => 2,
+            CallMoneyError::InvalidPeriod { .. } => 3,
+            CallMoneyError::InvalidAmount { .. } => 4,
+            CallMoneyError::InvalidTerms { .. } => 5,
+            CallMoneyError::InvalidTimestamp { .. } => 10,
+            CallMoneyError::BackdatedTimestamp { .. } => 11,
+            CallMoneyError::NotActive { .. } => 12,
+            CallMoneyError::NotCalled { .. } => 13,
+            CallMoneyError::AlreadyRepaid => 14,
+            CallMoneyError::BelowMinimumRepayment { .. } => 15,
+            CallMoneyError::DueDatePassed { .. } => 16,
+            CallMoneyError::PenaltiesApplied => 17,
+            CallMoneyError::ContractClosed { .. } => 18,
+            CallMoneyError::InvalidTransition { .. } => 19,
+            CallMoneyError::WrongResource { .. } => 20,
+            CallMoneyError::UnacceptedCollateral { .. } => 21,
+            CallMoneyError::CollateralLocked => 22,
+            CallMoneyError::RateResetNotDue { .. } => 23,
+            CallMoneyError::StaleOracleData { .. } => 24,
+            CallMoneyError::RepaymentNoticeGiven { .. } => 25,
+            CallMoneyError::NotOverdue => 26,
+            CallMoneyError::Paused => 27,
+            CallMoneyError::Frozen { .. } => 28,
+            CallMoneyError::NotAParty { .. } => 29,
+        }
+    }
+
+    /// Aborts the transaction with this error.
+    pub fn raise(self) -> ! {
+        panic!("{}", self)
+    }
+}
+
+impl std::fmt::Display for CallMoneyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "CLM_ERR[{}]: ", self.code())?;
+        match self {
+            CallMoneyError::InvalidPrincipal => write!(f, "InvalidPrincipal"),
+            CallMoneyError::InvalidRate { field } => write!(f, "InvalidRate(field={})", field),
+            CallMoneyError::InvalidPeriod { field } => write!(f, "InvalidPeriod(field={})", field),
+            CallMoneyError::InvalidAmount { field } => write!(f, "InvalidAmount(field={})", field),
+            CallMoneyError::InvalidTerms { reason } => write!(f, "InvalidTerms(reason={})", reason),
+            CallMoneyError::InvalidTimestamp { supplied } => write!(f, "InvalidTimestamp(supplied={})", supplied),
+            CallMoneyError::BackdatedTimestamp { supplied, minimum } => {
+                write!(f, "BackdatedTimestamp(supplied={}, minimum={})", supplied, minimum)
+            }
+            CallMoneyError::NotActive { current } => write!(f, "NotActive(current={})", current),
+            CallMoneyError::NotCalled { current } => write!(f, "NotCalled(current={})", current),
+            CallMoneyError::AlreadyRepaid => write!(f, "AlreadyRepaid"),
+            CallMoneyError::BelowMinimumRepayment { amount, minimum } => {
+                write!(f, "BelowMinimumRepayment(amount={}, minimum={})", amount, minimum)
+            }
+            CallMoneyError::DueDatePassed { due_date } => write!(f, "DueDatePassed(due_date={})", due_date),
+            CallMoneyError::PenaltiesApplied => write!(f, "PenaltiesApplied"),
+            CallMoneyError::ContractClosed { status } => write!(f, "ContractClosed(status={})", status),
+            CallMoneyError::InvalidTransition { from, to } => write!(f, "InvalidTransition(from={}, to={})", from, to),
+            CallMoneyError::WrongResource { expected, got } => write!(f, "WrongResource(expected={:?}, got={:?})", expected, got),
+            CallMoneyError::UnacceptedCollateral { resource } => write!(f, "UnacceptedCollateral(resource={:?})", resource),
+            CallMoneyError::CollateralLocked => write!(f, "CollateralLocked"),
+            CallMoneyError::RateResetNotDue { next_reset } => write!(f, "RateResetNotDue(next_reset={})", next_reset),
+            CallMoneyError::StaleOracleData { published_at, max_age } => {
+                write!(f, "StaleOracleData(published_at={}, max_age={})", published_at, max_age)
+            }
+            CallMoneyError::RepaymentNoticeGiven { termination_date } => {
+                write!(f, "RepaymentNoticeGiven(termination_date={})", termination_date)
+            }
+            CallMoneyError::NotOverdue => write!(f, "NotOverdue"),
+            CallMoneyError::Paused => write!(f, "Paused"),
+            CallMoneyError::Frozen { reason } => write!(f, "Frozen(reason={})", reason),
+            CallMoneyError::NotAParty { resource } => write!(f, "NotAParty(resource={:?})", resource),
+        }
+    }
+}
+
+/// Aborts the transaction with `error` unless `condition` holds.
+fn ensure(condition: bool, error: CallMoneyError) {
+    if !condition {
+        error.raise();
+    }
+}
+
+/// Checks that `date` is a valid Unix timestamp in seconds no earlier than `minimum`,
+/// so a stale date is rejected rather than accruing negative interest.
+fn check_date(date: i64, minimum: i64) -> Result<(), CallMoneyError> {
+    if !(0..MAX_TIMESTAMP).contains(&date) {
+        return Err(CallMoneyError::InvalidTimestamp { supplied: date });
+    }
+    if date < minimum {
+        return Err(CallMoneyError::BackdatedTimestamp { supplied: date, minimum });
+    }
+    Ok(())
+}
+
+/// Checks a repayment against the minimum partial repayment. A payment settling
+/// `total_due` in full is always accepted, however small.
+fn check_minimum_repayment(amount: Decimal, total_due: Decimal, minimum: Decimal) -> Result<(), CallMoneyError> {
+    if amount < total_due && amount < minimum {
+        return Err(CallMoneyError::BelowMinimumRepayment { amount, minimum });
+    }
+    Ok(())
+}
+
+/// Checks that a call can still be cancelled at `current_date`: before it falls due and
+/// before any penalty was charged on it. A call awaiting acknowledgment has no due date yet.
+fn check_call_cancellable(due_date: Option<i64>, penalties_applied: bool, current_date: i64) -> Result<(), CallMoneyError> {
+    if let Some(due_date) = due_date {
+        if current_date >= due_date {
+            return Err(CallMoneyError::DueDatePassed { due_date });
+        }
+    }
+    if penalties_applied {
+        return Err(CallMoneyError::PenaltiesApplied);
+    }
+    Ok(())
+}
+
+/// Whether a payment made at `paid_at` is late: after the call or maturity due date.
+fn paid_late(due_date: Option<Instant>, paid_at: Instant) -> bool {
+    due_date.is_some_and(|due_date| paid_at > due_date)
+}
+
+/// The keeper reward earned on a penalty: nothing when no penalty was applied, and never
+/// more than the penalty itself or than the reward pool holds.
+fn keeper_payout(reward: Decimal, penalty: Decimal, available: Decimal) -> Decimal {
+    if penalty <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    reward.min(penalty).min(available)
+}
+
+/// The steps of a rate schedule still to take effect after `after`.
+fn pending_steps(schedule: &[(Instant, Decimal)], after: Instant) -> Vec<(Instant, Decimal)> {
+    schedule.iter().filter(|(effective_date, _)| *effective_date > after).cloned().collect()
+}
+
+/// Derives a contract id from the parties and the start date, so the same contract
+/// always gets the same id.
+fn default_contract_id(lender: ResourceAddress, borrower: ResourceAddress, start_date: i64) -> String {
+    hash(scrypto_encode(&(lender, borrower, start_date)).unwrap()).to_string()
+}
+
+/// Whether `rate` is an acceptable interest rate: at least 0 and below 1, or above -1
+/// and below 1 when negative rates are allowed.
+fn interest_rate_in_range(rate: Decimal, allow_negative_rate: bool) -> bool {
+    let floor_ok = if allow_negative_rate { rate > -Decimal::ONE } else { rate >= Decimal::ZERO };
+    floor_ok && rate < Decimal::ONE
+}
+
+/// Shorthand for an `InvalidTerms` error.
+fn invalid_terms(reason: &str) -> CallMoneyError {
+    CallMoneyError::InvalidTerms { reason: reason.to_string() }
+}
+
+/// Shorthand for a `Frozen` error.
+fn frozen(reason: &str) -> CallMoneyError {
+    CallMoneyError::Frozen { reason: reason.to_string() }
+}
+
+/// The lifecycle status of a contract.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractStatus {
+    Offered,                           // Proposed but not yet accepted
+    Active,                            // Open; interest accrues and the lender may call
+    Called,                            // Repayment has been demanded
+    Term,                              // Converted to a fixed-term loan with a maturity date
+    Delinquent,                        // Overdue past the grace period, with penalties applied
+    MarginCall,                        // Open, but collateral no longer covers the loan at the maximum loan-to-value
+    Repaid,                            // Settled in full
+    Defaulted,                         // The borrower has defaulted
+    Terminated,                        // Closed without repayment, e.g. an offer withdrawn
+}
+
+impl ContractStatus {
+    /// Whether the contract can no longer change status.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, ContractStatus::Repaid | ContractStatus::Defaulted | ContractStatus::Terminated)
+    }
+
+    /// Whether the contract may move from this status to `next`.
+    pub fn can_transition_to(self, next: ContractStatus) -> bool {
+        use ContractStatus::*;
+        matches!(
+            (self, next),
+            (Offered, Active | Terminated)
+                | (Active, Called | MarginCall | Repaid | Terminated)
+                | (MarginCall, Active | Called | Repaid | Defaulted)
+                | (Called, Active | Term | Delinquent | Repaid | Defaulted)
+                | (Term, Delinquent | Repaid | Defaulted)
+                | (Delinquent, Active | Term | Repaid | Defaulted)
+        )
+    }
+
+    /// Checks that a contract in this status is open: accepted and not yet closed.
+    pub fn check_open(self) -> Result<(), CallMoneyError> {
+        if self.is_terminal() {
+            return Err(CallMoneyError::ContractClosed { status: self });
+        }
+        if self == ContractStatus::Offered {
+            return Err(CallMoneyError::NotActive { current: self });
+        }
+        Ok(())
+    }
+
+    /// The ACTUS performance the status reports as.
+    pub fn performance(self) -> ContractPerformance {
+        match self {
+            ContractStatus::Delinquent => ContractPerformance::Delayed,
+            ContractStatus::Defaulted => ContractPerformance::Default,
+            _ => ContractPerformance::Performant,
+        }
+    }
+}
+
+impl std::fmt::Display for ContractStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The balances of a contract as of a given time.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct BalanceSnapshot {
+    pub as_of: Instant,                // Time the balances are reported at
+    pub principal: Decimal,            // Outstanding principal
+    pub accrued_interest: Decimal,     // Interest accrued up to `as_of`
+    pub accrued_fees: Decimal,         // Servicing fees charged but not yet paid
+    pub accrued_penalties: Decimal,    // Penalties accrued but not yet paid
+    pub status: ContractStatus,        // Contract status at `as_of`
+}
+
+/// The ACTUS contract performance indicator (PRF).
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractPerformance {
+    Performant,                        // PF: obligations met on time
+    Delayed,                           // DL: payment overdue, penalties accruing
+    Delinquent,                        // DQ: payment significantly overdue
+    Default,                           // DF: contract in default
+}
+
+/// The contract state expressed with ACTUS state-variable names.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ActusState {
+    pub status_date: i64,              // SD: the date the state is reported at
+    pub contract_performance: ContractPerformance, // PRF: performance derived from the contract status
+    pub nominal_value: Decimal,        // Nt: outstanding principal
+    pub accrued_interest: Decimal,     // Ipac: interest accrued up to the status date
+    pub fee_accrued: Decimal,          // Feac: fees charged up to the status date and not yet paid
+    pub nominal_interest_rate: Decimal, // Ipnr: annual interest rate
+}
+
+/// The current details of a contract, as returned by `get_details`.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ContractDetails {
+    pub lender: ResourceAddress,       // Badge of the original lender
+    pub borrower: ResourceAddress,     // Badge of the current borrower
+    pub principal: Decimal,            // Outstanding principal
+    pub interest_rate: Decimal,        // Annual interest rate
+    pub start_date: i64,               // When the contract started, as a Unix timestamp
+    pub accrued_interest: Decimal,     // Interest accrued as of the last calculation
+    pub accrued_penalties: Decimal,    // Penalties accrued but not yet paid
+    pub status: ContractStatus,        // Current contract status
+    pub due_date: Option<i64>,         // Call due date or maturity date, once repayment falls due
+    pub collateral: Vec<(ResourceAddress, Decimal)>, // Collateral held, per accepted resource
+}
+
+/// The original tuple form of the contract details, as returned by `get_details_tuple`:
+/// (lender, borrower, principal, interest_rate, start_date, accrued_interest, status, collateral).
+pub type ContractDetailsTuple =
+    (ResourceAddress, ResourceAddress, Decimal, Decimal, i64, Decimal, ContractStatus, Vec<(ResourceAddress, Decimal)>);
+
+/// A summary of the contract state, with dates as `Instant`s.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ContractSummary {
+    pub principal: Decimal,            // Outstanding principal
+    pub accrued_interest: Decimal,     // Interest accrued as of the last calculation
+    pub accrued_fees: Decimal,         // Servicing fees charged but not yet paid
+    pub accrued_penalties: Decimal,    // Penalties accrued but not yet paid
+    pub interest_rate: Decimal,        // Annual interest rate
+    pub status: ContractStatus,        // Current contract status
+    pub start_date: Instant,           // When the contract started
+    pub last_interest_calculation_date: Instant, // When interest was last calculated
+}
+
+impl ContractSummary {
+    /// Returns the contract start date as a UTC calendar date and time.
+    pub fn start_date_utc(&self) -> UtcDateTime {
+        to_utc_date_time(self.start_date)
+    }
+
+    /// Returns the last interest calculation date as a UTC calendar date and time.
+    pub fn last_interest_calculation_date_utc(&self) -> UtcDateTime {
+        to_utc_date_time(self.last_interest_calculation_date)
+    }
+}
+
+/// Converts an `Instant` to a UTC calendar date and time.
+pub fn to_utc_date_time(instant: Instant) -> UtcDateTime {
+    UtcDateTime::from_instant(&instant).expect("Instant cannot be represented as a UTC date")
+}
+
+/// The balance a late-payment penalty is charged on.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PenaltyBase {
+    #[default]
+    PrincipalOnly,                     // Penalize the outstanding principal only
+    TotalOutstanding,                  // Penalize principal plus accrued interest
+}
+
+impl PenaltyBase {
+    /// The balance penalized, given the outstanding principal and accrued interest.
+    pub fn amount(self, principal: Decimal, accrued_interest: Decimal) -> Decimal {
+        match self {
+            PenaltyBase::PrincipalOnly => principal,
+            PenaltyBase::TotalOutstanding => principal + accrued_interest,
+        }
+    }
+}
+
+/// How late-payment penalties are charged, after the ACTUS penalty type (PYTP) attribute.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PenaltyType {
+    #[default]
+    DailyRate,                         // The penalty rate, or its tier, accrues on the penalty base while overdue
+    Absolute(Decimal),                 // A: a fixed amount per missed deadline
+    Relative(Decimal),                 // R: a share of the outstanding notional per missed deadline
+    Indexed(Decimal),                  // I: the reference rate plus this spread accrues on the penalty base while overdue
+}
+
+/// How a prepayment fee is charged, after the ACTUS penalty type (PYTP) attribute.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrepaymentFee {
+    Absolute(Decimal),                 // A: a fixed amount per prepayment
+    Relative(Decimal),                 // R: a rate on the prepaid principal
+}
+
+/// Prepayment terms: a fee on principal the borrower repays before the lender calls.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrepaymentTerms {
+    pub fee: PrepaymentFee,            // PYTP/PYRT: the fee charged on a prepayment
+    pub lockup_end: Option<i64>,       // End of the lockup the fee applies within, as a Unix timestamp; None for the whole uncalled life
+}
+
+/// What a fee amount is expressed against, after the ACTUS fee basis (FEB) attribute.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FeeBasis {
+    #[default]
+    Absolute,                          // A: the fee is a fixed amount
+    Notional,                          // N: the fee is a rate on the outstanding principal
+}
+
+/// ACTUS fee terms: an origination fee withheld from the disbursement and a servicing
+/// fee charged every fee cycle.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeTerms {
+    pub fee_basis: FeeBasis,           // FEB: whether the fees below are amounts or rates on the principal
+    pub origination_fee: Decimal,      // Withheld from the principal when it is lent: an amount, or a share of the principal
+    pub fee_rate: Decimal,             // FER: servicing fee per cycle, or its annual rate on the principal
+    pub fee_cycle: i64,                // FECL: seconds between servicing fee charges
+}
+
+/// Tax withheld from interest payments for a tax authority.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithholdingTax {
+    pub rate: Decimal,                 // Share of every interest payment withheld
+    pub authority: ResourceAddress,    // Badge of the tax authority the withheld tax is held for
+}
+
+/// How regular interest is treated once the whole loan has fallen due.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum GraceInterest {
+    #[default]
+    Continue,                          // Interest keeps accruing through the grace period and beyond
+    StopAtDueDate,                     // Interest stops at the due date; penalties start after the grace period
+    PenaltyRate,                       // Interest stops at the due date and penalties start right away
+}
+
+impl GraceInterest {
+    /// The date regular interest stops accruing at, given the date the whole loan fell due.
+    pub fn interest_stop_date(self, due_date: Option<Instant>) -> Option<Instant> {
+        match self {
+            GraceInterest::Continue => None,
+            GraceInterest::StopAtDueDate | GraceInterest::PenaltyRate => due_date,
+        }
+    }
+
+    /// Clamps an accrual period from `from` to `to` to the part before interest stops.
+    pub fn accrual_window(self, due_date: Option<Instant>, from: Instant, to: Instant) -> (Instant, Instant) {
+        match self.interest_stop_date(due_date) {
+            Some(stop_date) => (from.min(stop_date), to.min(stop_date)),
+            None => (from, to),
+        }
+    }
+
+    /// Whether penalties run from the due date itself rather than from the end of the grace period.
+    pub fn penalties_skip_grace(self) -> bool {
+        self == GraceInterest::PenaltyRate
+    }
+}
+
+/// Back-office reference data identifying the deal and its counterparties.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct ContractReference {
+    pub deal_id: String,               // Deal reference the back office books the contract under
+    pub lender_lei: String,            // Legal Entity Identifier of the lender
+    pub borrower_lei: String,          // Legal Entity Identifier of the borrower
+    pub booking_entity: String,        // Entity the contract is booked in
+    pub currency_code: String,         // ISO 4217 code of the principal currency
+}
+
+impl ContractReference {
+    /// Checks that every field is present and well-formed.
+    fn validate(&self) {
+        ensure(!self.deal_id.is_empty(), invalid_terms("deal id is required"));
+        ensure(Self::is_lei(&self.lender_lei), invalid_terms("lender LEI must be 20 alphanumeric characters"));
+        ensure(Self::is_lei(&self.borrower_lei), invalid_terms("borrower LEI must be 20 alphanumeric characters"));
+        ensure(!self.booking_entity.is_empty(), invalid_terms("booking entity is required"));
+        ensure(
+            self.currency_code.len() == 3 && self.currency_code.chars().all(|c| c.is_ascii_uppercase()),
+            invalid_terms("currency code must be three uppercase letters"),
+        );
+    }
+
+    /// Whether a value has the shape of an ISO 17442 Legal Entity Identifier.
+    fn is_lei(value: &str) -> bool {
+        value.len() == 20 && value.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+    }
+}
+
+/// How the days in an accrual period are counted and turned into a year fraction,
+/// after the ACTUS day count convention (DCC) attribute.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DayCountConvention {
+    ActualActual,                      // AA: actual days over the actual days in each year (ISDA)
+    Actual360,                         // A360: actual days over 360
+    #[default]
+    Actual365,                         // A365: actual days over 365
+    ThirtyE360,                        // 30E360: 30-day months, days 31 treated as 30, over 360
+}
+
+impl DayCountConvention {
+    /// The days in a year under the convention; 365 for actual/actual outside a specific year.
+    pub fn basis(self) -> i64 {
+        match self {
+            DayCountConvention::Actual360 | DayCountConvention::ThirtyE360 => 360,
+            DayCountConvention::ActualActual | DayCountConvention::Actual365 => 365,
+        }
+    }
+}
+
+/// How accrued interest and penalties, tracked at full precision, are rounded to
+/// whole `Decimal` amounts when they are reported, paid or settled.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RoundingConvention {
+    HalfUp,                            // Round to nearest, halves away from zero
+    #[default]
+    Down,                              // Truncate toward zero, never overcharging the borrower
+    Bankers,                           // Round to nearest, halves to the even digit
+}
+
+impl RoundingConvention {
+    /// The rounding mode the convention corresponds to.
+    pub fn mode(self) -> RoundingMode {
+        match self {
+            RoundingConvention::HalfUp => RoundingMode::ToNearestMidpointAwayFromZero,
+            RoundingConvention::Down => RoundingMode::ToZero,
+            RoundingConvention::Bankers => RoundingMode::ToNearestMidpointToEven,
+        }
+    }
+}
+
+/// ACTUS rate reset terms: the rate is periodically reset to a reference rate,
+/// scaled by a multiplier and shifted by a spread.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateReset {
+    pub cycle: i64,                    // RRCL: seconds between rate resets
+    pub spread: Decimal,               // RRSP: added to the scaled reference rate
+    pub multiplier: Decimal,           // RRMLT: applied to the reference rate before the spread
+}
+
+/// When the notice period of a call starts running.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NoticeStart {
+    #[default]
+    Call,                              // From the lender's call, acknowledged or not
+    Acknowledgment,                    // From the borrower's acknowledgment of the call
+}
+
+/// A lifecycle transition reported to the observer component. Observer components
+/// implement `on_clm_event(contract: ComponentAddress, event: ObserverEvent)`.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObserverEvent {
+    Called,                            // The lender called the money back
+    Delinquent,                        // Penalties started accruing on an overdue call
+    MarginCall,                        // Collateral fell below the maximum loan-to-value
+    Defaulted,                         // The guarantee was drawn, or the lender declared a default
+    Repaid,                            // The loan was repaid in full
+}
+
+/// An entry in the contract's transaction history.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct HistoryEntry {
+    pub timestamp: Instant,            // When the entry was made; undated operations use the last interest calculation date
+    pub description: String,           // What happened
+}
+
+impl HistoryEntry {
+    /// The marker left in place of `count` entries archived up to `before`.
+    pub fn archive_marker(count: usize, before: Instant) -> Self {
+        HistoryEntry {
+            timestamp: before,
+            description: format!("Archived {} entries up to {}", count, before.seconds_since_unix_epoch),
+        }
+    }
+}
+
+/// Takes the leading history entries dated before `before`, which are the ones an
+/// archive removes; everything from the first later entry on is retained.
+fn entries_before(entries: impl Iterator<Item = HistoryEntry>, before: Instant) -> Vec<HistoryEntry> {
+    entries.take_while(|entry| entry.timestamp < before).collect()
+}
+
+/// A balance-changing event, recorded so the balances can be replayed from the log.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub enum LedgerEntry {
+    Initiated { timestamp: Instant, principal: Decimal, accrued_interest: Decimal },
+    InterestAccrued { timestamp: Instant, rate: Decimal, amount: Decimal },
+    NegativeInterest { timestamp: Instant, rate: Decimal, interest_reduction: Decimal, principal_reduction: Decimal },
+    PenaltyApplied { timestamp: Instant, rate: Decimal, amount: Decimal },
+    Payment { timestamp: Instant, penalties_paid: Decimal, fees_paid: Decimal, interest_paid: Decimal, principal_paid: Decimal, late: bool },
+    ReserveDrawn { timestamp: Instant, amount: Decimal },
+    InterestCapitalized { timestamp: Instant, amount: Decimal },
+    InterestForgiven { timestamp: Instant, amount: Decimal },
+    CommitmentFeeAccrued { timestamp: Instant, rate: Decimal, amount: Decimal },
+    FeeAccrued { timestamp: Instant, amount: Decimal },
+    Drawdown { timestamp: Instant, amount: Decimal },
+    SplitOff { timestamp: Instant, principal: Decimal, interest: Decimal },
+    WrittenOff { timestamp: Instant, penalties: Decimal, fees: Decimal, interest: Decimal, principal: Decimal },
+}
+
+impl LedgerEntry {
+    /// When the entry was recorded.
+    pub fn timestamp(&self) -> Instant {
+        match self {
+            LedgerEntry::Initiated { timestamp, .. }
+            | LedgerEntry::InterestAccrued { timestamp, .. }
+            | LedgerEntry::NegativeInterest { timestamp, .. }
+            | LedgerEntry::PenaltyApplied { timestamp, .. }
+            | LedgerEntry::Payment { timestamp, .. }
+            | LedgerEntry::ReserveDrawn { timestamp, .. }
+            | LedgerEntry::InterestCapitalized { timestamp, .. }
+            | LedgerEntry::InterestForgiven { timestamp, .. }
+            | LedgerEntry::CommitmentFeeAccrued { timestamp, .. }
+            | LedgerEntry::FeeAccrued { timestamp, .. }
+            | LedgerEntry::Drawdown { timestamp, .. }
+            | LedgerEntry::SplitOff { timestamp, .. }
+            | LedgerEntry::WrittenOff { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// The kind of event the entry records and the amount it moved.
+    pub fn kind_and_amount(&self) -> (EventKind, Decimal) {
+        match self {
+            LedgerEntry::Initiated { principal, .. } => (EventKind::Initiation, *principal),
+            LedgerEntry::InterestAccrued { amount, .. } => (EventKind::InterestAccrual, *amount),
+            LedgerEntry::NegativeInterest { interest_reduction, principal_reduction, .. } => {
+                (EventKind::NegativeInterest, *interest_reduction + *principal_reduction)
+            }
+            LedgerEntry::PenaltyApplied { amount, .. } => (EventKind::Penalty, *amount),
+            LedgerEntry::Payment { penalties_paid, fees_paid, interest_paid, principal_paid, .. } => {
+                (EventKind::Repayment, *penalties_paid + *fees_paid + *interest_paid + *principal_paid)
+            }
+            LedgerEntry::ReserveDrawn { amount, .. } => (EventKind::ReserveDraw, *amount),
+            LedgerEntry::InterestCapitalized { amount, .. } => (EventKind::Capitalization, *amount),
+            LedgerEntry::InterestForgiven { amount, .. } => (EventKind::InterestForgiveness, *amount),
+            LedgerEntry::CommitmentFeeAccrued { amount, .. } => (EventKind::CommitmentFee, *amount),
+            LedgerEntry::FeeAccrued { amount, .. } => (EventKind::ServicingFee, *amount),
+            LedgerEntry::Drawdown { amount, .. } => (EventKind::Drawdown, *amount),
+            LedgerEntry::SplitOff { principal, interest, .. } => (EventKind::SplitOff, *principal + *interest),
+            LedgerEntry::WrittenOff { penalties, fees, interest, principal, .. } => {
+                (EventKind::WriteOff, *penalties + *fees + *interest + *principal)
+            }
+        }
+    }
+}
+
+/// What a typed contract event records.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    Initiation,                        // The contract started; amount is the principal
+    InterestAccrual,                   // Interest was charged
+    NegativeInterest,                  // Negative interest was credited to the borrower
+    Penalty,                           // A penalty was charged
+    Repayment,                         // A payment was received; amount is the total applied
+    ReserveDraw,                       // Interest was paid from the interest reserve
+    Capitalization,                    // Accrued interest was added to principal
+    InterestForgiveness,               // Interest was forgiven under a settlement quote
+    CommitmentFee,                     // A commitment fee was charged on the undrawn line
+    ServicingFee,                      // A servicing fee was charged for a fee cycle
+    Drawdown,                          // Additional principal was drawn
+    SplitOff,                          // Principal and interest moved to a new contract
+    WriteOff,                          // Part of a defaulted claim was recognized as a loss
+    InterestPayment,                   // Interest projected to be paid, in a cash-flow projection
+}
+
+/// A contract event scheduled for a future date, with its ACTUS event type.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScheduledEventType {
+    InitialExchange,                   // IED: the principal is exchanged and accrual starts
+    InterestPayment,                   // IP: an interest payment falls due
+    Capitalization,                    // IPCI: accrued interest is folded into principal
+    RateReset,                         // RR: the rate is reset from the reference rate
+    RateStep,                          // RRF: a pre-agreed rate step takes effect
+    CallDue,                           // MD: the called amount falls due
+    Maturity,                          // MD: a contract converted to a fixed term falls due
+    Termination,                       // TD: the borrower's repayment notice runs out
+    Penalty,                           // PY: penalties start accruing on the overdue balance
+}
+
+/// A contract event on the schedule generated from the contract terms.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduledEvent {
+    pub date: i64,                     // When the event falls, as a Unix timestamp
+    pub event_type: ScheduledEventType, // What happens on that date
+}
+
+/// A balance-changing event in a form downstream accounting can reconcile.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ContractEvent {
+    pub id: u64,                       // Position of the event in the ledger
+    pub timestamp: i64,                // When the event happened, as a Unix timestamp
+    pub kind: EventKind,               // What happened
+    pub amount: Decimal,               // The amount moved by the event
+    pub balance_after: Decimal,        // Principal, interest and penalties outstanding afterwards
+}
+
+/// A demand for part of the principal, falling due on its own date.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallNotice {
+    pub amount: Decimal,               // Principal demanded and not yet repaid
+    pub called_at: Instant,            // When the notice was given
+    pub due_date: Instant,             // When the demanded principal falls due
+}
+
+/// The financial terms in force when the contract was last called, kept as evidence.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct CallSnapshot {
+    pub timestamp: Instant,            // When the call was made
+    pub principal: Decimal,            // Outstanding principal at the call
+    pub accrued_interest: Decimal,     // Interest accrued up to the call
+    pub accrued_penalties: Decimal,    // Penalties outstanding at the call
+    pub interest_rate: Decimal,        // Rate in effect at the call
+    pub rate_schedule: Vec<(Instant, Decimal)>, // Scheduled rates still to take effect
+    pub penalty_type: PenaltyType,     // How penalties are charged
+    pub penalty_rate: Decimal,         // Flat penalty rate
+    pub penalty_tiers: Vec<(i64, Decimal)>, // Tiered penalty rates by days overdue
+    pub due_date: Option<Instant>,     // When the called amount falls due; None while awaiting acknowledgment
+}
+
+/// A repayment made in the settlement resource, recorded in both currencies.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct SettlementRecord {
+    pub timestamp: Instant,            // When the payment was applied
+    pub settled_amount: Decimal,       // Kept in the settlement resource, withheld tax included
+    pub conversion_rate: Decimal,      // Units of the denomination per unit of the settlement resource
+    pub denominated_amount: Decimal,   // Applied to the loan, in the denomination
+}
+
+/// One of the two parties to the contract.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractParty {
+    Lender,                            // Holder of the creditor note
+    Borrower,                          // Holder of the borrower badge
+}
+
+/// Renegotiated terms; a term left as None stays as it is.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Amendment {
+    pub interest_rate: Option<Decimal>, // New annual interest rate
+    pub notice_period: Option<i64>,    // New notice period in seconds, for calls made afterwards
+    pub grace_period: Option<i64>,     // New grace period in seconds after the due date
+    pub penalty_rate: Option<Decimal>, // New flat penalty rate
+}
+
+/// An amendment awaiting the counterparty's acceptance.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingAmendment {
+    pub terms: Amendment,              // The proposed changes
+    pub proposed_by: ContractParty,    // The party that proposed them
+    pub proposed_at: Instant,          // When they were proposed
+}
+
+/// The realized economics of a contract over its life.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ContractEconomics {
+    pub as_of: i64,                    // Date the figures are reported at
+    pub total_interest_accrued: Decimal, // Interest charged over the life of the contract
+    pub total_interest_paid: Decimal,  // Interest and commitment fees paid, including from the reserve
+    pub total_penalties_accrued: Decimal, // Penalties charged over the life of the contract
+    pub total_penalties_paid: Decimal, // Penalties paid
+    pub total_fees_accrued: Decimal,   // Commitment, origination and servicing fees charged
+    pub total_fees_paid: Decimal,      // Origination and servicing fees paid
+    pub total_principal_repaid: Decimal, // Principal repaid
+    pub total_written_off: Decimal,    // Claim recognized as a loss after a default
+    pub weighted_average_rate: Decimal, // Interest rate averaged over time from the start date
+    pub annualized_yield: Decimal,     // Approximate yield on the original principal, see `get_economics`
+}
+
+/// The balances re-derived from the ledger alongside the live balances.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ReplayResult {
+    pub matches: bool,                 // Whether every replayed balance is within tolerance of the live one
+    pub replayed_principal: Decimal,
+    pub stored_principal: Decimal,
+    pub replayed_accrued_interest: Decimal,
+    pub stored_accrued_interest: Decimal,
+    pub replayed_accrued_penalties: Decimal,
+    pub stored_accrued_penalties: Decimal,
+}
+
+/// The data of a creditor note: the transferable claim on a contract's lender side.
+#[derive(ScryptoSbor, NonFungibleData, Clone, Debug)]
+pub struct CreditorNote {
+    pub contract_id: String,           // Contract the note is a claim on
+    pub principal: Decimal,            // Principal lent when the note was minted
+}
+
+/// The data of a payment receipt: verifiable proof that a payment was applied to a contract.
+#[derive(ScryptoSbor, NonFungibleData, Clone, Debug)]
+pub struct PaymentReceipt {
+    pub contract_id: String,           // Contract the payment was made on
+    pub event_id: u64,                 // Id of the payment in the contract's events
+    pub timestamp: i64,                // When the payment was applied, as a Unix timestamp
+    pub amount: Decimal,               // Amount applied to the loan, in the principal currency
+    pub remaining_principal: Decimal,  // Principal outstanding after the payment
+}
+
+/// XRD royalties charged per call of the high-value methods; zero leaves a method free.
+#[derive(ScryptoSbor, Clone, Debug, Default)]
+pub struct RoyaltyConfig {
+    pub call_money: Decimal,           // Charged on `call_money`
+    pub apply_penalty: Decimal,        // Charged on `apply_penalty`
+    pub rollover: Decimal,             // Charged on `rollover`
+    pub split: Decimal,                // Charged on `split`
+    pub draw_guarantee: Decimal,       // Charged on `draw_guarantee`
+}
+
+impl RoyaltyConfig {
+    /// Converts a configured XRD amount into a royalty, treating zero as free.
+    fn royalty(amount: Decimal) -> RoyaltyAmount {
+        if amount.is_zero() {
+            RoyaltyAmount::Free
+        } else {
+            RoyaltyAmount::Xrd(amount)
+        }
+    }
+}
+
+/// The side of the loan the terms are recorded from, after the ACTUS contract role
+/// (CNTRL) attribute.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ContractRole {
+    #[default]
+    RealPositionAsset,                 // RPA: the lender's side, which pays out and receives the principal
+    RealPositionLiability,             // RPL: the borrower's side, which receives and repays the principal
+}
+
+/// The terms of a Call Money contract. Attributes of the ACTUS CLM dictionary are
+/// noted with their ACTUS codes; the rest extend the standard contract.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ContractTerms {
+    pub contract_id: Option<String>,   // CID: contract identifier; defaults to a hash of the parties and start date
+    pub contract_role: ContractRole,   // CNTRL: the side of the loan the terms are recorded from
+    pub currency: ResourceAddress,     // CUR: resource the principal is lent and repaid in
+    pub lender: ResourceAddress,       // Badge of the original lender; the lender's rights are held through the creditor note
+    pub borrower: ResourceAddress,     // CPID: badge of the borrower
+    pub reference: ContractReference,  // Deal and counterparty reference data for back-office systems
+    pub interest_rate: Decimal,        // IPNR: annual interest rate
+    pub allow_negative_rate: bool,     // Whether the interest rate may be negative, down to but excluding -1
+    pub day_count: DayCountConvention, // IPCB/DCC: how accrual periods are converted to year fractions
+    pub rounding: RoundingConvention,  // How accrued interest and penalties are rounded when reported or settled
+    pub capitalization_cycle: Option<i64>, // IPCICL: seconds between capitalizations of accrued interest; None keeps simple interest
+    pub interest_payment_cycle: Option<i64>, // IPCL: seconds between interest payment dates; None leaves interest payable with the principal
+    pub interest_payment_anchor: Option<i64>, // IPANX: first interest payment date; defaults to one cycle after the start date
+    pub rate_reset: Option<RateReset>, // RRCL/RRSP/RRMLT: rate reset terms; the first reset falls one cycle after the start date
+    pub rate_cap: Option<Decimal>,     // RRLC: life cap on rates set by resets and penalty indexing
+    pub rate_floor: Option<Decimal>,   // RRLF: life floor on rates set by resets and penalty indexing
+    pub rate_schedule: Vec<(i64, Decimal)>, // Pre-agreed (effective_date, rate) steps after the start date, ascending
+    pub start_date: i64,               // IED: initial exchange date, as a Unix timestamp in seconds
+    pub notice_period: i64,            // XDN: notice period in seconds
+    pub grace_period: i64,             // Grace period in seconds after the due date before penalties apply
+    pub grace_interest: GraceInterest, // Whether regular interest runs past the due date, or gives way to the penalty rate
+    pub notice_start: NoticeStart,     // When the notice period starts
+    pub calendar: BusinessDayCalendar, // CLDR/BDC: business-day calendar for due dates and, optionally, accrual
+    pub penalty_type: PenaltyType,     // PYTP: how penalties are charged if repayment is late
+    pub penalty_rate: Decimal,         // PYRT: rate at which penalties accrue if repayment is late
+    pub penalty_tiers: Vec<(i64, Decimal)>, // Escalating (days_overdue_threshold, rate) brackets, ascending; empty applies the penalty rate
+    pub penalty_base: PenaltyBase,     // The balance penalties are charged on
+    pub prepayment: Option<PrepaymentTerms>, // Fee on principal repaid before the lender calls; None charges none
+    pub fees: Option<FeeTerms>,        // FEB/FER/FECL: origination and servicing fees; None charges none
+    pub withholding: Option<WithholdingTax>, // Tax withheld from interest paid to the lender; None withholds none
+    pub keeper_reward: Option<Decimal>, // Reward paid to whoever successfully applies a penalty, from a lender-funded vault
+    pub min_partial_repayment: Decimal, // Smallest payment accepted unless it settles the loan in full
+    pub settlement_resource: Option<ResourceAddress>, // Resource repayments may alternatively be settled in
+    pub accepted_collateral: Vec<(ResourceAddress, Decimal)>, // Resources accepted as collateral, each with its haircut
+    pub min_collateral_ratio: Decimal, // Collateral value to amount owed that partial releases must preserve
+    pub max_ltv: Decimal,              // Loan-to-value above which `check_health` makes a margin call; zero disables monitoring
+    pub close_factor: Decimal,         // Share of the amount owed a single liquidation may repay, above 0 and at most 1
+    pub liquidation_bonus: Decimal,    // Extra collateral value a third-party liquidator receives, as a share of what it repays
+    pub penalty_cap: Option<Decimal>,  // Cap on cumulative penalties, as a fraction of the principal
+    pub total_cost_cap: Option<Decimal>, // Absolute ceiling on cumulative interest, penalties and fees
+    pub max_interest_multiple: Option<Decimal>, // Ceiling on outstanding accrued interest as a multiple of the principal
+    pub royalty_config: Option<RoyaltyConfig>, // XRD royalties for the high-value methods; None leaves every method free
+    pub credit_limit: Option<Decimal>, // Committed limit up to which the borrower may draw additional principal
+    pub commitment_fee_rate: Decimal,  // Annual fee rate charged on the undrawn part of the credit limit
+    pub test_mode: bool,               // Whether `set_time_override` may replace the ledger clock; only for testing
+}
+
+impl ContractTerms {
+    /// Checks every term, for a contract lending `principal`.
+    fn validate(&self, principal: Decimal) {
+        self.reference.validate();
+        self.calendar.validate();
+        let invalid_rate = |field: &str| CallMoneyError::InvalidRate { field: field.to_string() };
+        let invalid_amount = |field: &str| CallMoneyError::InvalidAmount { field: field.to_string() };
+        let invalid_period = |field: &str| CallMoneyError::InvalidPeriod { field: field.to_string() };
+        ensure(principal > Decimal::ZERO, CallMoneyError::InvalidPrincipal);
+        ensure(self.contract_id.as_ref().is_none_or(|id| !id.is_empty()), invalid_terms("contract id cannot be empty"));
+        ensure(interest_rate_in_range(self.interest_rate, self.allow_negative_rate), invalid_rate("interest_rate"));
+        for (index, (effective_date, rate)) in self.rate_schedule.iter().enumerate() {
+            ensure(interest_rate_in_range(*rate, self.allow_negative_rate), invalid_rate("rate_schedule"));
+            ensure(*effective_date > self.start_date, invalid_terms("scheduled rates must take effect after the start date"));
+            ensure(index == 0 || self.rate_schedule[index - 1].0 < *effective_date, invalid_terms("rate schedule dates must be ascending"));
+        }
+        ensure(self.notice_period >= 0, invalid_period("notice_period"));
+        ensure(self.grace_period >= 0, invalid_period("grace_period"));
+        ensure(self.capitalization_cycle.is_none_or(|cycle| cycle > 0), invalid_period("capitalization_cycle"));
+        ensure(self.interest_payment_cycle.is_none_or(|cycle| cycle > 0), invalid_period("interest_payment_cycle"));
+        ensure(self.rate_reset.is_none_or(|reset| reset.cycle > 0), invalid_period("rate_reset"));
+        ensure(
+            self.rate_cap.is_none_or(|cap| interest_rate_in_range(cap, self.allow_negative_rate)),
+            invalid_rate("rate_cap"),
+        );
+        ensure(
+            self.rate_floor.is_none_or(|floor| interest_rate_in_range(floor, self.allow_negative_rate)),
+            invalid_rate("rate_floor"),
+        );
+        ensure(
+            self.rate_floor.zip(self.rate_cap).is_none_or(|(floor, cap)| floor <= cap),
+            invalid_terms("rate floor must not exceed the rate cap"),
+        );
+        ensure(
+            self.interest_payment_anchor.is_none_or(|anchor| self.interest_payment_cycle.is_some() && anchor > self.start_date),
+            invalid_terms("interest payment anchor requires a cycle and must fall after the start date"),
+        );
+        ensure(self.penalty_rate >= Decimal::ZERO, invalid_rate("penalty_rate"));
+        for (index, (threshold, rate)) in self.penalty_tiers.iter().enumerate() {
+            ensure(*rate >= Decimal::ZERO, invalid_rate("penalty_tiers"));
+            ensure(*threshold >= 0, invalid_period("penalty_tiers"));
+            ensure(index == 0 || self.penalty_tiers[index - 1].0 < *threshold, invalid_terms("penalty tier thresholds must be ascending"));
+        }
+        match self.penalty_type {
+            PenaltyType::DailyRate | PenaltyType::Indexed(_) => {}
+            PenaltyType::Absolute(amount) => ensure(amount > Decimal::ZERO, invalid_amount("penalty_type")),
+            PenaltyType::Relative(rate) => ensure(rate > Decimal::ZERO && rate <= Decimal::ONE, invalid_rate("penalty_type")),
+        }
+        ensure(
+            self.penalty_tiers.is_empty() || self.penalty_type == PenaltyType::DailyRate,
+            invalid_terms("penalty tiers only apply to a daily-rate penalty"),
+        );
+        if let Some(prepayment) = self.prepayment {
+            ensure(
+                match prepayment.fee {
+                    PrepaymentFee::Absolute(amount) => amount > Decimal::ZERO,
+                    PrepaymentFee::Relative(rate) => rate > Decimal::ZERO && rate < Decimal::ONE,
+                },
+                invalid_amount("prepayment"),
+            );
+            ensure(
+                prepayment.lockup_end.is_none_or(|lockup_end| lockup_end > self.start_date),
+                invalid_terms("prepayment lockup must end after the start date"),
+            );
+        }
+        ensure(
+            self.withholding.is_none_or(|withholding| withholding.rate > Decimal::ZERO && withholding.rate < Decimal::ONE),
+            invalid_rate("withholding"),
+        );
+        if let Some(fees) = self.fees {
+            ensure(fees.fee_rate >= Decimal::ZERO, invalid_rate("fees"));
+            ensure(fees.fee_cycle > 0, invalid_period("fees"));
+            let origination_fee = match fees.fee_basis {
+                FeeBasis::Absolute => fees.origination_fee,
+                FeeBasis::Notional => principal * fees.origination_fee,
+            };
+            ensure(
+                fees.origination_fee >= Decimal::ZERO && origination_fee < principal,
+                invalid_amount("fees"),
+            );
+        }
+        ensure(
+            self.keeper_reward.is_none_or(|reward| reward > Decimal::ZERO && reward < principal),
+            invalid_amount("keeper_reward"),
+        );
+        ensure(self.min_partial_repayment >= Decimal::ZERO, invalid_amount("min_partial_repayment"));
+        ensure(self.penalty_cap.is_none_or(|cap| cap >= Decimal::ZERO), invalid_amount("penalty_cap"));
+        ensure(self.total_cost_cap.is_none_or(|cap| cap >= Decimal::ZERO), invalid_amount("total_cost_cap"));
+        ensure(self.max_interest_multiple.is_none_or(|multiple| multiple > Decimal::ZERO), invalid_amount("max_interest_multiple"));
+        ensure(self.credit_limit.is_none_or(|limit| limit >= principal), invalid_amount("credit_limit"));
+        ensure(self.commitment_fee_rate >= Decimal::ZERO && self.commitment_fee_rate < Decimal::ONE, invalid_rate("commitment_fee_rate"));
+        if let Some(royalty_config) = &self.royalty_config {
+            ensure(
+                [royalty_config.call_money, royalty_config.apply_penalty, royalty_config.rollover, royalty_config.split, royalty_config.draw_guarantee]
+                    .iter()
+                    .all(|amount| *amount >= Decimal::ZERO),
+                invalid_amount("royalty_config"),
+            );
+        }
+        ensure(self.min_collateral_ratio >= Decimal::ZERO, invalid_amount("min_collateral_ratio"));
+        ensure(self.max_ltv >= Decimal::ZERO, invalid_amount("max_ltv"));
+        ensure(self.close_factor > Decimal::ZERO && self.close_factor <= Decimal::ONE, invalid_amount("close_factor"));
+        ensure(self.liquidation_bonus >= Decimal::ZERO && self.liquidation_bonus < Decimal::ONE, invalid_rate("liquidation_bonus"));
+        for (index, (resource, haircut)) in self.accepted_collateral.iter().enumerate() {
+            ensure(*haircut >= Decimal::ZERO && *haircut < Decimal::ONE, invalid_rate("accepted_collateral"));
+            ensure(
+                self.accepted_collateral[..index].iter().all(|(other, _)| other != resource),
+                invalid_terms("collateral resource listed more than once"),
+            );
+        }
+    }
+}
+
+/// Emitted when interest is charged or, under a negative rate, credited.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct InterestAccrued {
+    pub timestamp: i64,                // When interest was brought up to date
+    pub rate: Decimal,                 // Rate in effect
+    pub amount: Decimal,               // Interest charged; negative when credited
+}
+
+/// Emitted when the lender calls the money back.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct MoneyCalled {
+    pub timestamp: i64,                // When the call was made
+    pub total_due: Decimal,            // Amount owed at the call
+    pub due_date: Option<i64>,         // When it falls due; None while awaiting acknowledgment
+}
+
+/// Emitted when the lender rescinds a call.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct CallCancelled {
+    pub timestamp: i64,                // When the call was cancelled
+}
+
+/// Emitted when a payment is applied to the loan.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct RepaymentReceived {
+    pub timestamp: i64,                // When the payment was applied
+    pub payer: String,                 // Who paid, as recorded in the history
+    pub penalties_paid: Decimal,       // Applied to accrued penalties
+    pub fees_paid: Decimal,            // Applied to accrued servicing fees
+    pub interest_paid: Decimal,        // Applied to accrued interest
+    pub principal_paid: Decimal,       // Applied to principal
+    pub prepayment_fee: Decimal,       // Charged for repaying principal before a call, for the lender
+    pub tax_withheld: Decimal,         // Withheld from the interest paid, for the tax authority
+    pub excess: Decimal,               // Returned to the payer
+}
+
+/// Emitted when a penalty is charged.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct PenaltyApplied {
+    pub timestamp: i64,                // When the penalty was charged
+    pub rate: Decimal,                 // Penalty rate applied
+    pub amount: Decimal,               // Penalty charged
+}
+
+/// Emitted when collateral is pledged.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct CollateralAdded {
+    pub resource: ResourceAddress,     // Collateral resource
+    pub amount: Decimal,               // Amount pledged
+}
+
+/// Emitted when collateral is withdrawn or released.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct CollateralRemoved {
+    pub resource: ResourceAddress,     // Collateral resource
+    pub amount: Decimal,               // Amount removed
+}
+
+/// Emitted when lent funds are paid out to the borrower.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct PrincipalDrawn {
+    pub amount: Decimal,               // Amount paid out
+    pub principal: Decimal,            // Principal outstanding afterwards
+}
+
+/// Emitted when the loan-to-value ratio breaches the maximum and a margin call is made.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct UndercollateralizedEvent {
+    pub timestamp: i64,                // When the breach was detected
+    pub ltv: Decimal,                  // Loan-to-value ratio at the time
+    pub max_ltv: Decimal,              // Maximum loan-to-value allowed
+}
+
+/// Emitted when the debt is transferred to a new borrower.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct NovationCompleted {
+    pub timestamp: i64,                // When the new borrower accepted
+    pub previous_borrower: ResourceAddress, // Badge of the borrower released from the debt
+    pub new_borrower: ResourceAddress, // Badge of the borrower now bound by the debt
+}
+
+/// Emitted on every status change.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct StatusChanged {
+    pub from: ContractStatus,          // Status before the change
+    pub to: ContractStatus,            // Status after the change
+}
+
+/// Emitted when tax is withheld from an interest payment.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct TaxWithheld {
+    pub timestamp: i64,                // When the interest was paid
+    pub interest_paid: Decimal,        // Gross interest paid
+    pub net_to_lender: Decimal,        // Interest left for the lender
+    pub tax: Decimal,                  // Withheld for the tax authority
+}
+
+/// Emitted when the lender declares the borrower in default.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct DefaultDeclared {
+    pub timestamp: i64,                // When the default was declared
+    pub total_due: Decimal,            // Claim outstanding at the default
+}
+
+/// Emitted when part of a defaulted claim is written off.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct WrittenOff {
+    pub timestamp: i64,                // When the loss was recognized
+    pub amount: Decimal,               // Claim written off
+    pub remaining_claim: Decimal,      // Claim still outstanding afterwards
+}
+
+/// Emitted when the interest reserve covers fewer days of interest than the warning threshold.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct ReserveLow {
+    pub timestamp: i64,                // When the shortfall was noticed
+    pub reserve: Decimal,              // Funds left in the interest reserve
+    pub daily_interest: Decimal,       // Interest accruing per day at the current terms
+    pub days_covered: Decimal,         // Days of interest the reserve still covers
+}
+
+/// Emitted when a contract is instantiated, with the reference data the back office books it under.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct ContractInstantiated {
+    pub timestamp: i64,                // The contract's start date
+    pub component: ComponentAddress,   // The new contract component
+    pub contract_id: String,           // Unique identifier of the contract
+    pub reference: ContractReference,  // Deal and counterparty reference data
+    pub principal: Decimal,            // Principal lent
+    pub currency: ResourceAddress,     // Resource the principal is lent in
+    pub lender: ResourceAddress,       // Badge of the original lender
+    pub borrower: ResourceAddress,     // Badge of the borrower
+}
+
+/// Emitted when the guarantee is drawn to repay an overdue loan.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct GuaranteeDrawn {
+    pub timestamp: i64,                // When the guarantee was drawn
+    pub amount: Decimal,               // Drawn and applied to the loan
+    pub remaining: Decimal,            // Guarantee left afterwards
+}
+
+/// Emitted when the remaining guarantee is returned to the guarantor.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct GuaranteeReleased {
+    pub timestamp: i64,                // When the guarantee was released
+    pub amount: Decimal,               // Returned to the guarantor
+}
+
+/// Emitted when a called contract is rolled over into a new open-ended period.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct RolledOver {
+    pub timestamp: i64,                // When the contract was rolled over
+    pub capitalized: Decimal,          // Interest folded into principal at the old rate
+    pub principal: Decimal,            // Principal carried into the new period
+    pub new_rate: Decimal,             // Annual interest rate for the new period
+}
+
+/// A cap on what the borrower can be charged.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CostCap {
+    Penalty,                           // Penalties charged, as a share of the original principal
+    TotalCost,                         // Interest, penalties and fees charged together
+}
+
+/// Emitted the first time a cap is reached; nothing more accrues under it afterwards.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug)]
+pub struct CapReached {
+    pub timestamp: i64,                // When the cap was reached
+    pub cap: CostCap,                  // Which cap was reached
+    pub limit: Decimal,                // The amount the cap allows
+}
+
 // This module defines a Call Money contract blueprint.
 // Call Money is a financial instrument where the lender can demand repayment at any time.
 #[blueprint]
+#[events(
+    ContractInstantiated,
+    InterestAccrued,
+    MoneyCalled,
+    CallCancelled,
+    RepaymentReceived,
+    PenaltyApplied,
+    CollateralAdded,
+    CollateralRemoved,
+    PrincipalDrawn,
+    UndercollateralizedEvent,
+    NovationCompleted,
+    StatusChanged,
+    TaxWithheld,
+    DefaultDeclared,
+    WrittenOff,
+    ReserveLow,
+    GuaranteeDrawn,
+    GuaranteeReleased,
+    RolledOver,
+    CapReached
+)]
 mod call_money {
     /// The CallMoney struct represents the state of a Call Money contract.
     struct CallMoney {
         // Parties involved in the contract
-        lender: ResourceAddress,           // Address of the lender's account
+        lender: ResourceAddress,           // Badge of the original lender; the lender's rights follow the creditor note
+        creditor_note: NonFungibleGlobalId, // Note whose holder acts as the lender: calls, collects and manages the loan
+        owner_badge: ResourceAddress,      // Badge of the component owner: pauses, claims royalties and administers the component
         borrower: ResourceAddress,         // Address of the borrower's account
+        pending_novation: Option<ResourceAddress>, // Badge of the proposed new borrower, until it accepts
+        pending_amendment: Option<PendingAmendment>, // Renegotiated terms awaiting the counterparty's acceptance
+        reference: ContractReference,      // Deal and counterparty reference data
+        contract_id: String,               // Stable identifier for indexers, from the terms or the parties and start date
+        contract_role: ContractRole,       // Side of the loan the terms were recorded from
 
         // Financial details
         principal: Decimal,                // The original amount borrowed
+        principal_vault: Vault,            // Lent funds not yet drawn by the borrower
+        repayment_vault: Vault,            // Repayments awaiting collection by the lender, or redemption once tokenized
+        original_principal: Decimal,       // Principal at instantiation, the base for the penalty cap
         interest_rate: Decimal,            // Annual interest rate (as a decimal, e.g., 0.05 for 5%)
-        accrued_interest: Decimal,         // Interest accumulated but not yet paid
+        allow_negative_rate: bool,         // Whether interest rates may be negative, crediting the borrower
+        rate_history: Vec<(Instant, Decimal)>, // Every rate applied, with the date it took effect
+        rate_schedule: Vec<(Instant, Decimal)>, // Pre-agreed rate steps, applied as accrual passes their dates
+        accrued_interest: PreciseDecimal,  // Interest accumulated but not yet paid, at full precision
+        accrued_penalties: PreciseDecimal, // Penalties accumulated but not yet paid, at full precision
+        accrued_fees: Decimal,             // Servicing fees charged but not yet paid
 
         // Time-related fields
-        start_date: i64,                   // Unix timestamp of when the contract started
-        last_interest_calculation_date: i64, // Last date interest was calculated
+        start_date: Instant,               // When the contract started
+        last_interest_calculation_date: Instant, // Last date interest was calculated
+        last_capitalization_date: Instant, // Start of the current capitalization cycle
+        test_mode: bool,                   // Whether the ledger clock may be overridden, for testing
+        time_override: Option<Instant>,    // Time used instead of the ledger clock while set, in test mode
         notice_period: i64,                // Required notice period (in seconds) before repayment
         grace_period: i64,                 // Grace period (in seconds) after due date before penalties apply
+        grace_interest: GraceInterest,     // Treatment of regular interest once the whole loan has fallen due
+        notice_start: NoticeStart,         // Whether the notice period runs from the call or from its acknowledgment
+        calendar: BusinessDayCalendar,     // Weekends, holidays and the business-day convention
+        day_count: DayCountConvention,     // How interest and penalty periods are converted to year fractions
+        rounding: RoundingConvention,      // How precise balances are rounded when reported or settled
+        capitalization_cycle: Option<i64>, // Seconds between folding accrued interest into principal; None accrues simple interest
+        interest_payment_cycle: Option<i64>, // IPCL: seconds between interest payment dates; None for no scheduled payments
+        next_interest_payment_date: Option<Instant>, // Next interest payment date not yet met
+        rate_reset: Option<RateReset>,     // Rate reset terms; None keeps the rate fixed between scheduled steps
+        next_rate_reset_date: Option<Instant>, // When the next rate reset falls due
+        rate_cap: Option<Decimal>,         // Life cap on rates set by resets and penalty indexing
+        rate_floor: Option<Decimal>,       // Life floor on rates set by resets and penalty indexing
 
         // Contract state
-        status: String,                    // Current status of the contract (e.g., "Active", "Called", "Repaid")
+        status: ContractStatus,            // Current status of the contract
+        offer_expires_at: Option<Instant>, // Last date an offer can be accepted, while it awaits the borrower
+        call_due_date: Option<Instant>,    // When the called amount falls due, while the contract is Called
+        penalized_until: Option<Instant>,  // How far penalties have been charged, so reapplying never double-charges
+        notice_acknowledged: bool,         // Whether the borrower has acknowledged the current call
+        call_snapshot: Option<CallSnapshot>, // Terms in force at the most recent call
+        call_notices: Vec<CallNotice>,     // Outstanding partial calls, oldest first, while the rest of the principal is uncalled
+        repayment_notice: Option<Instant>, // Termination date fixed by the borrower's repayment notice
+        maturity_date: Option<Instant>,    // When a contract converted to a fixed term falls due, while it is Term
+        settlement_quote: Option<(Decimal, Instant, Decimal)>, // Payoff quote, its expiry and the lifetime interest accrued when given
+        dispute_active: bool,              // Whether a borrower dispute is currently suspending penalties
+        dispute_started_at: Instant,       // When the open dispute was raised
+        disputed_seconds: i64,             // Total seconds spent under resolved disputes, excluded from overdue time
+        paused: bool,                      // Whether the owner has frozen the contract
+        paused_at: Instant,                // When the current pause began
+        paused_seconds: i64,               // Total seconds spent under lifted pauses, excluded from overdue time
 
         // Additional features
+        penalty_type: PenaltyType,         // Whether penalties accrue at a rate or are charged per missed deadline
         penalty_rate: Decimal,             // Rate at which penalties accrue if repayment is late
-        collateral: Option<ResourceAddress>, // Optional collateral provided by the borrower
+        penalty_tiers: Vec<(i64, Decimal)>, // Escalating (days overdue threshold, rate) brackets replacing the flat rate
+        keeper_reward: Option<Decimal>,    // Reward paid to the caller of `apply_penalty` when a penalty is applied
+        keeper_reward_vault: Option<Vault>, // Lender-funded pool keeper rewards are paid from
+        poke_fee: Decimal,                 // Fee paid to the caller of `poke` for refreshing the contract state
+        maintenance_vault: Option<Vault>,  // Pool poke fees are paid from
+        penalty_base: PenaltyBase,         // Balance the penalty rate is applied to
+        prepayment: Option<PrepaymentTerms>, // Fee charged on principal repaid before the lender calls
+        fees: Option<FeeTerms>,            // Origination and servicing fee terms
+        next_fee_date: Option<Instant>,    // When the next servicing fee is charged
+        min_partial_repayment: Decimal,    // Smallest payment accepted unless it settles the loan in full
+        accepted_collateral: Vec<(ResourceAddress, Decimal)>, // Accepted collateral resources and their haircuts
+        collateral_vaults: KeyValueStore<ResourceAddress, Vault>, // Pledged collateral, one vault per accepted resource
+        min_collateral_ratio: Decimal,     // Collateral value to amount owed that partial releases must preserve
+        max_ltv: Decimal,                  // Loan-to-value above which a margin call is made; zero disables monitoring
+        close_factor: Decimal,             // Share of the amount owed a single liquidation may repay
+        liquidation_bonus: Decimal,        // Extra collateral value third-party liquidators receive, as a share of what they repay
+        collateral_oracle: Option<Global<AnyComponent>>, // Oracle collateral prices are read from
+        max_price_age: i64,                // Oldest oracle price accepted, in seconds
+
+        // Regulatory caps
+        penalty_cap: Option<Decimal>,      // Cap on cumulative penalties as a fraction of the original principal
+        total_cost_cap: Option<Decimal>,   // Absolute ceiling on cumulative interest, penalties and fees
+        max_interest_multiple: Option<Decimal>, // Ceiling on outstanding accrued interest as a multiple of the principal
+        total_interest_accrued: Decimal,   // Interest charged over the life of the contract
+        total_penalties_accrued: Decimal,  // Penalties charged over the life of the contract
+        total_fees_accrued: Decimal,       // Commitment, origination and servicing fees charged over the life of the contract
+        total_interest_paid: Decimal,      // Interest and commitment fees paid over the life of the contract
+        total_penalties_paid: Decimal,     // Penalties paid over the life of the contract
+        total_fees_paid: Decimal,          // Origination and servicing fees paid over the life of the contract
+        total_principal_repaid: Decimal,   // Principal repaid over the life of the contract
+        total_written_off: Decimal,        // Claim written off after a default
+        penalty_cap_reached: bool,         // Whether the penalty cap has been logged as reached
+        total_cost_cap_reached: bool,      // Whether the total-cost cap has been logged as reached
+
+        // Settlement
+        settlement_resource: Option<ResourceAddress>, // Optional resource repayments may be settled in
+        settlement_vault: Option<Vault>,   // Holds repayments received in the settlement resource
+        settlements: Vec<SettlementRecord>, // Every repayment made in the settlement resource
+        fx_oracle: Option<Global<AnyComponent>>, // Oracle the settlement resource's price in the denomination is read from
+        max_fx_age: i64,                   // Oldest oracle conversion rate accepted, in seconds
+
+        // Guarantee
+        guarantor_badge: Option<ResourceAddress>, // Badge identifying the guarantor, once a guarantee is posted
+        guarantee_vault: Option<Vault>,    // Guarantee funds backstopping a borrower default
+
+        // Withholding tax
+        withholding: Option<WithholdingTax>, // Rate withheld from interest payments and the authority it is held for
+        tax_vaults: KeyValueStore<ResourceAddress, Vault>, // Withheld tax awaiting collection, one vault per resource interest was paid in
+
+        // Credit line
+        credit_limit: Option<Decimal>,     // Committed limit on principal, if the borrower may draw additional amounts
+        commitment_fee_rate: Decimal,      // Annual fee rate on the undrawn part of the credit limit
+        facility_vault: Option<Vault>,     // Lender funds available for additional drawdowns
+
+        // Interest reserve
+        interest_reserve: Option<Vault>,   // Borrower-prefunded interest, drawn down as interest accrues
+        interest_payout: Option<Vault>,    // Interest paid, drawn from the reserve or serviced, awaiting collection by the lender
+        reserve_warning_days: i64,         // Days of interest the reserve should cover before warning, 0 to disable
+        reserve_warned: bool,              // Whether the current shortfall has already been reported
+
+        // Debt tokenization
+        debt_token: Option<ResourceAddress>, // Fungible claims on the lender side, once the loan is tokenized
+        debt_token_supply: Decimal,        // Debt tokens outstanding, reduced as they are redeemed
+        call_quorum: Decimal,              // Share of the debt token supply whose proof is required to call
+        participation_offer: Option<Vault>, // Debt tokens the lender offers to other lenders at par
+        syndication_proceeds: Option<Vault>, // What other lenders paid for their participations, for the lender
+
+        // Payment receipts
+        receipt_resource: Option<ResourceAddress>, // Receipt NFTs minted for payments, created with the first one
+
+        // Royalties
+        royalty_config: RoyaltyConfig,     // XRD royalties charged on the high-value methods
+
+        // Observer
+        observer: Option<ComponentAddress>, // Component notified of lifecycle transitions
+        push_to_observer: bool,            // Whether to call the observer directly instead of queueing notifications
+        pending_notifications: Vec<(Instant, ObserverEvent)>, // Notifications queued for the observer to pull
+
+        // Reference rate oracle
+        rate_oracle: Option<Global<AnyComponent>>, // Oracle benchmark rates are read from, instead of either party
+        oracle_benchmark: String,          // Benchmark read from the oracle
+        max_rate_age: i64,                 // Oldest oracle rate accepted, in seconds
 
         // Record keeping
-        transaction_history: Vec<String>,  // Log of all transactions and status changes
+        transaction_history: KeyValueStore<u64, HistoryEntry>, // Log of all transactions and status changes, keyed by sequence
+        history_start: u64,                // Sequence of the oldest entry not yet archived
+        history_count: u64,                // Sequence the next entry is logged under
+        ledger: KeyValueStore<u64, LedgerEntry>, // Every balance change, keyed by sequence, detailed enough to replay the balances
+        contract_events: KeyValueStore<u64, ContractEvent>, // Each ledger entry as a typed event, under the same sequence
+        events_by_kind: KeyValueStore<(EventKind, u64), u64>, // Sequence of each event, keyed by its kind and its position among events of that kind
+        ledger_count: u64,                 // Sequence the next ledger entry is recorded under
+        event_kind_counts: HashMap<EventKind, u64>, // Number of events recorded of each kind
+        replayed_balances: core_math::Balances, // The balances re-derived entry by entry as the ledger grows
+        on_time_payments: u32,             // Payments made by their due date
+        late_payments: u32,                // Payments made after their due date
+        last_payment: Option<u64>,         // Sequence of the most recent payment in the ledger
+        snapshots: KeyValueStore<Instant, BalanceSnapshot>, // End-of-day balances keyed by day boundary
     }
 
     impl CallMoney {
         /// Instantiates a new Call Money contract.
         /// 
         /// # Arguments
-        /// * `lender` - ResourceAddress of the lender
-        /// * `borrower` - ResourceAddress of the borrower
-        /// * `principal` - The amount being borrowed
-        /// * `interest_rate` - Annual interest rate (as a decimal)
-        /// * `start_date` - Unix timestamp of the contract start date
-        /// * `notice_period` - Required notice period in seconds
-        /// * `grace_period` - Grace period in seconds
-        /// * `penalty_rate` - Rate at which penalties accrue if repayment is late
-        ///
-        /// # Returns
-        /// A tuple containing the ComponentAddress of the new contract and an owner_badge Bucket
-        pub fn instantiate_call_money(
-            lender: ResourceAddress,
-            borrower: ResourceAddress,
-            principal: Decimal,
-            interest_rate: Decimal,
-            start_date: i64,
-            notice_period: i64,
-            grace_period: i64,
-            penalty_rate: Decimal,
-        ) -> Global<CallMoney> {
+        /// * `terms` - The contract terms, validated before anything is created
+        /// * `principal_funds` - The funds lent, in the terms' currency; their amount is the principal, held by the contract until the borrower draws them
+        ///
+        /// # Returns
+        /// A tuple of the new contract, its creditor note and its owner badge, for the lender
+        pub fn instantiate_call_money(terms: ContractTerms, principal_funds: Bucket) -> (Global<CallMoney>, Bucket, Bucket) {
+            // The principal is whatever the lender puts up
+            let principal = principal_funds.amount();
+            let (contract, creditor_note, owner_badge) = Self::new_contract(terms, principal, principal_funds);
+            (Self::globalize_contract(contract), creditor_note, owner_badge)
+        }
+
+        /// Offers a Call Money contract to the borrower. The contract only binds the
+        /// borrower once accepted; until then the lent funds stay locked in it and the
+        /// lender may cancel the offer.
+        ///
+        /// # Arguments
+        /// * `terms` - The contract terms, validated before anything is created
+        /// * `principal_funds` - The funds lent, in the terms' currency; their amount is the principal
+        /// * `expires_at` - The last date the borrower can accept, as a Unix timestamp
+        ///
+        /// # Returns
+        /// A tuple of the offered contract, its creditor note and its owner badge, for the lender
+        pub fn create_offer(terms: ContractTerms, principal_funds: Bucket, expires_at: i64) -> (Global<CallMoney>, Bucket, Bucket) {
+            let principal = principal_funds.amount();
+            let (mut contract, creditor_note, owner_badge) = Self::new_contract(terms, principal, principal_funds);
+            ensure(expires_at > contract.now(), invalid_terms("an offer must expire in the future"));
+            contract.status = ContractStatus::Offered;
+            contract.offer_expires_at = Some(Self::to_instant(expires_at));
+            contract.log(contract.start_date, format!("Offer made. Expires at: {}", expires_at));
+            (Self::globalize_contract(contract), creditor_note, owner_badge)
+        }
+
+        /// Calculates the simple interest on a principal between two dates, the same way
+        /// the contract accrues it, without touching any contract state. UIs can use it
+        /// to display live accrual between transactions.
+        ///
+        /// # Arguments
+        /// * `principal` - The balance interest accrues on
+        /// * `rate` - The annual interest rate
+        /// * `from` - The start of the period, as a Unix timestamp
+        /// * `to` - The end of the period, as a Unix timestamp
+        /// * `convention` - The day count convention
+        ///
+        /// # Returns
+        /// The interest over the period, at full precision
+        pub fn compute_interest(principal: Decimal, rate: Decimal, from: i64, to: i64, convention: DayCountConvention) -> PreciseDecimal {
+            ensure(to >= from, CallMoneyError::BackdatedTimestamp { supplied: to, minimum: from });
+            core_math::compute_interest(principal, rate, from, to, convention)
+        }
+
+        /// Validates the contract terms and builds the initial contract state, with its
+        /// creditor note and owner badge.
+        fn new_contract(terms: ContractTerms, principal: Decimal, mut principal_funds: Bucket) -> (CallMoney, Bucket, Bucket) {
             // Input validation
-            assert!(principal > Decimal::ZERO, "Principal must be positive");
-            assert!(interest_rate > Decimal::ZERO && interest_rate < Decimal::ONE, "Interest rate must be between 0 and 1");
-            assert!(notice_period >= 0, "Notice period cannot be negative");
-            assert!(grace_period >= 0, "Grace period cannot be negative");
-            assert!(penalty_rate >= Decimal::ZERO, "Penalty rate cannot be negative");
+            terms.validate(principal);
+            let denomination = principal_funds.resource_address();
+            ensure(
+                denomination == terms.currency,
+                CallMoneyError::WrongResource { expected: terms.currency, got: denomination },
+            );
+            let ContractTerms {
+                contract_id,
+                contract_role,
+                currency: _,
+                lender,
+                borrower,
+                reference,
+                interest_rate,
+                allow_negative_rate,
+                day_count,
+                rounding,
+                capitalization_cycle,
+                interest_payment_cycle,
+                interest_payment_anchor,
+                rate_reset,
+                rate_cap,
+                rate_floor,
+                rate_schedule,
+                start_date,
+                notice_period,
+                grace_period,
+                grace_interest,
+                notice_start,
+                calendar,
+                penalty_type,
+                penalty_rate,
+                penalty_tiers,
+                penalty_base,
+                prepayment,
+                fees,
+                withholding,
+                keeper_reward,
+                min_partial_repayment,
+                settlement_resource,
+                accepted_collateral,
+                min_collateral_ratio,
+                max_ltv,
+                close_factor,
+                liquidation_bonus,
+                penalty_cap,
+                total_cost_cap,
+                max_interest_multiple,
+                royalty_config,
+                credit_limit,
+                commitment_fee_rate,
+                test_mode,
+            } = terms;
+            let royalty_config = royalty_config.unwrap_or_default();
+
+            // Use the supplied id, or derive one deterministically from the parties and start date
+            let contract_id = contract_id.unwrap_or_else(|| default_contract_id(lender, borrower, start_date));
+
+            let start_date = Self::to_instant(start_date);
+
+            // The origination fee is withheld from the lent funds and left with the repayments for the lender
+            let origination_fee = fees.map_or(Decimal::ZERO, |fees| match fees.fee_basis {
+                FeeBasis::Absolute => fees.origination_fee,
+                FeeBasis::Notional => principal * fees.origination_fee,
+            });
+            ensure(
+                principal_funds.amount() >= origination_fee,
+                CallMoneyError::InvalidAmount { field: "principal_funds".to_string() },
+            );
+            let origination_fee = principal_funds.take(origination_fee);
+
+            // Create an empty vault for every accepted collateral resource
+            let collateral_vaults = KeyValueStore::new();
+            for (resource, _) in &accepted_collateral {
+                collateral_vaults.insert(*resource, Vault::new(*resource));
+            }
+
+            // The lender's side of the contract is held as a transferable note
+            let creditor_note = ResourceBuilder::new_integer_non_fungible::<CreditorNote>(OwnerRole::None)
+                .metadata(metadata! {
+                    init {
+                        "name" => "CLM Creditor Note", locked;
+                        "contract_id" => contract_id.clone(), locked;
+                    }
+                })
+                .mint_initial_supply([(NonFungibleLocalId::integer(1), CreditorNote { contract_id: contract_id.clone(), principal })]);
+
+            // A single owner badge authorizes the administrative methods
+            let owner_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata(metadata! {
+                    init {
+                        "name" => "CLM Owner Badge", locked;
+                        "contract_id" => contract_id.clone(), locked;
+                    }
+                })
+                .mint_initial_supply(1);
+
+            // The history lives outside the component state, so it can grow without bloating every call
+            let transaction_history = KeyValueStore::new();
+            transaction_history.insert(0, HistoryEntry {
+                timestamp: start_date,
+                description: "Contract initiated".to_string(),
+            });
 
             // Create the CallMoney instance
-            Self {
+            let mut contract = Self {
                 lender,
+                creditor_note: NonFungibleGlobalId::new(creditor_note.resource_address(), NonFungibleLocalId::integer(1)),
+                owner_badge: owner_badge.resource_address(),
                 borrower,
+                pending_novation: None,
+                pending_amendment: None,
+                reference,
+                contract_id,
+                contract_role,
                 principal,
+                principal_vault: Vault::with_bucket(principal_funds),
+                repayment_vault: Vault::with_bucket(origination_fee),
+                original_principal: principal,
                 interest_rate,
+                allow_negative_rate,
+                rate_history: vec![(start_date, interest_rate)],
+                rate_schedule: rate_schedule
+                    .into_iter()
+                    .map(|(effective_date, rate)| (Self::to_instant(effective_date), rate))
+                    .collect(),
                 start_date,
-                accrued_interest: Decimal::ZERO,
+                accrued_interest: PreciseDecimal::ZERO,
+                accrued_penalties: PreciseDecimal::ZERO,
+                accrued_fees: Decimal::ZERO,
                 last_interest_calculation_date: start_date,
-                status: "Active".to_string(),
+                last_capitalization_date: start_date,
+                test_mode,
+                time_override: None,
+                status: ContractStatus::Active,
+                offer_expires_at: None,
+                call_due_date: None,
+                penalized_until: None,
+                notice_acknowledged: false,
+                call_snapshot: None,
+                call_notices: Vec::new(),
+                repayment_notice: None,
+                maturity_date: None,
+                settlement_quote: None,
+                dispute_active: false,
+                dispute_started_at: start_date,
+                disputed_seconds: 0,
+                paused: false,
+                paused_at: start_date,
+                paused_seconds: 0,
                 notice_period,
                 grace_period,
+                grace_interest,
+                notice_start,
+                calendar,
+                day_count,
+                rounding,
+                capitalization_cycle,
+                interest_payment_cycle,
+                next_interest_payment_date: interest_payment_cycle.map(|cycle| {
+                    Self::to_instant(interest_payment_anchor.unwrap_or(start_date.seconds_since_unix_epoch + cycle))
+                }),
+                rate_reset,
+                next_rate_reset_date: rate_reset.map(|reset| Self::to_instant(start_date.seconds_since_unix_epoch + reset.cycle)),
+                rate_cap,
+                rate_floor,
+                penalty_type,
                 penalty_rate,
-                collateral: None,
-                transaction_history: vec!["Contract initiated".to_string()],
-            }.instantiate()
-            .prepare_to_globalize(OwnerRole::None)
-            .globalize()
+                penalty_tiers,
+                keeper_reward,
+                keeper_reward_vault: None,
+                poke_fee: Decimal::ZERO,
+                maintenance_vault: None,
+                penalty_base,
+                prepayment,
+                fees,
+                next_fee_date: fees.map(|fees| Self::to_instant(start_date.seconds_since_unix_epoch + fees.fee_cycle)),
+                min_partial_repayment,
+                accepted_collateral,
+                collateral_vaults,
+                min_collateral_ratio,
+                max_ltv,
+                close_factor,
+                liquidation_bonus,
+                collateral_oracle: None,
+                max_price_age: 0,
+                penalty_cap,
+                total_cost_cap,
+                max_interest_multiple,
+                total_interest_accrued: Decimal::ZERO,
+                total_penalties_accrued: Decimal::ZERO,
+                total_fees_accrued: Decimal::ZERO,
+                total_interest_paid: Decimal::ZERO,
+                total_penalties_paid: Decimal::ZERO,
+                total_fees_paid: Decimal::ZERO,
+                total_principal_repaid: Decimal::ZERO,
+                total_written_off: Decimal::ZERO,
+                penalty_cap_reached: false,
+                total_cost_cap_reached: false,
+                settlement_resource,
+                settlement_vault: settlement_resource.map(Vault::new),
+                settlements: Vec::new(),
+                fx_oracle: None,
+                max_fx_age: 0,
+                guarantor_badge: None,
+                guarantee_vault: None,
+                withholding,
+                tax_vaults: KeyValueStore::new(),
+                credit_limit,
+                commitment_fee_rate,
+                facility_vault: None,
+                interest_reserve: None,
+                interest_payout: None,
+                reserve_warning_days: 0,
+                reserve_warned: false,
+                debt_token: None,
+                debt_token_supply: Decimal::ZERO,
+                call_quorum: Decimal::ZERO,
+                participation_offer: None,
+                syndication_proceeds: None,
+                receipt_resource: None,
+                royalty_config,
+                observer: None,
+                push_to_observer: false,
+                pending_notifications: Vec::new(),
+                rate_oracle: None,
+                oracle_benchmark: String::new(),
+                max_rate_age: 0,
+                transaction_history,
+                history_start: 0,
+                history_count: 1,
+                ledger: KeyValueStore::new(),
+                contract_events: KeyValueStore::new(),
+                events_by_kind: KeyValueStore::new(),
+                ledger_count: 0,
+                event_kind_counts: HashMap::new(),
+                replayed_balances: core_math::Balances::zero(),
+                on_time_payments: 0,
+                late_payments: 0,
+                last_payment: None,
+                snapshots: KeyValueStore::new(),
+            };
+            contract.record(LedgerEntry::Initiated {
+                timestamp: start_date,
+                principal,
+                accrued_interest: Decimal::ZERO,
+            });
+            let withheld = contract.repayment_vault.amount();
+            if withheld > Decimal::ZERO {
+                contract.total_fees_accrued += withheld;
+                contract.total_fees_paid += withheld;
+                contract.log(start_date, format!("Origination fee withheld: {}", withheld));
+            }
+            (contract, creditor_note.into(), owner_badge.into())
+        }
 
-            // Instantiate the component, create an owner badge, and globalize
-            // let (address, owner_badge) = Self::instantiate(call_money)
-            //     .with_owner_badge()
-            //     .globalize();
+        /// Instantiates and globalizes a contract state as a component.
+        ///
+        /// The owner badge holder owns the component and claims its royalties. State-changing
+        /// methods with a configured royalty are charged; everything else is free. The
+        /// component metadata identifies the contract, its currency, parties and origination
+        /// date to wallets and indexers, and a `ContractInstantiated` event carries the
+        /// back-office reference data.
+        fn globalize_contract(contract: CallMoney) -> Global<CallMoney> {
+            let lender = contract.lender;
+            let creditor_note = contract.creditor_note.clone();
+            let owner_badge = contract.owner_badge;
+            let config = contract.royalty_config.clone();
+            let deal_id = contract.reference.deal_id.clone();
+            let contract_id = contract.contract_id.clone();
+            let borrower = contract.borrower;
+            let currency = contract.repayment_vault.resource_address();
+            let origination_date = contract.start_date.seconds_since_unix_epoch;
+            let reference = contract.reference.clone();
+            let principal = contract.principal;
+            let component = contract.instantiate()
+                .prepare_to_globalize(OwnerRole::Fixed(rule!(require(owner_badge))))
+                .metadata(metadata! {
+                    init {
+                        "name" => "Call Money", locked;
+                        "description" => "A call money loan the lender can demand repayment of at any time", locked;
+                        "contract_type" => "CallMoney", locked;
+                        "instrument_type" => "ACTUS-CLM", locked;
+                        "contract_id" => contract_id.clone(), locked;
+                        "currency" => currency, locked;
+                        "origination_date" => origination_date, locked;
+                        "deal_id" => deal_id, locked;
+                        "lender" => lender, locked;
+                        "creditor_note" => creditor_note.resource_address(), locked;
+                        "borrower" => borrower, locked;
+                    }
+                })
+                .enable_component_royalties(component_royalties! {
+                    roles {
+                        royalty_setter => OWNER;
+                        royalty_setter_updater => rule!(deny_all);
+                        royalty_locker => OWNER;
+                        royalty_locker_updater => rule!(deny_all);
+                        royalty_claimer => OWNER;
+                        royalty_claimer_updater => rule!(deny_all);
+                    },
+                    init {
+                        update_accrued_interest => Free, locked;
+                        set_time_override => Free, locked;
+                        repay => Free, locked;
+                        request_settlement_quote => Free, locked;
+                        collect_repayments => Free, locked;
+                        tokenize => Free, locked;
+                        redeem => Free, locked;
+                        offer_participations => Free, locked;
+                        withdraw_participation_offer => Free, locked;
+                        buy_participation => Free, locked;
+                        collect_syndication_proceeds => Free, locked;
+                        repay_in_settlement_currency => Free, locked;
+                        collect_settlement_repayments => Free, locked;
+                        get_settlements => Free, locked;
+                        set_fx_oracle => Free, locked;
+                        set_guarantor => Free, locked;
+                        draw_guarantee => RoyaltyConfig::royalty(config.draw_guarantee), updatable;
+                        release_guarantee => Free, locked;
+                        declare_default => Free, locked;
+                        write_off => Free, locked;
+                        fund_facility => Free, locked;
+                        withdraw_unused_commitment => Free, locked;
+                        draw_additional => Free, locked;
+                        draw_principal => Free, locked;
+                        accept_offer => Free, locked;
+                        cancel_offer => Free, locked;
+                        fund_interest_reserve => Free, locked;
+                        interest_reserve_balance => Free, locked;
+                        set_reserve_warning_days => Free, locked;
+                        claim_accrued => Free, locked;
+                        collect_interest => Free, locked;
+                        claim_interest => Free, locked;
+                        collect_withheld_tax => Free, locked;
+                        pay_interest => Free, locked;
+                        service_interest => Free, locked;
+                        next_interest_payment_due => Free, locked;
+                        upcoming_events => Free, locked;
+                        get_schedule => Free, locked;
+                        guarantee_at_risk => Free, locked;
+                        call_money => RoyaltyConfig::royalty(config.call_money), updatable;
+                        call_partial => Free, locked;
+                        get_call_notices => Free, locked;
+                        cancel_call => Free, locked;
+                        acknowledge_call => Free, locked;
+                        give_repayment_notice => Free, locked;
+                        apply_penalty => RoyaltyConfig::royalty(config.apply_penalty), updatable;
+                        fund_keeper_rewards => Free, locked;
+                        poke => Free, locked;
+                        set_poke_fee => Free, locked;
+                        fund_maintenance => Free, locked;
+                        rollover => RoyaltyConfig::royalty(config.rollover), updatable;
+                        reset_rate => Free, locked;
+                        reset_rate_from_oracle => Free, locked;
+                        set_rate_oracle => Free, locked;
+                        convert_to_term => Free, locked;
+                        split => RoyaltyConfig::royalty(config.split), updatable;
+                        propose_novation => Free, locked;
+                        accept_novation => Free, locked;
+                        propose_amendment => Free, locked;
+                        accept_amendment => Free, locked;
+                        get_pending_amendment => Free, locked;
+                        raise_dispute => Free, locked;
+                        pause => Free, locked;
+                        unpause => Free, locked;
+                        resolve_dispute => Free, locked;
+                        add_collateral => Free, locked;
+                        withdraw_collateral => Free, locked;
+                        release_collateral => Free, locked;
+                        collateral_value => Free, locked;
+                        check_health => Free, locked;
+                        liquidate => Free, locked;
+                        set_collateral_oracle => Free, locked;
+                        collateral_amount => Free, locked;
+                        get_collateral => Free, locked;
+                        get_collateral_basket => Free, locked;
+                        record_snapshot => Free, locked;
+                        get_snapshot => Free, locked;
+                        get_snapshots_range => Free, locked;
+                        total_amount_due => Free, locked;
+                        quote_total_due => Free, locked;
+                        project_cash_flows => Free, locked;
+                        simulate_repayment => Free, locked;
+                        repayment_summary => Free, locked;
+                        get_economics => Free, locked;
+                        can_repay => Free, locked;
+                        can_call => Free, locked;
+                        actus_state => Free, locked;
+                        accrual_breakdown => Free, locked;
+                        get_details => Free, locked;
+                        get_details_tuple => Free, locked;
+                        get_accrued_balances => Free, locked;
+                        get_summary => Free, locked;
+                        set_observer => Free, locked;
+                        pending_notifications => Free, locked;
+                        clear_notifications => Free, locked;
+                        get_contract_id => Free, locked;
+                        get_terms => Free, locked;
+                        export_actus_terms => Free, locked;
+                        export_actus_state => Free, locked;
+                        get_creditor_note => Free, locked;
+                        get_reference_data => Free, locked;
+                        update_reference_data => Free, locked;
+                        get_royalty_config => Free, locked;
+                        archive_history => Free, locked;
+                        replay_check => Free, locked;
+                        get_events => Free, locked;
+                        get_ledger => Free, locked;
+                        get_call_snapshot => Free, locked;
+                        get_rate_history => Free, locked;
+                        get_transaction_history => Free, locked;
+                        get_history_page => Free, locked;
+                        get_history_by_kind => Free, locked;
+                    }
+                })
+                .globalize();
+            Runtime::emit_event(ContractInstantiated {
+                timestamp: origination_date,
+                component: component.address(),
+                contract_id,
+                reference,
+                principal,
+                currency,
+                lender,
+                borrower,
+            });
+            component
+        }
 
-            // Return the component address and owner badge
-            // (address, owner_badge)
+        /// Updates the accrued interest for the time passed since the last calculation,
+        /// up to the ledger clock.
+        pub fn update_accrued_interest(&mut self) {
+            self.ensure_not_paused();
+            let now = self.now();
+            self.accrue_interest(now);
         }
 
-        /// Updates the accrued interest based on the time passed since the last calculation.
+        /// Overrides the ledger clock for the time-based methods, or restores it with
+        /// `None`. Only available to the owner, on contracts instantiated in test mode.
         ///
         /// # Arguments
-        /// * `current_date` - The current date as a Unix timestamp
-        pub fn update_accrued_interest(&mut self, current_date: i64) {
-            // Calculate the number of days since the last interest calculation
-            let days = (current_date - self.last_interest_calculation_date) as i128;
-            
-            // Calculate the interest accrued over this period
-            let interest = self.principal * self.interest_rate * Decimal::from(days) / Decimal::from(365);
-            
-            // Add the calculated interest to the accrued interest
-            self.accrued_interest += interest;
+        /// * `time` - The time to use, as a Unix timestamp
+        pub fn set_time_override(&mut self, time: Option<i64>) {
+            self.require_owner();
+            ensure(self.test_mode, invalid_terms("the clock can only be overridden in test mode"));
+            self.time_override = time.map(Self::to_instant);
+        }
+
+        /// Accrues interest for the time passed since the last calculation up to `current_date`.
+        /// With a capitalization cycle, the accrued interest is folded into principal at
+        /// each cycle date passed, so later periods accrue on it.
+        ///
+        /// Dates earlier than the last calculation date are rejected rather than
+        /// accruing negative interest.
+        fn accrue_interest(&mut self, current_date: i64) {
+            // Nothing accrues on an offer the borrower has not accepted
+            if self.status == ContractStatus::Offered {
+                return;
+            }
+            if let Some(cycle) = self.capitalization_cycle {
+                let mut next_capitalization = self.last_capitalization_date.seconds_since_unix_epoch + cycle;
+                while next_capitalization <= current_date && !self.status.is_terminal() {
+                    self.accrue_period(Self::to_instant(next_capitalization));
+                    let capitalized = self.capitalize_interest();
+                    self.last_capitalization_date = Self::to_instant(next_capitalization);
+                    self.log(self.last_interest_calculation_date, format!("Interest capitalized: {}", capitalized));
+                    next_capitalization += cycle;
+                }
+            }
+            self.accrue_period(Self::to_instant(current_date));
+            self.accrue_fees(current_date);
+            self.check_reserve_level();
+        }
+
+        /// Charges the servicing fee for every fee cycle date passed up to `current_date`
+        /// into the accrued fees, which are settled before interest.
+        fn accrue_fees(&mut self, current_date: i64) {
+            let charges = self.fee_charges(current_date);
+            let Some((last_fee_date, _)) = charges.last().copied() else {
+                return;
+            };
+            for (fee_date, fee) in charges {
+                self.accrued_fees += fee;
+                self.total_fees_accrued += fee;
+                self.record(LedgerEntry::FeeAccrued { timestamp: fee_date, amount: fee });
+                self.log(fee_date, format!("Servicing fee charged: {}", fee));
+            }
+            let cycle = self.fees.unwrap().fee_cycle;
+            self.next_fee_date = Some(Self::to_instant(last_fee_date.seconds_since_unix_epoch + cycle));
+            self.log_caps_reached();
+        }
+
+        /// Accrues interest and commitment fees from the last calculation up to `current_date`.
+        fn accrue_period(&mut self, current_date: Instant) {
+
+            // Calculate the interest accrued since the last calculation, within the caps
+            let ceiling_headroom = self.interest_ceiling_headroom();
+            let interest = self.projected_interest(current_date);
+            let commitment_fee = self.projected_commitment_fee(current_date);
+
+            // Move onto any scheduled rates that took effect during the period
+            self.apply_scheduled_rates(current_date);
             
             // Update the last interest calculation date
             self.last_interest_calculation_date = current_date;
-            
-            // Log this transaction
-            self.transaction_history.push(format!("Interest updated: {}", interest));
+
+            // Nothing to record for a zero-rate or zero-length period
+            if interest > PreciseDecimal::ZERO {
+                // Add the calculated interest to the accrued interest; only the record is rounded
+                self.accrued_interest += interest;
+                let interest = self.round(interest);
+                self.total_interest_accrued += interest;
+                self.record(LedgerEntry::InterestAccrued {
+                    timestamp: current_date,
+                    rate: self.interest_rate,
+                    amount: interest,
+                });
+                
+                // Log this transaction
+                self.log(current_date, format!("Interest updated: {}", interest));
+                Runtime::emit_event(InterestAccrued {
+                    timestamp: current_date.seconds_since_unix_epoch,
+                    rate: self.interest_rate,
+                    amount: interest,
+                });
+            } else if interest < PreciseDecimal::ZERO {
+                // A negative rate credits the borrower: accrued interest first, then principal
+                let credit = -interest;
+                let (interest_reduction, principal_reduction) =
+                    core_math::split_negative_interest(self.round(credit), self.round(self.accrued_interest), self.principal);
+                self.accrued_interest -= credit.min(self.accrued_interest);
+                self.principal -= principal_reduction;
+                let interest = -(interest_reduction + principal_reduction);
+                self.total_interest_accrued += interest;
+                self.record(LedgerEntry::NegativeInterest {
+                    timestamp: current_date,
+                    rate: self.interest_rate,
+                    interest_reduction,
+                    principal_reduction,
+                });
+                self.log(
+                    current_date,
+                    format!("Negative interest credited: {} against interest, {} against principal", interest_reduction, principal_reduction),
+                );
+                Runtime::emit_event(InterestAccrued {
+                    timestamp: current_date.seconds_since_unix_epoch,
+                    rate: self.interest_rate,
+                    amount: interest,
+                });
+            }
+
+            // The commitment fee is payable alongside interest
+            if commitment_fee > PreciseDecimal::ZERO {
+                self.accrued_interest += commitment_fee;
+                let commitment_fee = self.round(commitment_fee);
+                self.total_fees_accrued += commitment_fee;
+                self.record(LedgerEntry::CommitmentFeeAccrued {
+                    timestamp: current_date,
+                    rate: self.commitment_fee_rate,
+                    amount: commitment_fee,
+                });
+                self.log(current_date, format!("Commitment fee accrued: {}", commitment_fee));
+            }
+            self.log_caps_reached();
+
+            if ceiling_headroom.is_some_and(|headroom| headroom > Decimal::ZERO)
+                && self.interest_ceiling_headroom() == Some(Decimal::ZERO)
+            {
+                self.log(current_date, "Interest ceiling reached".to_string());
+            }
+
+            // Settle the accrued interest from the reserve while it lasts
+            self.draw_interest_reserve();
         }
 
-        /// Processes a repayment on the loan.
+        /// Processes a repayment on the loan. The funds are held for the lender to
+        /// collect or, once the loan is tokenized, for debt token holders to redeem.
+        ///
+        /// Anyone may pay on behalf of the borrower. If the payer presents a badge,
+        /// the history records whether the payment came from the borrower or a third party.
+        ///
+        /// A payment in the settlement resource is converted at the FX oracle's rate.
         ///
         /// # Arguments
-        /// * `amount` - The amount being repaid
-        /// * `current_date` - The current date as a Unix timestamp
+        /// * `payment` - The repayment funds, in the principal currency or the settlement resource
+        /// * `payer` - Optional proof identifying the payer
         ///
         /// # Returns
-        /// Any excess payment that exceeds the total amount due
-        pub fn repay(&mut self, amount: Decimal, current_date: i64) -> Decimal {
-            // Update the accrued interest before processing the repayment
-            self.update_accrued_interest(current_date);
-            
-            // Calculate the total amount due
-            let total_due = self.principal + self.accrued_interest;
-            
-            if amount >= total_due {
-                // If the payment covers or exceeds the total due
-                self.status = "Repaid".to_string();
-                let excess = amount - total_due;
-                self.principal = Decimal::ZERO;
-                self.accrued_interest = Decimal::ZERO;
-                self.transaction_history.push(format!("Loan fully repaid. Excess: {}", excess));
-                excess // Return any excess payment
-            } else {
-                // If it's a partial payment
-                self.accrued_interest -= amount;
-                if self.accrued_interest < Decimal::ZERO {
-                    // If the payment exceeds the accrued interest, apply the remainder to the principal
-                    self.principal += self.accrued_interest;
-                    self.accrued_interest = Decimal::ZERO;
-                }
-                self.transaction_history.push(format!("Partial repayment: {}", amount));
-                Decimal::ZERO // No excess payment
+        /// A bucket with the part of the payment that exceeds the total amount due, or the
+        /// whole payment untouched if the contract is already closed, and the payment receipt
+        pub fn repay(&mut self, mut payment: Bucket, payer: Option<Proof>) -> (Bucket, Option<Bucket>) {
+            self.ensure_not_paused();
+            if self.check_open().is_err() {
+                return (payment, None);
+            }
+            if Some(payment.resource_address()) == self.settlement_resource {
+                let conversion_rate = self.fx_rate();
+                let excess = self.settle_payment(payment, conversion_rate, self.now(), payer);
+                return (excess, Some(self.mint_receipt()));
             }
+            let denomination = self.repayment_vault.resource_address();
+            ensure(
+                payment.resource_address() == denomination,
+                CallMoneyError::WrongResource { expected: denomination, got: payment.resource_address() },
+            );
+
+            let payer = self.payer_label(payer);
+            let (excess, interest_paid, tax_withheld) = self.apply_payment(payment.amount(), self.now(), payer);
+            let mut repaid = payment.take(payment.amount() - excess);
+            self.deposit_tax(repaid.take(tax_withheld));
+            self.deposit_interest(&mut repaid, interest_paid - tax_withheld);
+            self.repayment_vault.put(repaid);
+            (payment, Some(self.mint_receipt()))
         }
 
-        /// Initiates the process of calling the money back.
+        /// Hands the repayments received so far to the lender. Once the loan is
+        /// tokenized, repayments belong to debt token holders and are redeemed instead.
         ///
-        /// # Arguments
-        /// * `current_date` - The current date as a Unix timestamp
+        /// # Returns
+        /// The repayments received since the last collection
+        pub fn collect_repayments(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(self.debt_token.is_none(), frozen("repayments on a tokenized loan are redeemed with debt tokens"));
+            let collected = self.repayment_vault.take_all();
+            self.log(self.last_interest_calculation_date, format!("Repayments collected: {}", collected.amount()));
+            collected
+        }
+
+        /// Applies a payment to the loan: penalties first, then fees, then interest, then principal.
         ///
         /// # Returns
-        /// A tuple containing the total amount due and the due date
-        pub fn call_money(&mut self, current_date: i64) -> (Decimal, i64) {
-            assert!(self.status == "Active", "Contract is not active");
-            
-            // Update the accrued interest
-            self.update_accrued_interest(current_date);
+        /// A tuple of any excess payment that exceeds the total amount due, the interest
+        /// paid and the tax withheld from it, for the caller to route the funds
+        fn apply_payment(&mut self, amount: Decimal, current_date: i64, payer: String) -> (Decimal, Decimal, Decimal) {
+            if let Err(error) = self.check_repay(amount, current_date) {
+                error.raise();
+            }
+
+            // Update the accrued interest before processing the repayment
+            self.accrue_interest(current_date);
             
             // Calculate the total amount due
-            let total_due = self.principal + self.accrued_interest;
-            
-            // Mark the contract as called
-            self.status = "Called".to_string();
+            let mut total_due = self.total_due();
+
+            // A quote is single-use: honour it if still valid, discard it either way.
+            // Interest accrued since the quote was issued is forgiven; fees are still owed.
+            if let Some((quoted, expires_at, interest_at_quote)) = self.settlement_quote.take() {
+                let forgiven = core_math::quote_forgiveness(
+                    total_due,
+                    quoted,
+                    self.total_interest_accrued - interest_at_quote,
+                    self.interest_due(),
+                );
+                if self.last_interest_calculation_date <= expires_at && amount >= quoted && forgiven > Decimal::ZERO {
+                    self.accrued_interest -= PreciseDecimal::from(forgiven);
+                    self.record(LedgerEntry::InterestForgiven {
+                        timestamp: self.last_interest_calculation_date,
+                        amount: forgiven,
+                    });
+                    self.log(self.last_interest_calculation_date, format!("Settlement quote honoured. Interest forgiven: {}", forgiven));
+                    total_due -= forgiven;
+                }
+            }
+
+            // Reject dust partial payments; a full payoff is always accepted
+            if let Err(error) = check_minimum_repayment(amount, total_due, self.min_partial_repayment) {
+                error.raise();
+            }
+
+            // Once the borrower's notice runs out, only a full settlement is accepted
+            if self.repayment_notice.is_some_and(|termination_date| current_date >= termination_date.seconds_since_unix_epoch) {
+                ensure(amount >= total_due, CallMoneyError::BelowMinimumRepayment { amount, minimum: total_due });
+            }
             
-            // Calculate the due date
-            let due_date = current_date + self.notice_period;
+            // Settle penalties first, then fees, then interest, then principal, less any prepayment fee
+            let event = actus::ActusEvent::Repayment { date: current_date, amount };
+            let core_math::Allocation { penalties_paid, fees_paid, interest_paid, principal_paid, excess } =
+                actus::payoff(&event, &self.state_variables(), &self.actus_terms());
+            let (principal_paid, prepayment_fee, excess) = self.charge_prepayment_fee(principal_paid, excess, current_date);
+            self.transition_state(&actus::ActusEvent::Repayment {
+                date: current_date,
+                amount: penalties_paid + fees_paid + interest_paid + principal_paid,
+            });
+            self.settle_call_notices(principal_paid);
+            if prepayment_fee > Decimal::ZERO {
+                self.log(self.last_interest_calculation_date, format!("Prepayment fee charged: {}", prepayment_fee));
+            }
+
+            self.total_penalties_paid += penalties_paid;
+            self.total_fees_paid += fees_paid;
+            self.total_interest_paid += interest_paid;
+            self.total_principal_repaid += principal_paid;
+            self.record(LedgerEntry::Payment {
+                timestamp: self.last_interest_calculation_date,
+                penalties_paid,
+                fees_paid,
+                interest_paid,
+                principal_paid,
+                late: self.is_late(),
+            });
+            let tax_withheld = self.withhold_tax(interest_paid);
             
-            // Log this action
-            self.transaction_history.push(format!("Money called. Due on: {}", due_date));
+            Runtime::emit_event(RepaymentReceived {
+                timestamp: self.last_interest_calculation_date.seconds_since_unix_epoch,
+                payer: payer.clone(),
+                penalties_paid,
+                fees_paid,
+                interest_paid,
+                principal_paid,
+                prepayment_fee,
+                tax_withheld,
+                excess,
+            });
             
-            (total_due, due_date)
+            if amount - prepayment_fee >= total_due {
+                // The payment covers or exceeds the total due
+                self.transition(ContractStatus::Repaid);
+                self.log(self.last_interest_calculation_date, format!("Loan fully repaid by {}. Excess: {}", payer, excess));
+                self.notify(ObserverEvent::Repaid);
+            } else {
+                self.log(self.last_interest_calculation_date, format!("Partial repayment by {}: {}", payer, amount));
+            }
+            (excess, interest_paid, tax_withheld)
+        }
+
+        /// Works out the tax withheld from an interest payment and records both legs: the
+        /// interest net to the lender and the tax.
+        ///
+        /// # Returns
+        /// The tax withheld, in the currency the interest was paid in
+        fn withhold_tax(&mut self, interest_paid: Decimal) -> Decimal {
+            let Some(withholding) = self.withholding else {
+                return Decimal::ZERO;
+            };
+            let tax = self.round(PreciseDecimal::from(interest_paid) * PreciseDecimal::from(withholding.rate));
+            if tax == Decimal::ZERO {
+                return Decimal::ZERO;
+            }
+            let timestamp = self.last_interest_calculation_date;
+            self.log(timestamp, format!("Interest paid net to lender: {}", interest_paid - tax));
+            self.log(timestamp, format!("Withholding tax: {}", tax));
+            Runtime::emit_event(TaxWithheld {
+                timestamp: timestamp.seconds_since_unix_epoch,
+                interest_paid,
+                net_to_lender: interest_paid - tax,
+                tax,
+            });
+            tax
+        }
+
+        /// Holds withheld tax for the tax authority, in a vault for its resource.
+        fn deposit_tax(&mut self, tax: Bucket) {
+            if tax.is_empty() {
+                tax.drop_empty();
+                return;
+            }
+            let resource = tax.resource_address();
+            if self.tax_vaults.get(&resource).is_none() {
+                self.tax_vaults.insert(resource, Vault::new(resource));
+            }
+            self.tax_vaults.get_mut(&resource).unwrap().put(tax);
         }
 
-        /// Applies a penalty if the repayment is overdue.
+        /// Hands the tax withheld in a resource to the tax authority.
         ///
         /// # Arguments
-        /// * `current_date` - The current date as a Unix timestamp
-        pub fn apply_penalty(&mut self, current_date: i64) {
-            assert!(self.status == "Called", "Contract has not been called");
-            
-            // Get the due date from the call_money method
-            let (_, due_date) = self.call_money(current_date);
-            
-            // Check if we're past the grace period
-            if current_date > due_date + self.grace_period {
-                // Calculate the number of days overdue
-                let days_overdue = (current_date - (due_date + self.grace_period)) as i128;
-                
-                // Calculate the penalty
-                let penalty = self.principal * self.penalty_rate * Decimal::from(days_overdue) / Decimal::from(365);
-                
-                // Add the penalty to the accrued interest
-                self.accrued_interest += penalty;
-                
-                // Log this action
-                self.transaction_history.push(format!("Penalty applied: {}", penalty));
+        /// * `resource` - The resource the tax was withheld in
+        ///
+        /// # Returns
+        /// The withheld tax
+        pub fn collect_withheld_tax(&mut self, resource: ResourceAddress) -> Bucket {
+            self.ensure_not_paused();
+            let Some(withholding) = self.withholding else { invalid_terms("the contract withholds no tax").raise() };
+            Runtime::assert_access_rule(rule!(require(withholding.authority)));
+            let collected = self
+                .tax_vaults
+                .get_mut(&resource)
+                .unwrap_or_else(|| frozen("no tax has been withheld in this resource").raise())
+                .take_all();
+            self.log(self.last_interest_calculation_date, format!("Withheld tax collected: {}", collected.amount()));
+            collected
+        }
+
+        /// Ensures the lender may still change terms: not once the borrower has given notice.
+        fn check_terms_unlocked(&self) {
+            if let Some(termination_date) = self.repayment_notice {
+                CallMoneyError::RepaymentNoticeGiven { termination_date: termination_date.seconds_since_unix_epoch }.raise();
+            }
+        }
+
+        /// Reduces the outstanding partial calls by the principal repaid, oldest first.
+        fn settle_call_notices(&mut self, principal_paid: Decimal) {
+            let mut remaining = principal_paid;
+            for notice in self.call_notices.iter_mut() {
+                let settled = remaining.min(notice.amount);
+                notice.amount -= settled;
+                remaining -= settled;
+            }
+            self.call_notices.retain(|notice| notice.amount > Decimal::ZERO);
+        }
+
+        /// Takes the prepayment fee, if one applies at `current_date`, out of the part of a
+        /// payment left for principal.
+        ///
+        /// # Returns
+        /// The (principal_paid, prepayment_fee, excess) split
+        fn charge_prepayment_fee(&self, principal_paid: Decimal, excess: Decimal, current_date: i64) -> (Decimal, Decimal, Decimal) {
+            match self.prepayment {
+                Some(prepayment)
+                    if principal_paid > Decimal::ZERO
+                        && matches!(self.status, ContractStatus::Active | ContractStatus::MarginCall)
+                        && prepayment.lockup_end.is_none_or(|lockup_end| current_date < lockup_end)
+                        && self.repayment_notice.is_none_or(|termination_date| current_date < termination_date.seconds_since_unix_epoch) =>
+                {
+                    core_math::split_prepayment_fee(principal_paid, excess, prepayment.fee)
+                }
+                _ => (principal_paid, Decimal::ZERO, excess),
             }
         }
 
-        /// Adds collateral to the contract.
+        /// Locks the full payoff amount for a limited time. A repayment of at least the
+        /// quoted amount before the quote expires settles the contract in full, even
+        /// though a little more interest has accrued in the meantime. Only that interest
+        /// is forgiven: a servicing fee charged in the meantime is still owed.
+        ///
+        /// The quote is single-use, and is invalidated by any payment, penalty, rate
+        /// reset or change of principal before it is used.
+        ///
+        /// # Arguments
+        /// * `validity` - How long the quote is valid, in seconds
+        ///
+        /// # Returns
+        /// A tuple containing the quoted payoff amount and the expiry date
+        pub fn request_settlement_quote(&mut self, validity: i64) -> (Decimal, i64) {
+            self.ensure_not_paused();
+            let current_date = self.now();
+            ensure(self.status != ContractStatus::Repaid, CallMoneyError::AlreadyRepaid);
+            ensure(validity > 0, CallMoneyError::InvalidPeriod { field: "validity".to_string() });
+
+            self.accrue_interest(current_date);
+            let quoted = self.total_due();
+            let expires_at = current_date + validity;
+            self.settlement_quote = Some((quoted, Self::to_instant(expires_at), self.total_interest_accrued));
+            self.log(self.last_interest_calculation_date, format!("Settlement quote: {} valid until {}", quoted, expires_at));
+
+            (quoted, expires_at)
+        }
+
+        /// Splits the lender side of the loan into fungible debt tokens, one per unit
+        /// of outstanding principal, so it can be sold in pieces. Can only be done once.
         ///
         /// # Arguments
-        /// * `collateral` - The ResourceAddress of the collateral being added
-        pub fn add_collateral(&mut self, collateral: ResourceAddress) {
-            assert!(self.collateral.is_none(), "Collateral already exists");
-            self.collateral = Some(collateral);
-            self.transaction_history.push("Collateral added".to_string());
+        /// * `call_quorum` - Share of the debt token supply whose proof is required to call the loan
+        ///
+        /// # Returns
+        /// The debt tokens, for the lender
+        pub fn tokenize(&mut self, call_quorum: Decimal) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(self.debt_token.is_none(), frozen("the loan is already tokenized"));
+            ensure(self.repayment_vault.is_empty(), frozen("repayments must be collected before tokenizing"));
+            ensure(
+                self.interest_payout.as_ref().is_none_or(|payout| payout.is_empty()),
+                frozen("interest must be collected before tokenizing"),
+            );
+            ensure(self.status == ContractStatus::Active, CallMoneyError::NotActive { current: self.status });
+            ensure(
+                call_quorum > Decimal::ZERO && call_quorum <= Decimal::ONE,
+                CallMoneyError::InvalidAmount { field: "call_quorum".to_string() },
+            );
+
+            // Only this component may burn debt tokens, when they are redeemed
+            let component = Runtime::global_address();
+            let debt_tokens = ResourceBuilder::new_fungible(OwnerRole::None)
+                .metadata(metadata! {
+                    init {
+                        "name" => "CLM Debt Token", locked;
+                        "contract_id" => self.contract_id.clone(), locked;
+                    }
+                })
+                .burn_roles(burn_roles! {
+                    burner => rule!(require(global_caller(component)));
+                    burner_updater => rule!(deny_all);
+                })
+                .mint_initial_supply(self.principal);
+
+            self.debt_token = Some(debt_tokens.resource_address());
+            self.debt_token_supply = self.principal;
+            self.call_quorum = call_quorum;
+            self.log(self.last_interest_calculation_date, format!("Tokenized into {} debt tokens", self.principal));
+            debt_tokens.into()
         }
 
-        /// Removes and returns the collateral, if the loan is fully repaid.
+        /// Redeems debt tokens against the repayments and interest collected so far.
+        ///
+        /// While the loan is open, each token is redeemed at par for one unit of the
+        /// repayments, as far as they go; only the tokens paid out are burned and the rest
+        /// are returned, keeping their claim on principal repaid later. Only the burned
+        /// tokens receive their pro-rata share of the interest; the returned ones keep
+        /// their claim on it, so redeeming against empty repayments pays out nothing. Once
+        /// the contract is closed, nothing more will be repaid, so all tokens are burned for
+        /// their pro-rata share of whatever remains; the last tokens redeemed receive it all,
+        /// so no residual is left behind by rounding.
+        ///
+        /// # Arguments
+        /// * `debt_tokens` - The debt tokens to redeem
         ///
         /// # Returns
-        /// The ResourceAddress of the collateral, if it exists and the loan is repaid
-        pub fn remove_collateral(&mut self) -> Option<ResourceAddress> {
-            assert!(self.principal == Decimal::ZERO, "Loan must be fully repaid to remove collateral");
-            let collateral = self.collateral.take();
-            if collateral.is_some() {
-                self.transaction_history.push("Collateral removed".to_string());
+        /// The repayments paid out, the interest share if any has been received, and
+        /// the debt tokens not redeemed, if any
+        pub fn redeem(&mut self, mut debt_tokens: Bucket) -> Vec<Bucket> {
+            self.ensure_not_paused();
+            let Some(debt_token) = self.debt_token else { frozen("the loan has not been tokenized").raise() };
+            ensure(
+                debt_tokens.resource_address() == debt_token,
+                CallMoneyError::WrongResource { expected: debt_token, got: debt_tokens.resource_address() },
+            );
+            let presented = debt_tokens.amount();
+            ensure(presented > Decimal::ZERO, CallMoneyError::InvalidAmount { field: "debt_tokens".to_string() });
+            let supply = self.debt_token_supply;
+            let pro_rata = |vault: &mut Vault, redeemed: Decimal| {
+                if redeemed == supply {
+                    vault.take_all()
+                } else {
+                    let share = core_math::pro_rata(vault.amount(), redeemed, supply);
+                    vault.take_advanced(share, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                }
+            };
+
+            let closed = self.status.is_terminal();
+            let mut payouts = vec![if closed {
+                pro_rata(&mut self.repayment_vault, presented)
+            } else {
+                let at_par = presented.min(self.repayment_vault.amount());
+                self.repayment_vault.take_advanced(at_par, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+            }];
+
+            // Only the tokens the repayments covered are burned, and only they are paid interest
+            let redeemed = if closed { presented } else { payouts[0].amount() };
+            if let Some(interest_payout) = self.interest_payout.as_mut() {
+                payouts.push(pro_rata(interest_payout, redeemed));
             }
-            collateral
+            debt_tokens.take(redeemed).burn();
+            self.debt_token_supply -= redeemed;
+            self.log(
+                self.last_interest_calculation_date,
+                format!(
+                    "Redeemed {} debt tokens for {}",
+                    redeemed,
+                    payouts.iter().map(|payout| payout.amount().to_string()).collect::<Vec<_>>().join(" + "),
+                ),
+            );
+            if debt_tokens.amount() > Decimal::ZERO {
+                payouts.push(debt_tokens);
+            } else {
+                debt_tokens.drop_empty();
+            }
+            payouts
         }
 
-        /// Retrieves the current details of the contract.
+        /// Offers debt tokens to other lenders at par, so several lenders can fund the
+        /// position. Adds to any tokens already on offer.
+        ///
+        /// # Arguments
+        /// * `debt_tokens` - The debt tokens to offer
+        pub fn offer_participations(&mut self, debt_tokens: Bucket) {
+            self.ensure_not_paused();
+            self.require_lender();
+            let Some(debt_token) = self.debt_token else { frozen("the loan has not been tokenized").raise() };
+            ensure(
+                debt_tokens.resource_address() == debt_token,
+                CallMoneyError::WrongResource { expected: debt_token, got: debt_tokens.resource_address() },
+            );
+            self.log(self.last_interest_calculation_date, format!("Participations offered: {}", debt_tokens.amount()));
+            match self.participation_offer.as_mut() {
+                Some(offer) => offer.put(debt_tokens),
+                None => self.participation_offer = Some(Vault::with_bucket(debt_tokens)),
+            }
+        }
+
+        /// Takes back the debt tokens still on offer.
         ///
         /// # Returns
-        /// A tuple containing all the current contract details
-        pub fn get_details(&self) -> (ResourceAddress, ResourceAddress, Decimal, Decimal, i64, Decimal, String, Option<ResourceAddress>) {
-            (
-                self.lender,
-                self.borrower,
-                self.principal,
-                self.interest_rate,
-                self.start_date,
-                self.accrued_interest,
-                self.status.clone(),
-                self.collateral,
-            )
+        /// The unsold debt tokens, for the lender
+        pub fn withdraw_participation_offer(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            let unsold = self.participation_offer.as_mut().unwrap_or_else(|| frozen("no participations have been offered").raise()).take_all();
+            self.log(self.last_interest_calculation_date, format!("Participation offer withdrawn: {}", unsold.amount()));
+            unsold
         }
 
-        /// Retrieves the full transaction history of the contract.
+        /// Buys debt tokens on offer at par: one token per unit of the loan's currency.
+        /// Participants then share repayments and interest pro-rata and count toward the
+        /// call quorum.
+        ///
+        /// # Arguments
+        /// * `payment` - The payment, in the currency the loan is denominated in
+        ///
+        /// # Returns
+        /// A tuple of the debt tokens bought and the unused part of the payment
+        pub fn buy_participation(&mut self, mut payment: Bucket) -> (Bucket, Bucket) {
+            self.ensure_not_paused();
+            let denomination = self.principal_vault.resource_address();
+            ensure(
+                payment.resource_address() == denomination,
+                CallMoneyError::WrongResource { expected: denomination, got: payment.resource_address() },
+            );
+            let offer = self.participation_offer.as_mut().unwrap_or_else(|| frozen("no participations have been offered").raise());
+            let bought = payment.amount().min(offer.amount());
+            ensure(bought > Decimal::ZERO, CallMoneyError::InvalidAmount { field: "payment".to_string() });
+            let participation = offer.take(bought);
+
+            let paid = payment.take(bought);
+            match self.syndication_proceeds.as_mut() {
+                Some(proceeds) => proceeds.put(paid),
+                None => self.syndication_proceeds = Some(Vault::with_bucket(paid)),
+            }
+            self.log(self.last_interest_calculation_date, format!("Participation bought: {}", bought));
+            (participation, payment)
+        }
+
+        /// Hands what other lenders paid for their participations to the lender.
+        ///
+        /// # Returns
+        /// The proceeds received since the last collection
+        pub fn collect_syndication_proceeds(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            let collected = self.syndication_proceeds.as_mut().unwrap_or_else(|| frozen("no participations have been sold").raise()).take_all();
+            self.log(self.last_interest_calculation_date, format!("Syndication proceeds collected: {}", collected.amount()));
+            collected
+        }
+
+        /// Processes a repayment made in the settlement resource.
+        ///
+        /// The payment is converted into principal-currency terms at the FX oracle's rate
+        /// and then applied exactly like a regular repayment. Without an FX oracle the
+        /// payment is rejected.
+        ///
+        /// # Arguments
+        /// * `payment` - A bucket of the settlement resource
+        /// * `payer` - Optional proof identifying the payer
+        ///
+        /// # Returns
+        /// A bucket with the part of the payment that exceeds the total amount due, and the payment receipt
+        pub fn repay_in_settlement_currency(&mut self, payment: Bucket, payer: Option<Proof>) -> (Bucket, Bucket) {
+            self.ensure_not_paused();
+            let current_date = self.now();
+            let Some(settlement_resource) = self.settlement_resource else {
+                invalid_terms("the contract has no settlement resource").raise()
+            };
+            ensure(
+                payment.resource_address() == settlement_resource,
+                CallMoneyError::WrongResource { expected: settlement_resource, got: payment.resource_address() },
+            );
+            let conversion_rate = self.fx_rate();
+            let excess = self.settle_payment(payment, conversion_rate, current_date, payer);
+            (excess, self.mint_receipt())
+        }
+
+        /// Converts a payment in the settlement resource into principal-currency terms,
+        /// applies it and records it in both currencies.
+        ///
+        /// # Returns
+        /// A bucket with the part of the payment that exceeds the total amount due
+        fn settle_payment(&mut self, mut payment: Bucket, conversion_rate: Decimal, current_date: i64, payer: Option<Proof>) -> Bucket {
+            // Convert the payment into principal-currency terms
+            let converted_amount = payment.amount() * conversion_rate;
+            self.log(Self::to_instant(current_date), format!("Settlement payment: {} at rate {}", payment.amount(), conversion_rate));
+
+            // Apply the converted amount to the loan
+            let payer = self.payer_label(payer);
+            let (excess, _, tax_withheld) = self.apply_payment(converted_amount, current_date, payer);
+
+            // Keep the settled part, less the tax, and hand back the excess in settlement units
+            let (kept, _) = core_math::split_settlement(payment.amount(), conversion_rate, excess);
+            let mut settled = payment.take(kept);
+            self.settlements.push(SettlementRecord {
+                timestamp: self.last_interest_calculation_date,
+                settled_amount: settled.amount(),
+                conversion_rate,
+                denominated_amount: converted_amount - excess,
+            });
+            self.log(
+                self.last_interest_calculation_date,
+                format!("Settled {} in settlement resource for {} in denomination", settled.amount(), converted_amount - excess),
+            );
+            self.deposit_tax(settled.take(tax_withheld / conversion_rate));
+            self.settlement_vault.as_mut().unwrap().put(settled);
+            payment
+        }
+
+        /// Hands the repayments received in the settlement resource to the lender.
+        ///
+        /// # Returns
+        /// The settlement repayments received since the last collection
+        pub fn collect_settlement_repayments(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            let collected = self.settlement_vault.as_mut().unwrap_or_else(|| invalid_terms("the contract has no settlement resource").raise()).take_all();
+            self.log(self.last_interest_calculation_date, format!("Settlement repayments collected: {}", collected.amount()));
+            collected
+        }
+
+        /// Retrieves every repayment made in the settlement resource, in both currencies.
+        ///
+        /// # Returns
+        /// A vector of settlement records, oldest first
+        pub fn get_settlements(&self) -> Vec<SettlementRecord> {
+            self.settlements.clone()
+        }
+
+        /// Sets the oracle the settlement resource's price in the denomination is read
+        /// from, by agreement of both parties.
+        ///
+        /// # Arguments
+        /// * `oracle` - The oracle component, or `None` to stop accepting settlement payments
+        /// * `max_fx_age` - The oldest conversion rate accepted, in seconds
+        pub fn set_fx_oracle(&mut self, oracle: Option<ComponentAddress>, max_fx_age: i64) {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            ensure(self.settlement_resource.is_some(), invalid_terms("the contract has no settlement resource"));
+            ensure(max_fx_age >= 0, CallMoneyError::InvalidPeriod { field: "max_fx_age".to_string() });
+            self.fx_oracle = oracle.map(Global::<AnyComponent>::from);
+            self.max_fx_age = max_fx_age;
+            self.log(self.last_interest_calculation_date, match oracle {
+                Some(oracle) => format!("FX oracle set: {}", oracle),
+                None => "FX oracle removed".to_string(),
+            });
+        }
+
+        /// Reads the price of the settlement resource in the denomination from the FX
+        /// oracle, rejecting a missing oracle or a stale rate.
+        fn fx_rate(&self) -> Decimal {
+            let Some(oracle) = self.fx_oracle.as_ref() else { invalid_terms("the contract has no FX oracle").raise() };
+            let settlement_resource = self.settlement_resource.unwrap();
+            let (rate, published_at) = oracle.call_raw::<(Decimal, i64)>("get_price", scrypto_args!(settlement_resource));
+            ensure(
+                self.now() - published_at <= self.max_fx_age,
+                CallMoneyError::StaleOracleData { published_at, max_age: self.max_fx_age },
+            );
+            ensure(rate > Decimal::ZERO, CallMoneyError::InvalidAmount { field: "conversion_rate".to_string() });
+            rate
+        }
+
+        /// Posts a guarantee backstopping the borrower, by agreement of both parties. The
+        /// guarantee must be in the principal currency, since drawing it repays the loan
+        /// at face value.
+        ///
+        /// # Arguments
+        /// * `guarantee` - The guarantee funds, in the principal currency
+        ///
+        /// # Returns
+        /// A guarantor badge used to release the guarantee later
+        pub fn set_guarantor(&mut self, guarantee: Bucket) -> Bucket {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            ensure(self.guarantor_badge.is_none(), invalid_terms("a guarantee has already been posted"));
+            ensure(self.status != ContractStatus::Repaid, CallMoneyError::AlreadyRepaid);
+            let denomination = self.repayment_vault.resource_address();
+            ensure(
+                guarantee.resource_address() == denomination,
+                CallMoneyError::WrongResource { expected: denomination, got: guarantee.resource_address() },
+            );
+            ensure(guarantee.amount() > Decimal::ZERO, CallMoneyError::InvalidAmount { field: "guarantee".to_string() });
+
+            let guarantor_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata(metadata! {
+                    init {
+                        "name" => "Call Money Guarantor Badge", locked;
+                    }
+                })
+                .mint_initial_supply(1);
+
+            self.guarantor_badge = Some(guarantor_badge.resource_address());
+            self.log(self.last_interest_calculation_date, format!("Guarantee posted: {}", guarantee.amount()));
+            self.guarantee_vault = Some(Vault::with_bucket(guarantee));
+            guarantor_badge.into()
+        }
+
+        /// Draws the outstanding balance from the guarantee once the called amount is
+        /// overdue past the grace period, and applies it to the loan like a repayment:
+        /// the funds are held with the repayments for the lender to collect or, once the
+        /// loan is tokenized, for debt token holders to redeem.
+        ///
+        /// # Returns
+        /// The amount drawn from the guarantee
+        pub fn draw_guarantee(&mut self) -> Decimal {
+            self.ensure_not_paused();
+            self.require_lender();
+            let current_date = self.now();
+            ensure(self.is_past_grace_period(current_date), CallMoneyError::NotOverdue);
+
+            // Draw up to the full outstanding balance
+            self.accrue_interest(current_date);
+            let total_due = self.total_due();
+            let Some(vault) = self.guarantee_vault.as_mut() else {
+                invalid_terms("no guarantee has been posted").raise()
+            };
+            ensure(!vault.is_empty(), invalid_terms("the guarantee is exhausted"));
+            let mut drawn = vault.take(total_due.min(vault.amount()));
+
+            let amount = drawn.amount();
+            self.log(self.last_interest_calculation_date, format!("Guarantee drawn: {}", amount));
+            self.notify(ObserverEvent::Defaulted);
+            let (excess, interest_paid, tax_withheld) = self.apply_payment(amount, current_date, "guarantor".to_string());
+            let vault = self.guarantee_vault.as_mut().unwrap();
+            vault.put(drawn.take(excess));
+            let remaining = vault.amount();
+            self.deposit_tax(drawn.take(tax_withheld));
+            self.deposit_interest(&mut drawn, interest_paid - tax_withheld);
+            self.repayment_vault.put(drawn);
+            Runtime::emit_event(GuaranteeDrawn { timestamp: current_date, amount: amount - excess, remaining });
+            amount - excess
+        }
+
+        /// Returns the remaining guarantee to the guarantor once nothing is owed.
         ///
         /// # Returns
-        /// A vector of strings, each representing a transaction or status change
-        pub fn get_transaction_history(&self) -> Vec<String> {
-            self.transaction_history.clone()
+        /// The released guarantee funds
+        pub fn release_guarantee(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            let Some(guarantor_badge) = self.guarantor_badge else {
+                invalid_terms("no guarantee has been posted").raise()
+            };
+            Runtime::assert_access_rule(rule!(require(guarantor_badge)));
+            ensure(self.total_due() == Decimal::ZERO, CallMoneyError::CollateralLocked);
+
+            let released = self.guarantee_vault.as_mut().unwrap().take_all();
+            self.log(self.last_interest_calculation_date, format!("Guarantee released: {}", released.amount()));
+            Runtime::emit_event(GuaranteeReleased { timestamp: self.now(), amount: released.amount() });
+            released
+        }
+
+        /// Declares the borrower in default once the amount due is overdue past the grace
+        /// period. Interest is settled up to the declaration; nothing accrues afterwards.
+        pub fn declare_default(&mut self) {
+            self.ensure_not_paused();
+            self.require_lender();
+            let current_date = self.now();
+            if let Err(error) = self.check_open().and_then(|_| self.check_date(current_date)) {
+                error.raise();
+            }
+            ensure(self.is_past_grace_period(current_date), CallMoneyError::NotOverdue);
+
+            self.accrue_interest(current_date);
+            let total_due = self.total_due();
+            self.transition(ContractStatus::Defaulted);
+            self.log(self.last_interest_calculation_date, format!("Default declared. Claim outstanding: {}", total_due));
+            Runtime::emit_event(DefaultDeclared { timestamp: current_date, total_due });
+            self.notify(ObserverEvent::Defaulted);
+        }
+
+        /// Recognizes part of a defaulted claim as a loss, reducing penalties first, then
+        /// fees, then interest, then principal. The lender may also seize all pledged
+        /// collateral.
+        ///
+        /// # Arguments
+        /// * `amount` - The claim to write off; anything above the outstanding claim is ignored
+        /// * `seize_collateral` - Whether to hand all pledged collateral to the lender
+        ///
+        /// # Returns
+        /// The seized collateral, one bucket per resource held
+        pub fn write_off(&mut self, amount: Decimal, seize_collateral: bool) -> Vec<Bucket> {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(self.status == ContractStatus::Defaulted, frozen("the contract has not defaulted"));
+            ensure(amount > Decimal::ZERO, CallMoneyError::InvalidAmount { field: "amount".to_string() });
+
+            let core_math::Allocation { penalties_paid, fees_paid, interest_paid, principal_paid, .. } =
+                core_math::allocate_payment(amount, self.penalties_due(), self.accrued_fees, self.interest_due(), self.principal);
+            self.settle_penalties(penalties_paid);
+            self.accrued_fees -= fees_paid;
+            self.settle_interest(interest_paid);
+            self.principal -= principal_paid;
+            let written_off = penalties_paid + fees_paid + interest_paid + principal_paid;
+            self.total_written_off += written_off;
+            self.settlement_quote = None;
+
+            let timestamp = self.last_interest_calculation_date;
+            self.record(LedgerEntry::WrittenOff {
+                timestamp,
+                penalties: penalties_paid,
+                fees: fees_paid,
+                interest: interest_paid,
+                principal: principal_paid,
+            });
+            let remaining_claim = self.total_due();
+            self.log(timestamp, format!("Written off: {}. Remaining claim: {}", written_off, remaining_claim));
+            Runtime::emit_event(WrittenOff { timestamp: timestamp.seconds_since_unix_epoch, amount: written_off, remaining_claim });
+
+            let mut seized = Vec::new();
+            if seize_collateral {
+                for (resource, _) in self.accepted_collateral.clone() {
+                    let taken = self.collateral_vaults.get_mut(&resource).unwrap().take_all();
+                    if taken.is_empty() {
+                        taken.drop_empty();
+                        continue;
+                    }
+                    self.log(timestamp, format!("Collateral seized: {} of {:?}", taken.amount(), resource));
+                    Runtime::emit_event(CollateralRemoved { resource, amount: taken.amount() });
+                    seized.push(taken);
+                }
+            }
+            seized
+        }
+
+        /// Funds the credit line the borrower draws additional principal from.
+        ///
+        /// # Arguments
+        /// * `funds` - The funds to make available, in the principal currency
+        pub fn fund_facility(&mut self, funds: Bucket) {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(self.credit_limit.is_some(), invalid_terms("the contract has no credit line"));
+            let resource = funds.resource_address();
+            match &self.facility_vault {
+                Some(vault) => ensure(
+                    vault.resource_address() == resource,
+                    CallMoneyError::WrongResource { expected: vault.resource_address(), got: resource },
+                ),
+                None => self.facility_vault = Some(Vault::new(resource)),
+            }
+
+            self.log(self.last_interest_calculation_date, format!("Facility funded: {}", funds.amount()));
+            self.facility_vault.as_mut().unwrap().put(funds);
+        }
+
+        /// Returns the facility funds the borrower has not drawn to the lender.
+        ///
+        /// # Returns
+        /// All funds remaining in the facility vault
+        pub fn withdraw_unused_commitment(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            self.check_terms_unlocked();
+            let unused = self.facility_vault.as_mut().unwrap_or_else(|| frozen("the facility has not been funded").raise()).take_all();
+            self.log(self.last_interest_calculation_date, format!("Unused commitment withdrawn: {}", unused.amount()));
+            unused
+        }
+
+        /// Draws additional principal from the funded credit line, up to the credit limit.
+        /// Interest is settled up to the draw date first.
+        ///
+        /// # Arguments
+        /// * `amount` - The additional principal to draw
+        ///
+        /// # Returns
+        /// The drawn funds, for the borrower
+        pub fn draw_additional(&mut self, amount: Decimal) -> Bucket {
+            self.ensure_not_paused();
+            self.require_borrower();
+            let current_date = self.now();
+            let Some(credit_limit) = self.credit_limit else { invalid_terms("the contract has no credit line").raise() };
+            ensure(self.status == ContractStatus::Active, CallMoneyError::NotActive { current: self.status });
+            ensure(
+                amount > Decimal::ZERO && self.principal + amount <= credit_limit,
+                CallMoneyError::InvalidAmount { field: "amount".to_string() },
+            );
+
+            // Settle interest and the commitment fee on the old notional
+            self.accrue_interest(current_date);
+
+            let vault = self.facility_vault.as_mut().unwrap_or_else(|| frozen("the facility has not been funded").raise());
+            ensure(vault.amount() >= amount, frozen("the facility has insufficient funds"));
+            let drawn = vault.take(amount);
+
+            self.principal += amount;
+            self.settlement_quote = None;
+            self.record(LedgerEntry::Drawdown {
+                timestamp: self.last_interest_calculation_date,
+                amount,
+            });
+            self.log(self.last_interest_calculation_date, format!("Drawdown: {}. Principal now: {}", amount, self.principal));
+            Runtime::emit_event(PrincipalDrawn { amount, principal: self.principal });
+            drawn
+        }
+
+        /// Accepts the offer as the borrower, binding both parties to the terms. Nothing
+        /// accrues while the contract is on offer: an acceptance after the start date
+        /// moves the start, and every date derived from it, to the acceptance.
+        ///
+        /// # Arguments
+        /// * `borrower_proof` - Proof of the borrower's badge
+        pub fn accept_offer(&mut self, borrower_proof: Proof) {
+            self.ensure_not_paused();
+            borrower_proof.check_with_message(self.borrower, "Proof is not of the borrower's badge");
+            ensure(self.status == ContractStatus::Offered, CallMoneyError::InvalidTransition { from: self.status, to: ContractStatus::Active });
+            let accepted_at = self.now();
+            ensure(
+                self.offer_expires_at.is_none_or(|expires_at| accepted_at <= expires_at.seconds_since_unix_epoch),
+                frozen("the offer has expired"),
+            );
+
+            let delay = accepted_at - self.start_date.seconds_since_unix_epoch;
+            if delay > 0 {
+                self.shift_start(delay);
+            }
+            self.offer_expires_at = None;
+            self.transition(ContractStatus::Active);
+            self.log(Self::to_instant(accepted_at), "Offer accepted by the borrower".to_string());
+        }
+
+        /// Withdraws an offer the borrower has not accepted, expired or not, returning the
+        /// lent funds and any origination fee withheld from them.
+        ///
+        /// # Returns
+        /// The lent funds, for the lender
+        pub fn cancel_offer(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(self.status == ContractStatus::Offered, CallMoneyError::InvalidTransition { from: self.status, to: ContractStatus::Terminated });
+            let mut returned = self.principal_vault.take_all();
+            returned.put(self.repayment_vault.take_all());
+            self.offer_expires_at = None;
+            self.transition(ContractStatus::Terminated);
+            self.log(Self::to_instant(self.now()), format!("Offer cancelled. Returned: {}", returned.amount()));
+            returned
+        }
+
+        /// Moves the start date, and every date derived from it, later by `delay` seconds.
+        fn shift_start(&mut self, delay: i64) {
+            let shift = |date: Instant| Self::to_instant(date.seconds_since_unix_epoch + delay);
+            self.start_date = shift(self.start_date);
+            self.last_interest_calculation_date = shift(self.last_interest_calculation_date);
+            self.last_capitalization_date = shift(self.last_capitalization_date);
+            self.dispute_started_at = shift(self.dispute_started_at);
+            self.rate_history[0].0 = self.start_date;
+            self.next_interest_payment_date = self.next_interest_payment_date.map(shift);
+            self.next_rate_reset_date = self.next_rate_reset_date.map(shift);
+            self.next_fee_date = self.next_fee_date.map(shift);
+            if let Some(LedgerEntry::Initiated { timestamp, .. }) = self.ledger.get_mut(&0).as_deref_mut() {
+                *timestamp = self.start_date;
+            }
+            if let Some(mut event) = self.contract_events.get_mut(&0) {
+                event.timestamp = self.start_date.seconds_since_unix_epoch;
+            }
+        }
+
+        /// Pays the lent funds out to the borrower. Interest runs from the start date
+        /// whether or not the funds have been drawn.
+        ///
+        /// # Returns
+        /// The undrawn principal
+        pub fn draw_principal(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_borrower();
+            if let Err(error) = self.check_open() {
+                error.raise();
+            }
+            let drawn = self.principal_vault.take_all();
+            ensure(drawn.amount() > Decimal::ZERO, invalid_terms("the principal has already been drawn"));
+            self.log(self.last_interest_calculation_date, format!("Principal drawn: {}", drawn.amount()));
+            Runtime::emit_event(PrincipalDrawn { amount: drawn.amount(), principal: self.principal });
+            drawn
+        }
+
+        /// Deposits prefunded interest into the reserve. Accrued interest is drawn from
+        /// the reserve automatically until it is exhausted.
+        ///
+        /// # Arguments
+        /// * `funds` - The funds to add to the reserve, in the principal currency
+        pub fn fund_interest_reserve(&mut self, funds: Bucket) {
+            self.ensure_not_paused();
+            ensure(self.status != ContractStatus::Repaid, CallMoneyError::AlreadyRepaid);
+            let resource = funds.resource_address();
+            if let Some(reserve) = &self.interest_reserve {
+                ensure(
+                    reserve.resource_address() == resource,
+                    CallMoneyError::WrongResource { expected: reserve.resource_address(), got: resource },
+                );
+            } else {
+                self.interest_reserve = Some(Vault::new(resource));
+            }
+            self.ensure_interest_payout(resource);
+
+            self.log(self.last_interest_calculation_date, format!("Interest reserve funded: {}", funds.amount()));
+            self.interest_reserve.as_mut().unwrap().put(funds);
+
+            // Cover any interest that is already outstanding
+            self.draw_interest_reserve();
+            self.reserve_warned = false;
+            self.check_reserve_level();
+        }
+
+        /// Sets how many days of interest the reserve should cover. Once it covers fewer,
+        /// a `ReserveLow` event is emitted until the borrower tops it up.
+        ///
+        /// # Arguments
+        /// * `days` - The warning threshold in days, or 0 to disable the warning
+        pub fn set_reserve_warning_days(&mut self, days: i64) {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(days >= 0, CallMoneyError::InvalidPeriod { field: "reserve_warning_days".to_string() });
+            self.reserve_warning_days = days;
+            self.reserve_warned = false;
+            self.log(self.last_interest_calculation_date, format!("Reserve warning threshold set: {} days", days));
+            self.check_reserve_level();
+        }
+
+        /// Returns the funds remaining in the interest reserve.
+        pub fn interest_reserve_balance(&self) -> Decimal {
+            self.interest_reserve.as_ref().map_or(Decimal::ZERO, |vault| vault.amount())
+        }
+
+        /// Hands the interest paid, drawn from the reserve or paid through `pay_interest`
+        /// to the lender, independently of principal repayment. Once the loan is
+        /// tokenized, interest belongs to debt token holders and is redeemed instead.
+        ///
+        /// # Returns
+        /// The interest received since the last collection
+        pub fn collect_interest(&mut self) -> Bucket {
+            self.ensure_not_paused();
+            self.require_lender();
+            self.take_interest()
+        }
+
+        /// Hands the interest received so far to the lender presenting the creditor note,
+        /// like `collect_interest`.
+        ///
+        /// # Arguments
+        /// * `lender_proof` - Proof of the creditor note
+        ///
+        /// # Returns
+        /// The interest received since the last claim
+        pub fn claim_interest(&mut self, lender_proof: Proof) -> Bucket {
+            self.ensure_not_paused();
+            lender_proof.check_with_message(self.creditor_note.resource_address(), "Proof is not of the creditor note");
+            self.take_interest()
+        }
+
+        /// Streams interest to the lender: accrues up to now, settles it from the interest
+        /// reserve and hands over everything received so far.
+        ///
+        /// # Arguments
+        /// * `current_proof` - Proof of the creditor note
+        ///
+        /// # Returns
+        /// The interest accrued and received since the last claim
+        pub fn claim_accrued(&mut self, current_proof: Proof) -> Bucket {
+            self.ensure_not_paused();
+            current_proof.check_with_message(self.creditor_note.resource_address(), "Proof is not of the creditor note");
+            self.accrue_interest(self.now());
+            self.take_interest()
+        }
+
+        /// Empties the interest vault for the lender.
+        fn take_interest(&mut self) -> Bucket {
+            ensure(self.debt_token.is_none(), frozen("interest on a tokenized loan is redeemed with debt tokens"));
+            let collected =
+                self.interest_payout.as_mut().unwrap_or_else(|| frozen("no interest has been received").raise()).take_all();
+            self.log(self.last_interest_calculation_date, format!("Interest collected: {}", collected.amount()));
+            collected
+        }
+
+        /// Moves the interest part of a repayment into the interest vault, so the lender
+        /// can claim it without waiting for the principal. The interest stays with the
+        /// repayments when the interest vault holds a different resource.
+        fn deposit_interest(&mut self, funds: &mut Bucket, interest: Decimal) {
+            let resource = funds.resource_address();
+            if interest == Decimal::ZERO
+                || self.interest_payout.as_ref().is_some_and(|payout| payout.resource_address() != resource)
+            {
+                return;
+            }
+            self.ensure_interest_payout(resource);
+            self.interest_payout.as_mut().unwrap().put(funds.take(interest));
+        }
+
+        /// Pays accrued interest while the principal stays outstanding. The payment is
+        /// applied to accrued interest only, never to penalties or principal, and is held
+        /// for the lender to collect. Clearing the accrued interest meets every interest
+        /// payment date up to now.
+        ///
+        /// # Arguments
+        /// * `payment` - The interest payment, in the interest payout resource once one is set
+        ///
+        /// # Returns
+        /// A bucket with the part of the payment that exceeds the accrued interest, and the payment receipt
+        pub fn pay_interest(&mut self, mut payment: Bucket) -> (Bucket, Bucket) {
+            self.ensure_not_paused();
+            let current_date = self.now();
+            if let Err(error) = self.check_repay(payment.amount(), current_date) {
+                error.raise();
+            }
+            self.ensure_interest_payout(payment.resource_address());
+
+            // Accrue up to now, then settle as much of the interest as the payment covers
+            self.accrue_interest(current_date);
+            let (interest_paid, _) = core_math::split_interest_payment(payment.amount(), self.interest_due());
+            let mut serviced = payment.take(interest_paid);
+
+            self.settle_interest(interest_paid);
+            self.total_interest_paid += interest_paid;
+            let missed_payment_date = self.interest_payment_due_date()
+                .is_some_and(|due_date| due_date.seconds_since_unix_epoch < current_date);
+            self.record(LedgerEntry::Payment {
+                timestamp: self.last_interest_calculation_date,
+                penalties_paid: Decimal::ZERO,
+                fees_paid: Decimal::ZERO,
+                interest_paid,
+                principal_paid: Decimal::ZERO,
+                late: self.is_late() || missed_payment_date,
+            });
+            let tax_withheld = self.withhold_tax(interest_paid);
+            self.deposit_tax(serviced.take(tax_withheld));
+            self.interest_payout.as_mut().unwrap().put(serviced);
+            if self.accrued_interest == PreciseDecimal::ZERO {
+                self.advance_interest_payment_date(current_date);
+            }
+            self.log(
+                self.last_interest_calculation_date,
+                format!("Interest serviced: {}. Returned: {}", interest_paid, payment.amount()),
+            );
+            (payment, self.mint_receipt())
+        }
+
+        /// Pays accrued interest while the principal stays outstanding. Kept for callers
+        /// of the original interest-only payment; it is the same as `pay_interest`.
+        ///
+        /// # Arguments
+        /// * `payment` - The interest payment, in the interest payout resource once one is set
+        ///
+        /// # Returns
+        /// A bucket with the part of the payment that exceeds the accrued interest, and the payment receipt
+        pub fn service_interest(&mut self, payment: Bucket) -> (Bucket, Bucket) {
+            self.pay_interest(payment)
+        }
+
+        /// Mints a receipt for the most recent payment in the ledger, creating the
+        /// receipt resource with the first one.
+        ///
+        /// # Returns
+        /// A bucket with the receipt NFT
+        fn mint_receipt(&mut self) -> Bucket {
+            let event_id = self.last_payment.expect("No payment has been made");
+            let payment = self.ledger.get(&event_id).unwrap().clone();
+            let receipt = PaymentReceipt {
+                contract_id: self.contract_id.clone(),
+                event_id,
+                timestamp: payment.timestamp().seconds_since_unix_epoch,
+                amount: payment.kind_and_amount().1,
+                remaining_principal: self.principal,
+            };
+
+            // Only this component may mint receipts
+            let receipt_resource = *self.receipt_resource.get_or_insert_with(|| {
+                let component = Runtime::global_address();
+                ResourceBuilder::new_ruid_non_fungible::<PaymentReceipt>(OwnerRole::None)
+                    .metadata(metadata! {
+                        init {
+                            "name" => "CLM Payment Receipt", locked;
+                            "contract_id" => self.contract_id.clone(), locked;
+                        }
+                    })
+                    .mint_roles(mint_roles! {
+                        minter => rule!(require(global_caller(component)));
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply()
+                    .address()
+            });
+            ResourceManager::from(receipt_resource).mint_ruid_non_fungible(receipt)
+        }
+
+        /// Reports the next interest payment date not yet met and the interest that will
+        /// be owed on it. A date in the past means the payment is overdue.
+        ///
+        /// # Returns
+        /// The (due_date, interest_due) pair, or None without an interest payment cycle
+        /// or once the contract is closed
+        pub fn next_interest_payment_due(&self) -> Option<(i64, Decimal)> {
+            if self.status.is_terminal() {
+                return None;
+            }
+            self.interest_payment_due_date().map(|due_date| {
+                let as_of = due_date.max(self.last_interest_calculation_date);
+                let accrued_interest = self.accrued_interest + self.projected_interest(as_of) + self.projected_commitment_fee(as_of);
+                (due_date.seconds_since_unix_epoch, self.round(accrued_interest))
+            })
+        }
+
+        /// Lists the next occurrence of each scheduled event, earliest first. Recurring
+        /// events are reported once, for their next date only.
+        ///
+        /// # Returns
+        /// A vector of (date, event_type) pairs, or an empty vector once the contract is closed
+        pub fn upcoming_events(&self) -> Vec<(i64, ScheduledEventType)> {
+            if self.status.is_terminal() {
+                return Vec::new();
+            }
+            let capitalization_date = self.capitalization_cycle
+                .map(|cycle| Self::to_instant(self.last_capitalization_date.seconds_since_unix_epoch + cycle));
+            let rate_step_date = self.rate_schedule
+                .iter()
+                .map(|(effective_date, _)| *effective_date)
+                .find(|effective_date| *effective_date > self.last_interest_calculation_date);
+
+            let mut events: Vec<(i64, ScheduledEventType)> = [
+                (self.interest_payment_due_date(), ScheduledEventType::InterestPayment),
+                (capitalization_date, ScheduledEventType::Capitalization),
+                (self.next_rate_reset_date, ScheduledEventType::RateReset),
+                (rate_step_date, ScheduledEventType::RateStep),
+                (self.call_due_date, ScheduledEventType::CallDue),
+                (self.maturity_date, ScheduledEventType::Maturity),
+                (self.repayment_notice, ScheduledEventType::Termination),
+            ]
+            .into_iter()
+            .filter_map(|(date, event_type)| date.map(|date| (date.seconds_since_unix_epoch, event_type)))
+            .collect();
+            events.extend(self.call_notices.iter().map(|notice| (notice.due_date.seconds_since_unix_epoch, ScheduledEventType::CallDue)));
+            events.sort_by_key(|(date, _)| *date);
+            events
+        }
+
+        /// Generates the ACTUS event schedule between two dates from the contract terms.
+        /// Recurring events follow their cycles from the start date; the due date and the
+        /// start of penalties appear once the money has been called. Nothing is scheduled
+        /// after the contract closes.
+        ///
+        /// # Arguments
+        /// * `from` - Start of the window as a Unix timestamp, inclusive
+        /// * `to` - End of the window as a Unix timestamp, inclusive
+        ///
+        /// # Returns
+        /// The events in the window, earliest first
+        pub fn get_schedule(&self, from: i64, to: i64) -> Vec<ScheduledEvent> {
+            ensure(from <= to, CallMoneyError::InvalidPeriod { field: "to".to_string() });
+            schedule::generate(&self.schedule_terms(), from, to)
         }
+
+        /// Calculates the guarantor's exposure: the part of the guarantee that would be
+        /// drawn if the loan defaulted at `as_of`.
+        ///
+        /// # Arguments
+        /// * `as_of` - The date to project the outstanding balance to
+        pub fn guarantee_at_risk(&self, as_of: i64) -> Decimal {
+            let guarantee = self.guarantee_vault.as_ref().map_or(Decimal::ZERO, |vault| vault.amount());
+            let accrued_interest = self.accrued_interest + self.projected_interest(Self::to_instant(as_of));
+            let outstanding = self.principal + self.round(accrued_interest) + self.penalties_due();
+            guarantee.min(outstanding)
+        }
+
+        /// Initiates the process of calling the money back. The creditor note carries the
+        /// right to call; once the loan is tokenized, a proof of at least the call quorum
+        /// of debt tokens is required instead.
+        ///
+        /// Under `NoticeStart::Acknowledgment` the due date is only fixed once the
+        /// borrower acknowledges the call; the returned date assumes acknowledgment today.
+        ///
+        /// # Arguments
+        /// * `quorum_proof` - Proof of debt tokens, required once the loan is tokenized
+        ///
+        /// # Returns
+        /// A tuple containing the total amount due and the due date
+        pub fn call_money(&mut self, quorum_proof: Option<Proof>) -> (Decimal, i64) {
+            self.ensure_not_paused();
+            match self.debt_token {
+                Some(debt_token) => {
+                    let Some(proof) = quorum_proof else {
+                        invalid_terms("a proof of debt tokens is required to call").raise()
+                    };
+                    let presented = proof.check_with_message(debt_token, "Proof must be of the debt token").amount();
+                    ensure(
+                        presented >= self.debt_token_supply * self.call_quorum,
+                        CallMoneyError::InvalidAmount { field: "quorum_proof".to_string() },
+                    );
+                }
+                None => self.require_lender(),
+            }
+            let current_date = self.now();
+            if let Err(error) = self.check_call(current_date) {
+                error.raise();
+            }
+            
+            // Update the accrued interest
+            self.accrue_interest(current_date);
+            
+            // Calculate the total amount due
+            let total_due = self.total_due();
+            
+            // Mark the contract as called; the whole principal is now demanded
+            self.transition(ContractStatus::Called);
+            self.call_notices.clear();
+            
+            // Calculate and record the due date, unless it waits for acknowledgment
+            let due_date = self.calendar.notice_due_date(current_date, self.notice_period);
+            self.notice_acknowledged = false;
+            self.transition_state(&actus::ActusEvent::Call {
+                date: current_date,
+                due_date: match self.notice_start {
+                    NoticeStart::Call => Some(due_date),
+                    NoticeStart::Acknowledgment => None,
+                },
+            });
+            
+            // Freeze the terms in force as evidence of the call
+            self.call_snapshot = Some(CallSnapshot {
+                timestamp: self.last_interest_calculation_date,
+                principal: self.principal,
+                accrued_interest: self.interest_due(),
+                accrued_penalties: self.penalties_due(),
+                interest_rate: self.interest_rate,
+                rate_schedule: pending_steps(&self.rate_schedule, self.last_interest_calculation_date),
+                penalty_type: self.penalty_type,
+                penalty_rate: self.penalty_rate,
+                penalty_tiers: self.penalty_tiers.clone(),
+                due_date: self.call_due_date,
+            });
+            
+            // Log this action
+            self.log(self.last_interest_calculation_date, format!("Money called. Due on: {}", due_date));
+            Runtime::emit_event(MoneyCalled {
+                timestamp: self.last_interest_calculation_date.seconds_since_unix_epoch,
+                total_due,
+                due_date: self.call_due_date.map(|date| date.seconds_since_unix_epoch),
+            });
+            self.notify(ObserverEvent::Called);
+            
+            (total_due, due_date)
+        }
+
+        /// Demands part of the principal, due after the notice period, while the rest keeps
+        /// accruing as before. Several partial calls may be outstanding at once; principal
+        /// repaid settles them oldest first, and each one left unpaid past its grace period
+        /// accrues penalties on its own amount.
+        ///
+        /// # Arguments
+        /// * `amount` - The principal to demand, less than the principal not yet demanded
+        ///
+        /// # Returns
+        /// The due date of the partial call
+        pub fn call_partial(&mut self, amount: Decimal) -> i64 {
+            self.ensure_not_paused();
+            self.require_lender();
+            let current_date = self.now();
+            ensure(self.debt_token.is_none(), frozen("a tokenized loan can only be called in full"));
+            if let Err(error) = self.check_call(current_date) {
+                error.raise();
+            }
+            let demanded = self.call_notices.iter().fold(Decimal::ZERO, |total, notice| total + notice.amount);
+            ensure(
+                amount > Decimal::ZERO && demanded + amount < self.principal,
+                CallMoneyError::InvalidAmount { field: "amount".to_string() },
+            );
+
+            // Interest keeps accruing on the whole principal; bring it up to date at the call
+            self.accrue_interest(current_date);
+            let due_date = self.calendar.notice_due_date(current_date, self.notice_period);
+            self.call_notices.push(CallNotice {
+                amount,
+                called_at: self.last_interest_calculation_date,
+                due_date: Self::to_instant(due_date),
+            });
+
+            self.log(self.last_interest_calculation_date, format!("Partial call of {}. Due on: {}", amount, due_date));
+            Runtime::emit_event(MoneyCalled {
+                timestamp: self.last_interest_calculation_date.seconds_since_unix_epoch,
+                total_due: amount,
+                due_date: Some(due_date),
+            });
+            due_date
+        }
+
+        /// Retrieves the outstanding partial calls, oldest first.
+        pub fn get_call_notices(&self) -> Vec<CallNotice> {
+            self.call_notices.clone()
+        }
+
+        /// Rescinds the lender's demand before it falls due. A full call returns the
+        /// contract to Active; otherwise the partial calls not yet due are withdrawn.
+        pub fn cancel_call(&mut self) {
+            self.ensure_not_paused();
+            self.require_lender();
+            let current_date = self.now();
+            if let Err(error) = self.check_date(current_date) {
+                error.raise();
+            }
+            if self.status != ContractStatus::Called {
+                self.cancel_partial_calls(current_date);
+                return;
+            }
+            if let Err(error) = check_call_cancellable(
+                self.call_due_date.map(|due_date| due_date.seconds_since_unix_epoch),
+                self.accrued_penalties != PreciseDecimal::ZERO,
+                current_date,
+            ) {
+                error.raise();
+            }
+
+            // Disputes over the cancelled call no longer apply
+            self.call_due_date = None;
+            self.notice_acknowledged = false;
+            self.disputed_seconds = 0;
+            self.transition(ContractStatus::Active);
+            self.log(Self::to_instant(current_date), "Call cancelled".to_string());
+            Runtime::emit_event(CallCancelled { timestamp: current_date });
+        }
+
+        /// Withdraws the partial calls that have not yet fallen due.
+        fn cancel_partial_calls(&mut self, current_date: i64) {
+            let outstanding = self.call_notices.len();
+            self.call_notices.retain(|notice| notice.due_date.seconds_since_unix_epoch <= current_date);
+            let cancelled = outstanding - self.call_notices.len();
+            ensure(cancelled > 0, CallMoneyError::NotCalled { current: self.status });
+            self.log(Self::to_instant(current_date), format!("Partial calls cancelled: {}", cancelled));
+            Runtime::emit_event(CallCancelled { timestamp: current_date });
+        }
+
+        /// Gives the borrower's notice to repay, fixing a termination date after the notice
+        /// period. From that date a repayment must settle the loan in full, and no prepayment
+        /// fee is charged on it; until the loan is settled, the lender cannot change terms.
+        ///
+        /// # Returns
+        /// The termination date
+        pub fn give_repayment_notice(&mut self) -> i64 {
+            self.ensure_not_paused();
+            self.require_borrower();
+            let current_date = self.now();
+            if let Err(error) = self.check_call(current_date) {
+                error.raise();
+            }
+            if let Some(termination_date) = self.repayment_notice {
+                CallMoneyError::RepaymentNoticeGiven { termination_date: termination_date.seconds_since_unix_epoch }.raise();
+            }
+            let termination_date = self.calendar.notice_due_date(current_date, self.notice_period);
+            self.repayment_notice = Some(Self::to_instant(termination_date));
+            self.log(Self::to_instant(current_date), format!("Repayment notice given. Terminates on: {}", termination_date));
+            termination_date
+        }
+
+        /// Records the borrower's acknowledgment of the call. Under
+        /// `NoticeStart::Acknowledgment` this starts the notice period.
+        pub fn acknowledge_call(&mut self) {
+            self.ensure_not_paused();
+            self.require_borrower();
+            let current_date = self.now();
+            ensure(self.status == ContractStatus::Called, CallMoneyError::NotCalled { current: self.status });
+            ensure(!self.notice_acknowledged, frozen("the call has already been acknowledged"));
+
+            let acknowledged_at = Self::to_instant(current_date);
+            self.notice_acknowledged = true;
+            if self.notice_start == NoticeStart::Acknowledgment {
+                let due_date = self.calendar.notice_due_date(current_date, self.notice_period);
+                self.call_due_date = Some(Self::to_instant(due_date));
+                self.log(acknowledged_at, format!("Call acknowledged. Due on: {}", due_date));
+            } else {
+                self.log(acknowledged_at, "Call acknowledged".to_string());
+            }
+        }
+
+        /// Applies a penalty if the repayment is overdue. Anyone may call this; when a
+        /// keeper reward is configured and funded, the caller is paid for applying a penalty.
+        ///
+        /// The reward never exceeds the penalty it was earned on, so enforcement cannot
+        /// cost the lender more than it recovers.
+        ///
+        /// # Returns
+        /// The keeper reward, if a penalty was applied and a reward is configured
+        pub fn apply_penalty(&mut self) -> Option<Bucket> {
+            self.ensure_not_paused();
+            let current_date = self.now();
+            let penalty = self.charge_penalty(current_date);
+            let reward = self.keeper_reward?;
+            if penalty == Decimal::ZERO {
+                return None;
+            }
+            let vault = self.keeper_reward_vault.as_mut()?;
+            let paid = vault.take(keeper_payout(reward, penalty, vault.amount()));
+            self.log(Self::to_instant(current_date), format!("Keeper reward paid: {}", paid.amount()));
+            Some(paid)
+        }
+
+        /// Deposits funds the keeper rewards are paid from.
+        ///
+        /// # Arguments
+        /// * `funds` - The funds to add to the reward pool
+        pub fn fund_keeper_rewards(&mut self, funds: Bucket) {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(self.keeper_reward.is_some(), invalid_terms("no keeper reward is configured"));
+            let resource = funds.resource_address();
+            if let Some(vault) = &self.keeper_reward_vault {
+                ensure(
+                    vault.resource_address() == resource,
+                    CallMoneyError::WrongResource { expected: vault.resource_address(), got: resource },
+                );
+            } else {
+                self.keeper_reward_vault = Some(Vault::new(resource));
+            }
+            self.log(self.last_interest_calculation_date, format!("Keeper rewards funded: {}", funds.amount()));
+            self.keeper_reward_vault.as_mut().unwrap().put(funds);
+        }
+
+        /// Brings the contract state up to the ledger clock: accrues interest and fees and,
+        /// once repayment is overdue, applies the penalty. Anyone may call this; the
+        /// caller is paid the poke fee from the maintenance vault when the poke moved
+        /// accrual forward by at least a day or applied a penalty, so keepers are paid
+        /// for keeping the state fresh rather than for calling repeatedly.
+        ///
+        /// # Returns
+        /// The poke fee, if one was earned and the maintenance vault can pay it
+        pub fn poke(&mut self) -> Option<Bucket> {
+            self.ensure_not_paused();
+            let current_date = self.now();
+            if self.status.is_terminal() || self.status == ContractStatus::Offered {
+                return None;
+            }
+
+            let accrued_from = self.last_interest_calculation_date.seconds_since_unix_epoch;
+            self.accrue_interest(current_date);
+            let penalty = if self.penalties_can_accrue() {
+                self.charge_penalty(current_date)
+            } else {
+                Decimal::ZERO
+            };
+
+            if self.poke_fee == Decimal::ZERO || (current_date - accrued_from < SECONDS_PER_DAY && penalty == Decimal::ZERO) {
+                return None;
+            }
+            let vault = self.maintenance_vault.as_mut()?;
+            let paid = vault.take(self.poke_fee.min(vault.amount()));
+            self.log(Self::to_instant(current_date), format!("Poke fee paid: {}", paid.amount()));
+            Some(paid)
+        }
+
+        /// Sets the fee paid to callers of `poke`.
+        ///
+        /// # Arguments
+        /// * `fee` - The fee per rewarded poke, or zero to stop paying keepers
+        pub fn set_poke_fee(&mut self, fee: Decimal) {
+            self.ensure_not_paused();
+            self.require_lender();
+            ensure(fee >= Decimal::ZERO, CallMoneyError::InvalidAmount { field: "poke_fee".to_string() });
+            self.poke_fee = fee;
+            self.log(self.last_interest_calculation_date, format!("Poke fee set: {}", fee));
+        }
+
+        /// Deposits funds the poke fees are paid from. Anyone may fund maintenance.
+        ///
+        /// # Arguments
+        /// * `funds` - The funds to add to the maintenance vault
+        pub fn fund_maintenance(&mut self, funds: Bucket) {
+            self.ensure_not_paused();
+            let resource = funds.resource_address();
+            if let Some(vault) = &self.maintenance_vault {
+                ensure(
+                    vault.resource_address() == resource,
+                    CallMoneyError::WrongResource { expected: vault.resource_address(), got: resource },
+                );
+            } else {
+                self.maintenance_vault = Some(Vault::new(resource));
+            }
+            self.log(self.last_interest_calculation_date, format!("Maintenance funded: {}", funds.amount()));
+            self.maintenance_vault.as_mut().unwrap().put(funds);
+        }
+
+        /// Charges a penalty if the repayment is overdue. Only the overdue time not yet
+        /// penalized is charged, and a fixed penalty only once per missed deadline, so
+        /// calling this repeatedly never double-charges; the penalty bracket still follows
+        /// the total time overdue.
+        ///
+        /// # Returns
+        /// The penalty charged, zero if none was due
+        fn charge_penalty(&mut self, current_date: i64) -> Decimal {
+            ensure(self.penalties_can_accrue(), CallMoneyError::NotCalled { current: self.status });
+
+            if self.projected_penalty(current_date, self.interest_due()).is_none() {
+                return Decimal::ZERO;
+            }
+
+            // Bring interest up to date, so the penalty event starts from the current state
+            self.accrue_interest(current_date);
+            let (penalty_rate, penalty) = self.projected_penalty(current_date, self.interest_due()).unwrap();
+            self.penalized_until = Some(Self::to_instant(current_date));
+            if penalty == PreciseDecimal::ZERO {
+                return Decimal::ZERO;
+            }
+
+            // Add the penalty to the accrued penalties; only the record is rounded
+            let newly_delinquent = self.accrued_penalties == PreciseDecimal::ZERO;
+            self.transition_state(&actus::ActusEvent::Penalty { date: current_date, charge: penalty });
+            let penalty = self.round(penalty);
+            self.settlement_quote = None;
+            self.total_penalties_accrued += penalty;
+            self.record(LedgerEntry::PenaltyApplied {
+                timestamp: Self::to_instant(current_date),
+                rate: penalty_rate,
+                amount: penalty,
+            });
+            
+            // Log this action
+            self.log(Self::to_instant(current_date), format!("Penalty applied: {}", penalty));
+            Runtime::emit_event(PenaltyApplied { timestamp: current_date, rate: penalty_rate, amount: penalty });
+            if matches!(self.penalty_type, PenaltyType::Indexed(_)) {
+                self.log_rate_bound(Self::to_instant(current_date), penalty_rate);
+            }
+            self.log_caps_reached();
+            if newly_delinquent {
+                // An overdue partial call leaves the uncalled remainder in good standing
+                if matches!(self.status, ContractStatus::Called | ContractStatus::Term) {
+                    self.transition(ContractStatus::Delinquent);
+                }
+                self.notify(ObserverEvent::Delinquent);
+            }
+            penalty
+        }
+
+        /// Calculates the penalty for the overdue time not yet penalized, up to
+        /// `current_date`, without charging it.
+        ///
+        /// # Arguments
+        /// * `current_date` - The date to calculate up to, as a Unix timestamp
+        /// * `accrued_interest` - The accrued interest at that date, for a total-outstanding penalty base
+        ///
+        /// # Returns
+        /// The (rate, penalty) pair, or None while nothing is overdue or a dispute is open
+        fn projected_penalty(&self, current_date: i64, accrued_interest: Decimal) -> Option<(Decimal, PreciseDecimal)> {
+            // Penalties are suspended while a dispute is open or the contract is paused
+            if self.dispute_active || self.paused {
+                return None;
+            }
+
+            // A term contract falls due at maturity, a called one on the recorded due date and
+            // each partially called amount on its own due date; a call still awaiting
+            // acknowledgment has no due date yet
+            let due_amounts: Vec<(Instant, Decimal)> = match self.maturity_date.or(self.call_due_date) {
+                Some(due_date) => vec![(due_date, self.penalty_base_amount(accrued_interest))],
+                None => self.call_notices.iter().map(|notice| (notice.due_date, notice.amount)).collect(),
+            };
+
+            // Under the penalty-rate treatment, penalties replace interest from the whole loan's due date
+            let skip_grace = self.grace_interest.penalties_skip_grace() && self.interest_stop_date().is_some();
+
+            let mut charged: Option<(Decimal, PreciseDecimal)> = None;
+            for (due_date, base) in due_amounts {
+                // Check if we're past the grace period, not counting time spent in dispute or paused
+                let deadline = if skip_grace {
+                    due_date.seconds_since_unix_epoch
+                } else {
+                    self.grace_period_end(due_date.seconds_since_unix_epoch)
+                };
+                let Some((overdue_from, charge_from)) = core_math::penalty_period(
+                    deadline,
+                    self.disputed_seconds + self.paused_seconds,
+                    self.penalized_until.map(|until| until.seconds_since_unix_epoch),
+                    current_date,
+                ) else {
+                    continue;
+                };
+
+                // Calculate the number of whole days overdue
+                let days_overdue = (current_date - overdue_from) / SECONDS_PER_DAY;
+
+                // A fixed charge falls due once per missed deadline, when first charged after it
+                let newly_missed = self.penalized_until.is_none_or(|until| until.seconds_since_unix_epoch <= overdue_from);
+                let accrue_at = |rate: Decimal| core_math::compute_interest(base, rate, charge_from, current_date, self.day_count);
+
+                // Calculate the penalty under the penalty type; the earliest overdue amount's rate is reported
+                let (penalty_rate, penalty) = match self.penalty_type {
+                    PenaltyType::DailyRate => {
+                        let rate = self.penalty_rate_for(days_overdue);
+                        (rate, accrue_at(rate))
+                    }
+                    PenaltyType::Indexed(spread) => {
+                        let rate = self.bounded_rate((self.penalty_reference_rate() + spread).max(Decimal::ZERO));
+                        (rate, accrue_at(rate))
+                    }
+                    PenaltyType::Absolute(amount) if newly_missed => (Decimal::ZERO, PreciseDecimal::from(amount)),
+                    PenaltyType::Relative(rate) if newly_missed => (rate, PreciseDecimal::from(base) * PreciseDecimal::from(rate)),
+                    PenaltyType::Absolute(_) | PenaltyType::Relative(_) => (Decimal::ZERO, PreciseDecimal::ZERO),
+                };
+                let (reported_rate, total) = charged.unwrap_or((penalty_rate, PreciseDecimal::ZERO));
+                charged = Some((reported_rate, total + penalty));
+            }
+
+            // Keep the total within the penalty and total-cost caps
+            charged.map(|(penalty_rate, penalty)| (penalty_rate, core_math::clamp_to_headroom(penalty, self.penalty_headroom())))
+        }
+
+        /// The penalties that would be owed at `current_date`: those accrued, plus those
+        /// projected since last charged once penalties can accrue.
+        fn projected_penalties(&self, current_date: i64, accrued_interest: Decimal) -> Decimal {
+            let mut penalties = self.accrued_penalties;
+            if self.penalties_can_accrue() {
+                if let Some((_, penalty)) = self.projected_penalty(current_date, accrued_interest) {
+                    penalties += penalty;
+                }
+            }
+            self.round(penalties)
+        }
+
+        /// Whether penalties can accrue: once the whole loan is called or matures, or while
+        /// a partial call is outstanding.
+        fn penalties_can_accrue(&self) -> bool {
+            matches!(self.status, ContractStatus::Called | ContractStatus::Term | ContractStatus::Delinquent)
+                || (!self.status.is_terminal() && !self.call_notices.is_empty())
+        }
+
+        /// Rolls a called contract over into a new open-ended period, by agreement
+        /// of both parties.
+        ///
+        /// Interest is settled at the old rate and capitalized into principal, the
+        /// rate is reset and the contract returns to Active. Any later call starts a
+        /// fresh notice period.
+        ///
+        /// # Arguments
+        /// * `new_rate` - The annual interest rate for the new period
+        pub fn rollover(&mut self, new_rate: Decimal) {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            let current_date = self.now();
+            ensure(
+                matches!(self.status, ContractStatus::Called | ContractStatus::Delinquent),
+                CallMoneyError::NotCalled { current: self.status },
+            );
+            ensure(
+                interest_rate_in_range(new_rate, self.allow_negative_rate),
+                CallMoneyError::InvalidRate { field: "new_rate".to_string() },
+            );
+
+            // Settle interest at the old rate and capitalize it
+            self.accrue_interest(current_date);
+            let capitalized = self.capitalize_interest();
+            self.last_capitalization_date = self.last_interest_calculation_date;
+
+            // Reprice and reopen the contract; disputes over the old call no longer apply
+            self.interest_rate = new_rate;
+            self.rate_history.push((self.last_interest_calculation_date, new_rate));
+            self.settlement_quote = None;
+            self.disputed_seconds = 0;
+            self.call_due_date = None;
+            self.maturity_date = None;
+            self.notice_acknowledged = false;
+            self.transition(ContractStatus::Active);
+            self.log(self.last_interest_calculation_date, format!("RolledOver: capitalized {} at new rate {}", capitalized, new_rate));
+            Runtime::emit_event(RolledOver {
+                timestamp: self.last_interest_calculation_date.seconds_since_unix_epoch,
+                capitalized,
+                principal: self.principal,
+                new_rate,
+            });
+        }
+
+        /// Folds the accrued interest into principal as of the last calculation.
+        ///
+        /// # Returns
+        /// The amount capitalized
+        fn capitalize_interest(&mut self) -> Decimal {
+            let capitalized = self.interest_due();
+            self.principal += capitalized;
+            self.accrued_interest = PreciseDecimal::ZERO;
+            self.record(LedgerEntry::InterestCapitalized {
+                timestamp: self.last_interest_calculation_date,
+                amount: capitalized,
+            });
+            capitalized
+        }
+
+        /// Resets the interest rate from a new reference rate under the rate reset terms.
+        /// Interest accrues at the old rate up to the scheduled reset date; the new rate,
+        /// the reference rate times the multiplier plus the spread, applies from then on.
+        /// Reset dates missed in the meantime are skipped. Only the lender may supply the
+        /// rate, and only while no oracle is set.
+        ///
+        /// # Arguments
+        /// * `new_reference_rate` - The reference rate observed for the reset
+        pub fn reset_rate(&mut self, new_reference_rate: Decimal) {
+            self.ensure_not_paused();
+            self.require_lender();
+            self.check_terms_unlocked();
+            ensure(self.rate_oracle.is_none(), invalid_terms("rate resets are read from the oracle"));
+            self.apply_rate_reset(new_reference_rate);
+        }
+
+        /// Resets the interest rate from the reference rate published by the oracle.
+        /// Anyone may trigger it, as the rate does not come from the caller. Rates
+        /// older than the maximum age are rejected.
+        pub fn reset_rate_from_oracle(&mut self) {
+            self.ensure_not_paused();
+            let reference_rate = self.oracle_rate();
+            self.apply_rate_reset(reference_rate);
+        }
+
+        /// Resets the interest rate from a reference rate under the rate reset terms.
+        fn apply_rate_reset(&mut self, new_reference_rate: Decimal) {
+            if let Err(error) = self.check_open() {
+                error.raise();
+            }
+            let Some(rate_reset) = self.rate_reset else { invalid_terms("the contract has no rate reset terms").raise() };
+            let reset_date = self.next_rate_reset_date.unwrap();
+            let current_date = self.now();
+            ensure(
+                reset_date.seconds_since_unix_epoch <= current_date,
+                CallMoneyError::RateResetNotDue { next_reset: reset_date.seconds_since_unix_epoch },
+            );
+            let new_rate = self.bounded_rate(new_reference_rate * rate_reset.multiplier + rate_reset.spread);
+            ensure(
+                interest_rate_in_range(new_rate, self.allow_negative_rate),
+                CallMoneyError::InvalidRate { field: "new_reference_rate".to_string() },
+            );
+
+            // Settle interest at the old rate up to the reset point
+            let reset_point = reset_date.max(self.last_interest_calculation_date);
+            self.accrue_interest(reset_point.seconds_since_unix_epoch);
+            self.interest_rate = new_rate;
+            self.rate_history.push((reset_point, new_rate));
+            self.settlement_quote = None;
+
+            let mut next_reset = reset_date.seconds_since_unix_epoch;
+            while next_reset <= current_date {
+                next_reset += rate_reset.cycle;
+            }
+            self.next_rate_reset_date = Some(Self::to_instant(next_reset));
+            self.log(reset_point, format!("Rate reset to {} from reference rate {}", new_rate, new_reference_rate));
+            self.log_rate_bound(reset_point, new_rate);
+        }
+
+        /// Converts a called contract into a fixed-term loan, by agreement of both parties.
+        ///
+        /// Interest keeps accruing; penalties only apply once the maturity date and
+        /// grace period have passed.
+        ///
+        /// # Arguments
+        /// * `maturity_date` - When the loan falls due, as a Unix timestamp
+        pub fn convert_to_term(&mut self, maturity_date: i64) {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            let current_date = self.now();
+            ensure(
+                matches!(self.status, ContractStatus::Called | ContractStatus::Delinquent),
+                CallMoneyError::NotCalled { current: self.status },
+            );
+            ensure(maturity_date > current_date, CallMoneyError::InvalidPeriod { field: "maturity_date".to_string() });
+
+            // Settle interest up to the conversion
+            self.accrue_interest(current_date);
+
+            // Replace the call with the agreed maturity; disputes over the call no longer apply
+            self.maturity_date = Some(Self::to_instant(maturity_date));
+            self.call_due_date = None;
+            self.notice_acknowledged = false;
+            self.disputed_seconds = 0;
+            self.transition(ContractStatus::Term);
+            self.log(self.last_interest_calculation_date, format!("Converted to term. Matures on: {}", maturity_date));
+        }
+
+        /// Splits part of the loan off into a new, independent contract.
+        ///
+        /// Interest is settled first. The new contract takes over `principal_to_move`
+        /// and a proportional share of the accrued interest; all other terms are copied.
+        ///
+        /// # Arguments
+        /// * `principal_to_move` - The principal to carve out, less than the outstanding principal
+        ///
+        /// # Returns
+        /// The newly instantiated contract holding the carved-out position, its creditor note and its owner badge
+        pub fn split(&mut self, principal_to_move: Decimal) -> (Global<CallMoney>, Bucket, Bucket) {
+            self.ensure_not_paused();
+            self.require_lender();
+            let current_date = self.now();
+            self.check_terms_unlocked();
+            ensure(self.status == ContractStatus::Active, CallMoneyError::NotActive { current: self.status });
+            ensure(
+                principal_to_move > Decimal::ZERO && principal_to_move < self.principal,
+                CallMoneyError::InvalidAmount { field: "principal_to_move".to_string() },
+            );
+            let demanded = self.call_notices.iter().fold(Decimal::ZERO, |total, notice| total + notice.amount);
+            ensure(principal_to_move <= self.principal - demanded, frozen("principal under a partial call cannot be split off"));
+
+            // Settle interest up to the split date
+            self.accrue_interest(current_date);
+
+            // Carve out the principal and its share of the accrued interest
+            let interest_to_move = core_math::pro_rata(self.interest_due(), principal_to_move, self.principal);
+            self.principal -= principal_to_move;
+            self.accrued_interest -= PreciseDecimal::from(interest_to_move);
+            // The credit line stays with this contract; the carved-out principal no longer counts against it
+            self.credit_limit = self.credit_limit.map(|limit| limit - principal_to_move);
+            self.settlement_quote = None;
+            self.record(LedgerEntry::SplitOff {
+                timestamp: self.last_interest_calculation_date,
+                principal: principal_to_move,
+                interest: interest_to_move,
+            });
+            self.log(self.last_interest_calculation_date, format!("Split off principal: {}, interest: {}", principal_to_move, interest_to_move));
+
+            // Create the new contract on the same terms
+            let mut terms = self.get_terms();
+            terms.contract_id = Some(format!("{}-{}", self.contract_id, self.ledger_count));
+            terms.interest_payment_anchor = None;
+            terms.rate_schedule.retain(|(date, _)| *date > current_date);
+            terms.start_date = current_date;
+            terms.credit_limit = None;
+            terms.fees = terms.fees.map(|fees| FeeTerms { origination_fee: Decimal::ZERO, ..fees });
+            let funds = self.principal_vault.take(principal_to_move.min(self.principal_vault.amount()));
+            let (mut carved_out, creditor_note, owner_badge) = Self::new_contract(terms, principal_to_move, funds);
+            carved_out.accrued_interest = PreciseDecimal::from(interest_to_move);
+            // Restate the initiation, so the new ledger opens with the carved-out interest
+            carved_out.ledger_count = 0;
+            carved_out.event_kind_counts.clear();
+            carved_out.record(LedgerEntry::Initiated {
+                timestamp: carved_out.start_date,
+                principal: principal_to_move,
+                accrued_interest: interest_to_move,
+            });
+            carved_out.log(carved_out.start_date, format!("Split from parent contract with interest: {}", interest_to_move));
+
+            (Self::globalize_contract(carved_out), creditor_note, owner_badge)
+        }
+
+        /// Proposes transferring the debt to a new borrower, by agreement of the lender
+        /// and the current borrower. The transfer takes effect once the new borrower
+        /// accepts; a new proposal replaces any pending one.
+        ///
+        /// # Arguments
+        /// * `new_borrower_badge` - ResourceAddress of the new borrower's badge
+        pub fn propose_novation(&mut self, new_borrower_badge: ResourceAddress) {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            if let Err(error) = self.check_open() {
+                error.raise();
+            }
+            ensure(new_borrower_badge != self.borrower, invalid_terms("the new borrower must differ from the current one"));
+            self.pending_novation = Some(new_borrower_badge);
+            self.log(self.last_interest_calculation_date, format!("Novation proposed to {:?}", new_borrower_badge));
+        }
+
+        /// Accepts a proposed novation as the new borrower. From then on the new
+        /// borrower's badge holds every borrower right and obligation; the previous
+        /// borrower is released.
+        ///
+        /// # Arguments
+        /// * `proof` - Proof of the proposed new borrower's badge
+        pub fn accept_novation(&mut self, proof: Proof) {
+            self.ensure_not_paused();
+            let Some(new_borrower) = self.pending_novation.take() else { frozen("no novation has been proposed").raise() };
+            proof.check_with_message(new_borrower, "Proof is not of the proposed borrower's badge");
+            if let Err(error) = self.check_open() {
+                error.raise();
+            }
+            let previous_borrower = std::mem::replace(&mut self.borrower, new_borrower);
+            let timestamp = self.now();
+            Runtime::emit_event(NovationCompleted { timestamp, previous_borrower, new_borrower });
+            self.log(
+                Self::to_instant(timestamp),
+                format!("Novation: debt transferred from {:?} to {:?}", previous_borrower, new_borrower),
+            );
+        }
+
+        /// Proposes renegotiated terms, by either party. The terms change once the
+        /// counterparty accepts; a new proposal replaces any pending one.
+        ///
+        /// # Arguments
+        /// * `new_terms` - The terms to change; terms left as None stay as they are
+        /// * `proof` - Proof of the proposing party's badge
+        pub fn propose_amendment(&mut self, new_terms: Amendment, proof: Proof) {
+            self.ensure_not_paused();
+            let proposed_by = self.party_of(proof);
+            if let Err(error) = self.check_open() {
+                error.raise();
+            }
+            ensure(
+                new_terms.interest_rate.is_none_or(|rate| interest_rate_in_range(rate, self.allow_negative_rate)),
+                CallMoneyError::InvalidRate { field: "interest_rate".to_string() },
+            );
+            ensure(
+                new_terms.notice_period.is_none_or(|period| period >= 0),
+                CallMoneyError::InvalidPeriod { field: "notice_period".to_string() },
+            );
+            ensure(
+                new_terms.grace_period.is_none_or(|period| period >= 0),
+                CallMoneyError::InvalidPeriod { field: "grace_period".to_string() },
+            );
+            ensure(
+                new_terms.penalty_rate.is_none_or(|rate| rate >= Decimal::ZERO),
+                CallMoneyError::InvalidRate { field: "penalty_rate".to_string() },
+            );
+            ensure(new_terms != Amendment::default(), invalid_terms("an amendment must change at least one term"));
+
+            let proposed_at = self.now();
+            self.pending_amendment = Some(PendingAmendment { terms: new_terms, proposed_by, proposed_at: Self::to_instant(proposed_at) });
+            self.log(Self::to_instant(proposed_at), format!("Amendment proposed by {:?}: {:?}", proposed_by, new_terms));
+        }
+
+        /// Accepts the pending amendment, as the counterparty of the proposer. Interest
+        /// accrues at the old rate up to the acceptance; the new terms apply from then on.
+        ///
+        /// # Arguments
+        /// * `proof` - Proof of the accepting party's badge
+        pub fn accept_amendment(&mut self, proof: Proof) {
+            self.ensure_not_paused();
+            let accepted_by = self.party_of(proof);
+            let Some(pending) = self.pending_amendment else { frozen("no amendment has been proposed").raise() };
+            ensure(accepted_by != pending.proposed_by, frozen("an amendment must be accepted by the counterparty"));
+            if let Err(error) = self.check_open() {
+                error.raise();
+            }
+            self.pending_amendment = None;
+
+            // Settle interest under the old terms up to the amendment
+            self.accrue_interest(self.now());
+            let amended_at = self.last_interest_calculation_date;
+            let Amendment { interest_rate, notice_period, grace_period, penalty_rate } = pending.terms;
+
+            let mut changes = Vec::new();
+            if let Some(rate) = interest_rate {
+                changes.push(format!("interest rate {} -> {}", self.interest_rate, rate));
+                self.interest_rate = rate;
+                self.rate_history.push((amended_at, rate));
+            }
+            if let Some(period) = notice_period {
+                changes.push(format!("notice period {} -> {}", self.notice_period, period));
+                self.notice_period = period;
+            }
+            if let Some(period) = grace_period {
+                changes.push(format!("grace period {} -> {}", self.grace_period, period));
+                self.grace_period = period;
+            }
+            if let Some(rate) = penalty_rate {
+                changes.push(format!("penalty rate {} -> {}", self.penalty_rate, rate));
+                self.penalty_rate = rate;
+            }
+            self.settlement_quote = None;
+            self.log(
+                amended_at,
+                format!("Amendment proposed by {:?} accepted by {:?}: {}", pending.proposed_by, accepted_by, changes.join(", ")),
+            );
+        }
+
+        /// Retrieves the amendment awaiting acceptance, if any.
+        pub fn get_pending_amendment(&self) -> Option<PendingAmendment> {
+            self.pending_amendment
+        }
+
+        /// Raises a borrower dispute, suspending penalty accrual until it is resolved.
+        /// Repayments and interest accrual continue as normal.
+        pub fn raise_dispute(&mut self) {
+            self.ensure_not_paused();
+            self.require_borrower();
+            let current_date = self.now();
+            ensure(!self.dispute_active, frozen("a dispute is already open"));
+            self.dispute_active = true;
+            self.dispute_started_at = Self::to_instant(current_date);
+            self.log(self.dispute_started_at, "Dispute raised".to_string());
+        }
+
+        /// Resolves the open dispute. The time spent in dispute is excluded from
+        /// the overdue period when penalties are next applied.
+        pub fn resolve_dispute(&mut self) {
+            self.ensure_not_paused();
+            self.require_lender();
+            let current_date = self.now();
+            ensure(self.dispute_active, frozen("no dispute is open"));
+            let resolved_at = Self::to_instant(current_date);
+            ensure(
+                resolved_at >= self.dispute_started_at,
+                CallMoneyError::BackdatedTimestamp {
+                    supplied: current_date,
+                    minimum: self.dispute_started_at.seconds_since_unix_epoch,
+                },
+            );
+            let disputed = resolved_at.seconds_since_unix_epoch - self.dispute_started_at.seconds_since_unix_epoch;
+            self.disputed_seconds += disputed;
+            self.dispute_active = false;
+            self.log(resolved_at, format!("Dispute resolved after {} seconds", disputed));
+        }
+
+        /// Freezes the contract during an incident. Every state-changing method is
+        /// blocked until the owner unpauses it; views keep working.
+        pub fn pause(&mut self) {
+            self.require_owner();
+            ensure(!self.paused, CallMoneyError::Paused);
+            self.paused = true;
+            self.paused_at = Self::to_instant(self.now());
+            self.log(self.paused_at, "Contract paused".to_string());
+        }
+
+        /// Lifts the pause. The time spent paused is excluded from the overdue period,
+        /// so borrowers are not penalized for a protocol-level freeze.
+        pub fn unpause(&mut self) {
+            self.require_owner();
+            ensure(self.paused, frozen("the contract is not paused"));
+            let resumed_at = Self::to_instant(self.now().max(self.paused_at.seconds_since_unix_epoch));
+            let paused = resumed_at.seconds_since_unix_epoch - self.paused_at.seconds_since_unix_epoch;
+            self.paused_seconds += paused;
+
+            // Overdue time already penalized before the pause stays charged; the rest moves past it
+            self.penalized_until = self.penalized_until.map(|until| Self::to_instant(until.seconds_since_unix_epoch + paused));
+            self.paused = false;
+            self.log(resumed_at, format!("Contract unpaused after {} seconds", paused));
+        }
+
+        /// Adds collateral to the contract. Collateral can be topped up at any time; with a
+        /// collateral oracle set, a top-up during a margin call re-checks the loan-to-value
+        /// and ends the margin call once it is restored.
+        ///
+        /// # Arguments
+        /// * `collateral` - A bucket of one of the accepted collateral resources
+        pub fn add_collateral(&mut self, collateral: Bucket) {
+            self.ensure_not_paused();
+            let resource = collateral.resource_address();
+            let amount = collateral.amount();
+            self.collateral_vaults
+                .get_mut(&resource)
+                .unwrap_or_else(|| CallMoneyError::UnacceptedCollateral { resource }.raise())
+                .put(collateral);
+            self.log(self.last_interest_calculation_date, format!("Collateral added: {} of {:?}", amount, resource));
+            Runtime::emit_event(CollateralAdded { resource, amount });
+            if self.status == ContractStatus::MarginCall && self.collateral_oracle.is_some() {
+                self.check_health();
+            }
+        }
+
+        /// Withdraws collateral from the contract. Once nothing is owed, interest, fees and
+        /// penalties included, any amount may be withdrawn; before that, a withdrawal needs the collateral oracle and is
+        /// only allowed if the loan-to-value afterwards stays within the maximum.
+        ///
+        /// # Arguments
+        /// * `resource` - The collateral resource to withdraw
+        /// * `amount` - The amount to withdraw
+        ///
+        /// # Returns
+        /// A bucket containing the withdrawn collateral
+        pub fn withdraw_collateral(&mut self, resource: ResourceAddress, amount: Decimal) -> Bucket {
+            self.ensure_not_paused();
+            self.require_borrower();
+            let collateral = self.collateral_vaults
+                .get_mut(&resource)
+                .unwrap_or_else(|| CallMoneyError::UnacceptedCollateral { resource }.raise())
+                .take(amount);
+            if self.total_amount_due(self.now()) > Decimal::ZERO {
+                // Value what remains once the withdrawal has been taken out
+                ensure(self.collateral_oracle.is_some() && self.max_ltv > Decimal::ZERO, CallMoneyError::CollateralLocked);
+                ensure(self.loan_to_value(self.now()) <= self.max_ltv, CallMoneyError::CollateralLocked);
+            }
+            self.log(self.last_interest_calculation_date, format!("Collateral removed: {} of {:?}", amount, resource));
+            Runtime::emit_event(CollateralRemoved { resource, amount });
+            collateral
+        }
+
+        /// Releases part of the collateral to the borrower before the loan is repaid, with
+        /// the lender's approval, as long as the remaining collateral still covers the
+        /// minimum ratio against the amount owed. The collateral is valued at the
+        /// collateral oracle's prices.
+        ///
+        /// # Arguments
+        /// * `resource` - The collateral resource to release
+        /// * `amount` - The amount to release
+        ///
+        /// # Returns
+        /// A bucket containing the released collateral, for the borrower
+        pub fn release_collateral(&mut self, resource: ResourceAddress, amount: Decimal) -> Bucket {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            ensure(self.collateral_oracle.is_some(), CallMoneyError::CollateralLocked);
+            let released = {
+                let mut vault = self.collateral_vaults
+                    .get_mut(&resource)
+                    .unwrap_or_else(|| CallMoneyError::UnacceptedCollateral { resource }.raise());
+                ensure(
+                    amount > Decimal::ZERO && amount <= vault.amount(),
+                    CallMoneyError::InvalidAmount { field: "amount".to_string() },
+                );
+                vault.take(amount)
+            };
+
+            // Value what remains once the release has been taken out
+            let value = self.value_collateral(&self.oracle_prices(), true);
+            ensure(
+                liquidation::covers_minimum_ratio(value, self.total_amount_due(self.now()), self.min_collateral_ratio),
+                CallMoneyError::CollateralLocked,
+            );
+
+            self.log(self.last_interest_calculation_date, format!("Collateral released: {} of {:?}", amount, resource));
+            Runtime::emit_event(CollateralRemoved { resource, amount });
+            released
+        }
+
+        /// Values the pledged collateral after applying each asset's haircut.
+        ///
+        /// Prices are supplied by the caller; `check_health` reads them from the
+        /// collateral oracle instead.
+        ///
+        /// # Arguments
+        /// * `prices` - The price of each collateral resource in principal currency
+        ///
+        /// # Returns
+        /// The sum of amount * price * (1 - haircut) over all pledged collateral
+        pub fn collateral_value(&self, prices: Vec<(ResourceAddress, Decimal)>) -> Decimal {
+            self.value_collateral(&prices, true)
+        }
+
+        /// Values the pledged collateral at the given prices, with or without the haircuts.
+        fn value_collateral(&self, prices: &[(ResourceAddress, Decimal)], apply_haircuts: bool) -> Decimal {
+            let mut value = Decimal::ZERO;
+            for (resource, haircut) in &self.accepted_collateral {
+                let amount = self.collateral_amount(*resource);
+                if amount == Decimal::ZERO {
+                    continue;
+                }
+                let price = prices
+                    .iter()
+                    .find(|(priced, _)| priced == resource)
+                    .map(|(_, price)| *price)
+                    .unwrap_or_else(|| CallMoneyError::UnacceptedCollateral { resource: *resource }.raise());
+                let haircut = if apply_haircuts { *haircut } else { Decimal::ZERO };
+                value += liquidation::haircut_value(amount, price, haircut);
+            }
+            value
+        }
+
+        /// Computes the loan-to-value ratio from oracle prices and moves the contract into
+        /// or out of a margin call against the maximum loan-to-value. Anyone may call it,
+        /// as the prices come from the oracle.
+        ///
+        /// # Returns
+        /// The amount owed over the haircut collateral value; `Decimal::MAX` when debt
+        /// is owed against no collateral value
+        pub fn check_health(&mut self) -> Decimal {
+            self.ensure_not_paused();
+            let current_date = self.now();
+            let ltv = self.loan_to_value(current_date);
+
+            if self.max_ltv > Decimal::ZERO {
+                if ltv > self.max_ltv && self.status == ContractStatus::Active {
+                    Runtime::emit_event(UndercollateralizedEvent { timestamp: current_date, ltv, max_ltv: self.max_ltv });
+                    self.transition(ContractStatus::MarginCall);
+                    self.log(Self::to_instant(current_date), format!("MarginCall: loan-to-value {} above {}", ltv, self.max_ltv));
+                    self.notify(ObserverEvent::MarginCall);
+                } else if ltv <= self.max_ltv && self.status == ContractStatus::MarginCall {
+                    self.transition(ContractStatus::Active);
+                    self.log(Self::to_instant(current_date), format!("Margin call cured: loan-to-value {}", ltv));
+                }
+            }
+            ltv
+        }
+
+        /// Repays part of a loan in a margin call, or of a called loan overdue past the
+        /// grace period, in exchange for a proportional share of the collateral. A
+        /// single liquidation repays at most the close factor's share of the amount
+        /// owed; the rest of the payment is returned. Anyone may liquidate: third
+        /// parties receive the liquidation bonus on top, the lender does not.
+        ///
+        /// Collateral is valued at oracle prices after haircuts, and the loan-to-value
+        /// is re-checked afterwards, so a liquidation that restores it ends the margin call.
+        ///
+        /// # Arguments
+        /// * `payment` - The repayment, in the currency the loan is denominated in
+        /// * `liquidator` - Proof of the creditor note to liquidate as the lender, if any
+        ///
+        /// # Returns
+        /// A tuple of the unused part of the payment and the released collateral
+        pub fn liquidate(&mut self, mut payment: Bucket, liquidator: Option<Proof>) -> (Bucket, Vec<Bucket>) {
+            self.ensure_not_paused();
+            if let Err(error) = self.check_open() {
+                error.raise();
+            }
+            let denomination = self.repayment_vault.resource_address();
+            ensure(
+                payment.resource_address() == denomination,
+                CallMoneyError::WrongResource { expected: denomination, got: payment.resource_address() },
+            );
+            let current_date = self.now();
+            self.check_health();
+            ensure(
+                self.status == ContractStatus::MarginCall
+                    || (matches!(self.status, ContractStatus::Called | ContractStatus::Delinquent)
+                        && self.is_past_grace_period(current_date)),
+                CallMoneyError::NotOverdue,
+            );
+
+            // Value the collateral at market before any of it is released; the haircuts only
+            // decide eligibility, so the liquidator is paid the repaid amount plus the bonus
+            let market_value = self.value_collateral(&self.oracle_prices(), false);
+            let repayable = liquidation::max_repayable(self.total_amount_due(current_date), self.close_factor);
+            let amount = payment.amount().min(repayable);
+            let by_lender = liquidator.is_some_and(|proof| proof.skip_checking().resource_address() == self.creditor_note.resource_address());
+            let (bonus, payer) = if by_lender {
+                (Decimal::ZERO, "lender (liquidation)".to_string())
+            } else {
+                (self.liquidation_bonus, "liquidator".to_string())
+            };
+            let (excess, interest_paid, tax_withheld) = self.apply_payment(amount, current_date, payer);
+            let repaid = amount - excess;
+            let mut repaid_funds = payment.take(repaid);
+            self.deposit_tax(repaid_funds.take(tax_withheld));
+            self.deposit_interest(&mut repaid_funds, interest_paid - tax_withheld);
+            self.repayment_vault.put(repaid_funds);
+
+            // Release the same share of every collateral vault
+            let share = liquidation::collateral_share(repaid, market_value, bonus);
+            let mut released = Vec::new();
+            for (resource, _) in self.accepted_collateral.clone() {
+                let mut vault = self.collateral_vaults.get_mut(&resource).unwrap();
+                let to_release = vault.amount() * share;
+                let taken = vault.take_advanced(to_release, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+                if taken.amount() == Decimal::ZERO {
+                    taken.drop_empty();
+                    continue;
+                }
+                Runtime::emit_event(CollateralRemoved { resource, amount: taken.amount() });
+                released.push(taken);
+            }
+            self.log(
+                self.last_interest_calculation_date,
+                format!("Liquidated: {} repaid for {} of the collateral", repaid, share),
+            );
+
+            if !self.status.is_terminal() {
+                self.check_health();
+            }
+            (payment, released)
+        }
+
+        /// Sets the oracle collateral prices are read from, by agreement of both parties.
+        ///
+        /// # Arguments
+        /// * `oracle` - The oracle component, or `None` to stop monitoring
+        /// * `max_price_age` - The oldest price accepted, in seconds
+        pub fn set_collateral_oracle(&mut self, oracle: Option<ComponentAddress>, max_price_age: i64) {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            ensure(max_price_age >= 0, CallMoneyError::InvalidPeriod { field: "max_price_age".to_string() });
+            self.collateral_oracle = oracle.map(Global::<AnyComponent>::from);
+            self.max_price_age = max_price_age;
+            self.log(self.last_interest_calculation_date, match oracle {
+                Some(oracle) => format!("Collateral oracle set: {}", oracle),
+                None => "Collateral oracle removed".to_string(),
+            });
+        }
+
+        /// Retrieves the amount of a collateral resource held by the contract.
+        ///
+        /// # Arguments
+        /// * `resource` - The collateral resource
+        ///
+        /// # Returns
+        /// The amount pledged, zero for resources that are not accepted
+        pub fn collateral_amount(&self, resource: ResourceAddress) -> Decimal {
+            self.collateral_vaults
+                .get(&resource)
+                .map(|vault| vault.amount())
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        /// Retrieves the amount pledged of every accepted collateral resource.
+        ///
+        /// # Returns
+        /// A vector of (resource, amount) pairs
+        pub fn get_collateral(&self) -> Vec<(ResourceAddress, Decimal)> {
+            self.accepted_collateral
+                .iter()
+                .map(|(resource, _)| (*resource, self.collateral_amount(*resource)))
+                .collect()
+        }
+
+        /// Retrieves the collateral basket: every accepted resource with the haircut
+        /// applied to it in the health calculation and the amount pledged.
+        ///
+        /// # Returns
+        /// A vector of (resource, haircut, amount) rows
+        pub fn get_collateral_basket(&self) -> Vec<(ResourceAddress, Decimal, Decimal)> {
+            self.accepted_collateral
+                .iter()
+                .map(|(resource, haircut)| (*resource, *haircut, self.collateral_amount(*resource)))
+                .collect()
+        }
+
+        /// Reads the benchmark rate from the oracle, rejecting a missing oracle or a stale rate.
+        fn oracle_rate(&self) -> Decimal {
+            let Some(oracle) = self.rate_oracle.as_ref() else { invalid_terms("the contract has no rate oracle").raise() };
+            let (rate, published_at) = oracle.call_raw::<(Decimal, i64)>("get_rate", scrypto_args!(self.oracle_benchmark.clone()));
+            ensure(
+                self.now() - published_at <= self.max_rate_age,
+                CallMoneyError::StaleOracleData { published_at, max_age: self.max_rate_age },
+            );
+            rate
+        }
+
+        /// Computes the amount owed at `current_date` over the haircut collateral value at
+        /// oracle prices; `Decimal::MAX` when debt is owed against no collateral value.
+        fn loan_to_value(&self, current_date: i64) -> Decimal {
+            let value = self.collateral_value(self.oracle_prices());
+            let owed = self.total_amount_due(current_date);
+            if value > Decimal::ZERO {
+                owed / value
+            } else if owed > Decimal::ZERO {
+                Decimal::MAX
+            } else {
+                Decimal::ZERO
+            }
+        }
+
+        /// Reads the price of each pledged collateral resource from the oracle, rejecting
+        /// a missing oracle or a stale price.
+        fn oracle_prices(&self) -> Vec<(ResourceAddress, Decimal)> {
+            let Some(oracle) = self.collateral_oracle.as_ref() else { invalid_terms("the contract has no collateral oracle").raise() };
+            let current_date = self.now();
+            self.accepted_collateral
+                .iter()
+                .filter(|(resource, _)| self.collateral_amount(*resource) > Decimal::ZERO)
+                .map(|(resource, _)| {
+                    let (price, published_at) = oracle.call_raw::<(Decimal, i64)>("get_price", scrypto_args!(*resource));
+                    ensure(
+                        current_date - published_at <= self.max_price_age,
+                        CallMoneyError::StaleOracleData { published_at, max_age: self.max_price_age },
+                    );
+                    (*resource, price)
+                })
+                .collect()
+        }
+
+        /// Reports a lifecycle transition to the observer, if one is registered.
+        fn notify(&mut self, event: ObserverEvent) {
+            let Some(observer) = self.observer else {
+                return;
+            };
+            if self.push_to_observer {
+                Global::<AnyComponent>::from(observer)
+                    .call_raw::<()>("on_clm_event", scrypto_args!(Runtime::global_address(), event));
+            } else {
+                self.pending_notifications.push((self.last_interest_calculation_date, event));
+            }
+        }
+
+        /// Creates the vault interest is held in for the lender, or checks that `resource`
+        /// matches the existing one.
+        fn ensure_interest_payout(&mut self, resource: ResourceAddress) {
+            if let Some(payout) = &self.interest_payout {
+                ensure(
+                    payout.resource_address() == resource,
+                    CallMoneyError::WrongResource { expected: payout.resource_address(), got: resource },
+                );
+            } else {
+                self.interest_payout = Some(Vault::new(resource));
+            }
+        }
+
+        /// Whether a payment made now is late: the call or maturity due date has passed.
+        fn is_late(&self) -> bool {
+            paid_late(self.call_due_date.or(self.maturity_date), self.last_interest_calculation_date)
+        }
+
+        /// Pays accrued interest out of the reserve, as far as the reserve allows.
+        fn draw_interest_reserve(&mut self) {
+            let interest_due = self.interest_due();
+            let Some(reserve) = self.interest_reserve.as_mut() else {
+                return;
+            };
+            let mut drawn = reserve.take(interest_due.min(reserve.amount()));
+            let amount = drawn.amount();
+            if amount == Decimal::ZERO {
+                drawn.drop_empty();
+                return;
+            }
+
+            self.settle_interest(amount);
+            self.total_interest_paid += amount;
+            self.record(LedgerEntry::ReserveDrawn {
+                timestamp: self.last_interest_calculation_date,
+                amount,
+            });
+            self.log(self.last_interest_calculation_date, format!("Interest drawn from reserve: {}", amount));
+            let tax_withheld = self.withhold_tax(amount);
+            self.deposit_tax(drawn.take(tax_withheld));
+            self.interest_payout.as_mut().unwrap().put(drawn);
+        }
+
+        /// Emits a `ReserveLow` event once when the interest reserve falls below the
+        /// warning threshold, and rearms the warning when it is covered again.
+        fn check_reserve_level(&mut self) {
+            let Some(reserve) = self.interest_reserve.as_ref().map(|vault| vault.amount()) else {
+                return;
+            };
+            if self.reserve_warning_days == 0 || self.status.is_terminal() {
+                return;
+            }
+            let from = self.last_interest_calculation_date;
+            let to = Self::to_instant(from.seconds_since_unix_epoch + SECONDS_PER_DAY);
+            let daily_interest = self.round(self.interest_between(from, to));
+            if reserve >= daily_interest * Decimal::from(self.reserve_warning_days) {
+                self.reserve_warned = false;
+                return;
+            }
+            if self.reserve_warned {
+                return;
+            }
+
+            self.reserve_warned = true;
+            let days_covered = reserve / daily_interest;
+            self.log(from, format!("Interest reserve low: {} days of interest left", days_covered));
+            Runtime::emit_event(ReserveLow {
+                timestamp: from.seconds_since_unix_epoch,
+                reserve,
+                daily_interest,
+                days_covered,
+            });
+        }
+
+        /// Records the contract balances for the current day, as of the ledger clock.
+        ///
+        /// Only one snapshot can be recorded per day. Interest is projected up to now;
+        /// the accrual is only written back to the contract if `persist_accrual` is set,
+        /// which only the lender or the owner may do.
+        ///
+        /// # Arguments
+        /// * `persist_accrual` - Whether to also update the contract's accrued interest to now
+        pub fn record_snapshot(&mut self, persist_accrual: bool) {
+            self.ensure_not_paused();
+            if persist_accrual {
+                Runtime::assert_access_rule(rule!(require(self.creditor_note.clone()) || require(self.owner_badge)));
+            }
+            let as_of = self.now();
+            let day = Self::day_boundary(as_of);
+            ensure(self.snapshots.get(&day).is_none(), frozen("a snapshot has already been recorded for this day"));
+
+            let accrued_interest = if persist_accrual {
+                self.accrue_interest(as_of);
+                self.interest_due()
+            } else {
+                self.round(self.accrued_interest + self.projected_interest(Self::to_instant(as_of)))
+            };
+
+            self.snapshots.insert(day, BalanceSnapshot {
+                as_of: Self::to_instant(as_of),
+                principal: self.principal,
+                accrued_interest,
+                accrued_fees: self.accrued_fees,
+                accrued_penalties: self.penalties_due(),
+                status: self.status,
+            });
+        }
+
+        /// Retrieves the snapshot recorded for a given day.
+        ///
+        /// # Arguments
+        /// * `day` - Any Unix timestamp within the day of interest
+        pub fn get_snapshot(&self, day: i64) -> Option<BalanceSnapshot> {
+            self.snapshots.get(&Self::day_boundary(day)).map(|snapshot| snapshot.clone())
+        }
+
+        /// Retrieves the snapshots recorded between two days, inclusive.
+        ///
+        /// # Arguments
+        /// * `from_day` - Any Unix timestamp within the first day
+        /// * `to_day` - Any Unix timestamp within the last day
+        /// * `limit` - The maximum number of snapshots to return
+        pub fn get_snapshots_range(&self, from_day: i64, to_day: i64, limit: u32) -> Vec<BalanceSnapshot> {
+            let mut snapshots = Vec::new();
+            let mut day = Self::day_boundary(from_day);
+            let last_day = Self::day_boundary(to_day);
+            while day <= last_day && snapshots.len() < limit as usize {
+                if let Some(snapshot) = self.snapshots.get(&day) {
+                    snapshots.push(snapshot.clone());
+                }
+                day = Instant::new(day.seconds_since_unix_epoch + SECONDS_PER_DAY);
+            }
+            snapshots
+        }
+
+        /// Calculates the total amount due as of a given date, projecting interest
+        /// without modifying the contract.
+        ///
+        /// # Arguments
+        /// * `current_date` - The date to project the balance to, as a Unix timestamp
+        ///
+        /// # Returns
+        /// Principal, accrued interest, fees and penalties owed at `current_date`
+        pub fn total_amount_due(&self, current_date: i64) -> Decimal {
+            let fees = self.projected_fees(current_date);
+            let current_date = Self::to_instant(current_date);
+            let accrued_interest = self.accrued_interest + self.projected_interest(current_date) + self.projected_commitment_fee(current_date);
+            self.principal + self.round(accrued_interest) + fees + self.penalties_due()
+        }
+
+        /// Projects the contract's future cash flows without modifying it: interest on each
+        /// interest payment date, then interest and principal on the due date. Interest is
+        /// projected on the current principal along the rate schedule; caps, commitment fees
+        /// and future rate resets are not projected, and no further penalties are assumed.
+        ///
+        /// # Arguments
+        /// * `horizon` - The last date to project to, as a Unix timestamp
+        /// * `assumed_call_date` - A date the lender is assumed to call an uncalled contract on
+        ///
+        /// # Returns
+        /// A vector of (date, amount, kind) cash flows, earliest first
+        pub fn project_cash_flows(&self, horizon: i64, assumed_call_date: Option<i64>) -> Vec<(i64, Decimal, EventKind)> {
+            if self.status.is_terminal() {
+                return Vec::new();
+            }
+            let start = self.now().max(self.last_interest_calculation_date.seconds_since_unix_epoch);
+            ensure(horizon >= start, CallMoneyError::InvalidPeriod { field: "horizon".to_string() });
+
+            // The balance falls due on the recorded date or, under an assumed call, after the notice period
+            let due_date = match assumed_call_date {
+                Some(call_date) => {
+                    if let Err(error) = self.check_call(call_date) {
+                        error.raise();
+                    }
+                    ensure(call_date >= start, CallMoneyError::InvalidPeriod { field: "assumed_call_date".to_string() });
+                    Some(self.calendar.notice_due_date(call_date, self.notice_period))
+                }
+                None => self.maturity_date.or(self.call_due_date).map(|date| date.seconds_since_unix_epoch),
+            };
+            let last_payment_date = due_date.map_or(horizon, |due_date| due_date.min(horizon));
+
+            let mut cash_flows = Vec::new();
+            let mut accrued_interest = self.accrued_interest;
+            let mut accrued_from = self.last_interest_calculation_date;
+            if let (Some(cycle), Some(first_date)) = (self.interest_payment_cycle, self.next_interest_payment_date) {
+                let mut payment_date = first_date.seconds_since_unix_epoch;
+                while payment_date <= last_payment_date && due_date.is_none_or(|due_date| payment_date < due_date) {
+                    let adjusted_date = self.calendar.adjust(payment_date);
+                    let payment_instant = Self::to_instant(adjusted_date).max(accrued_from);
+                    accrued_interest += self.interest_between(accrued_from, payment_instant);
+                    cash_flows.push((adjusted_date, self.round(accrued_interest), EventKind::InterestPayment));
+                    accrued_interest = PreciseDecimal::ZERO;
+                    accrued_from = payment_instant;
+                    payment_date += cycle;
+                }
+            }
+            if let Some(due_date) = due_date.filter(|due_date| *due_date <= horizon) {
+                let due_instant = Self::to_instant(due_date).max(accrued_from);
+                accrued_interest += self.interest_between(accrued_from, due_instant);
+                cash_flows.push((due_date, self.round(accrued_interest), EventKind::InterestPayment));
+                cash_flows.push((due_date, self.principal + self.accrued_fees + self.penalties_due(), EventKind::Repayment));
+            }
+            cash_flows
+        }
+
+        /// Quotes what would be owed at a given date, without modifying the contract, so it
+        /// can be asked from a preview transaction. Interest, fees and penalties are projected
+        /// as `update_accrued_interest` and `apply_penalty` would charge them.
+        ///
+        /// # Arguments
+        /// * `as_of` - The date to quote for, as a Unix timestamp
+        ///
+        /// # Returns
+        /// A tuple of (principal, interest, fees, penalties)
+        pub fn quote_total_due(&self, as_of: i64) -> (Decimal, Decimal, Decimal, Decimal) {
+            if let Err(error) = self.check_date(as_of) {
+                error.raise();
+            }
+            let as_of_instant = Self::to_instant(as_of);
+            let interest = self.round(self.accrued_interest + self.projected_interest(as_of_instant) + self.projected_commitment_fee(as_of_instant));
+            (self.principal, interest, self.projected_fees(as_of), self.projected_penalties(as_of, interest))
+        }
+
+        /// Shows how a payment would be applied at a given date, without modifying the
+        /// contract. Interest, fees and penalties are projected as `quote_total_due` does,
+        /// interest is drawn from the reserve, and a valid settlement quote is honoured,
+        /// exactly as `repay` would.
+        ///
+        /// Commitment fees accrue together with interest, so they are reported as interest.
+        ///
+        /// # Arguments
+        /// * `amount` - The amount that would be repaid
+        /// * `current_date` - The date the repayment would be made, as a Unix timestamp
+        ///
+        /// # Returns
+        /// A tuple of (prepayment_fee, penalty_paid, fees_paid, interest_paid, principal_paid, excess)
+        pub fn simulate_repayment(&self, amount: Decimal, current_date: i64) -> (Decimal, Decimal, Decimal, Decimal, Decimal, Decimal) {
+            let as_of = Self::to_instant(current_date);
+
+            // Bring interest up to date and draw what the reserve covers
+            let mut accrued_interest = self.round(self.accrued_interest + self.projected_interest(as_of) + self.projected_commitment_fee(as_of));
+            let penalties = self.projected_penalties(current_date, accrued_interest);
+            accrued_interest -= accrued_interest.min(self.interest_reserve_balance());
+
+            // A valid quote forgives the interest accrued since it was issued
+            let fees = self.projected_fees(current_date);
+            let total_due = self.principal + accrued_interest + fees + penalties;
+            if let Some((quoted, expires_at, interest_at_quote)) = self.settlement_quote {
+                if as_of <= expires_at && amount >= quoted {
+                    let interest_since_quote = self.total_interest_accrued + self.round(self.projected_interest(as_of)) - interest_at_quote;
+                    accrued_interest -= core_math::quote_forgiveness(total_due, quoted, interest_since_quote, accrued_interest);
+                }
+            }
+
+            let allocation = core_math::allocate_payment(amount, penalties, fees, accrued_interest, self.principal);
+            let (principal_paid, prepayment_fee, excess) =
+                self.charge_prepayment_fee(allocation.principal_paid, allocation.excess, current_date);
+            (
+                prepayment_fee,
+                allocation.penalties_paid,
+                allocation.fees_paid,
+                allocation.interest_paid,
+                principal_paid,
+                excess,
+            )
+        }
+
+        /// Summarizes the borrower's repayment behavior from the ledger.
+        ///
+        /// A payment counts as late when it was made after the call or maturity due date.
+        /// Interest settled from the reserve is not a borrower payment and is not counted,
+        /// though it is included in the interest paid.
+        ///
+        /// # Returns
+        /// A tuple of (on-time payments, late payments, total interest paid, total penalties paid)
+        pub fn repayment_summary(&self) -> (u32, u32, Decimal, Decimal) {
+            (self.on_time_payments, self.late_payments, self.total_interest_paid, self.total_penalties_paid)
+        }
+
+        /// Reports the realized economics of the contract.
+        ///
+        /// The annualized yield is a simple approximation: everything charged so far
+        /// (interest, penalties and fees, paid or not) relative to the original principal,
+        /// scaled to a year on the same time basis interest accrues on. It ignores the
+        /// timing of individual cash flows and changes in principal.
+        ///
+        /// # Arguments
+        /// * `as_of` - The date to report at, as a Unix timestamp
+        ///
+        /// # Returns
+        /// The contract's cumulative counters, average rate and approximate yield
+        pub fn get_economics(&self, as_of: i64) -> ContractEconomics {
+            let as_of_instant = Self::to_instant(as_of);
+            let elapsed = self.year_fraction(self.start_date.seconds_since_unix_epoch, as_of);
+            let total_charged = self.total_interest_accrued + self.total_penalties_accrued + self.total_fees_accrued;
+
+            ContractEconomics {
+                as_of,
+                total_interest_accrued: self.total_interest_accrued,
+                total_interest_paid: self.total_interest_paid,
+                total_penalties_accrued: self.total_penalties_accrued,
+                total_penalties_paid: self.total_penalties_paid,
+                total_fees_accrued: self.total_fees_accrued,
+                total_fees_paid: self.total_fees_paid,
+                total_principal_repaid: self.total_principal_repaid,
+                total_written_off: self.total_written_off,
+                weighted_average_rate: self.weighted_average_rate(as_of_instant),
+                annualized_yield: core_math::annualized_yield(total_charged, self.original_principal, elapsed),
+            }
+        }
+
+        /// Checks whether a repayment would be accepted, without failing the transaction.
+        ///
+        /// # Arguments
+        /// * `amount` - The amount that would be repaid
+        /// * `as_of` - The date the repayment would be made, as a Unix timestamp
+        ///
+        /// # Returns
+        /// The error the repayment would fail with, if any
+        pub fn can_repay(&self, amount: Decimal, as_of: i64) -> Result<(), CallMoneyError> {
+            self.check_repay(amount, as_of)?;
+            check_minimum_repayment(amount, self.total_amount_due(as_of), self.min_partial_repayment)
+        }
+
+        /// Checks whether the contract could be called, without failing the transaction.
+        ///
+        /// # Arguments
+        /// * `as_of` - The date the call would be made, as a Unix timestamp
+        ///
+        /// # Returns
+        /// The error the call would fail with, if any
+        pub fn can_call(&self, as_of: i64) -> Result<(), CallMoneyError> {
+            self.check_call(as_of)
+        }
+
+        /// Maps the contract onto the ACTUS state variables as of a given date.
+        /// Interest is projected up to `current_date` without modifying the contract.
+        ///
+        /// # Arguments
+        /// * `current_date` - The status date as a Unix timestamp
+        ///
+        /// # Returns
+        /// The contract state under ACTUS naming
+        pub fn actus_state(&self, current_date: i64) -> ActusState {
+            ActusState {
+                status_date: current_date,
+                contract_performance: self.status.performance(),
+                nominal_value: self.principal,
+                accrued_interest: self.round(self.accrued_interest + self.projected_interest(Self::to_instant(current_date))),
+                fee_accrued: self.projected_fees(current_date),
+                nominal_interest_rate: self.interest_rate,
+            }
+        }
+
+        /// Exports the terms currently in force as an ACTUS CLM attribute dictionary, for
+        /// off-ledger ACTUS engines and risk systems.
+        ///
+        /// # Returns
+        /// A JSON object keyed by ACTUS attribute names, with string values
+        pub fn export_actus_terms(&self) -> String {
+            actus::terms_json(
+                &self.get_terms(),
+                self.original_principal,
+                self.now(),
+                self.maturity_date.or(self.call_due_date).map(|date| date.seconds_since_unix_epoch),
+                self.rate_oracle.as_ref().map(|_| self.oracle_benchmark.as_str()),
+            )
+        }
+
+        /// Exports the current state as ACTUS state variables, with interest projected to now.
+        ///
+        /// # Returns
+        /// A JSON object keyed by ACTUS state variable names, with string values
+        pub fn export_actus_state(&self) -> String {
+            actus::state_json(
+                &self.actus_state(self.now()),
+                self.maturity_date.or(self.call_due_date).map(|date| date.seconds_since_unix_epoch),
+            )
+        }
+
+        /// Breaks the interest on the current principal between two dates down into
+        /// sub-periods, one per rate in effect. Does not modify the contract.
+        ///
+        /// # Arguments
+        /// * `from` - Start of the period as a Unix timestamp
+        /// * `to` - End of the period as a Unix timestamp
+        ///
+        /// # Returns
+        /// A vector of (period_start, period_end, rate_used, interest_added) rows
+        pub fn accrual_breakdown(&self, from: i64, to: i64) -> Vec<(i64, i64, Decimal, Decimal)> {
+            let from = Self::to_instant(from).seconds_since_unix_epoch;
+            let to = Self::to_instant(to).seconds_since_unix_epoch;
+            ensure(to >= from, CallMoneyError::InvalidPeriod { field: "to".to_string() });
+
+            core_math::rate_periods(&self.rate_history, from, to)
+                .into_iter()
+                .map(|(period_start, period_end, rate)| {
+                    let interest = core_math::simple_interest(self.principal, rate, self.year_fraction(period_start, period_end));
+                    (period_start, period_end, rate, self.round(interest))
+                })
+                .collect()
+        }
+
+        /// Returns the next interest payment date not yet met, shifted off non-business
+        /// days. The unadjusted date stays the anchor of the payment cycle.
+        fn interest_payment_due_date(&self) -> Option<Instant> {
+            self.next_interest_payment_date
+                .map(|due_date| Self::to_instant(self.calendar.adjust(due_date.seconds_since_unix_epoch)))
+        }
+
+        /// Moves the next interest payment date past `current_date`, one cycle at a time.
+        fn advance_interest_payment_date(&mut self, current_date: i64) {
+            let (Some(cycle), Some(mut due_date)) = (self.interest_payment_cycle, self.next_interest_payment_date) else {
+                return;
+            };
+            while due_date.seconds_since_unix_epoch <= current_date {
+                due_date = Self::to_instant(due_date.seconds_since_unix_epoch + cycle);
+            }
+            self.next_interest_payment_date = Some(due_date);
+        }
+
+        /// Collects the terms the event schedule is generated from.
+        fn schedule_terms(&self) -> schedule::ScheduleTerms {
+            let due_date = match (self.maturity_date, self.call_due_date) {
+                (Some(maturity_date), _) => Some((maturity_date.seconds_since_unix_epoch, ScheduledEventType::Maturity)),
+                (None, Some(call_due_date)) => Some((call_due_date.seconds_since_unix_epoch, ScheduledEventType::CallDue)),
+                (None, None) => self.repayment_notice.map(|date| (date.seconds_since_unix_epoch, ScheduledEventType::Termination)),
+            };
+            schedule::ScheduleTerms {
+                start_date: self.start_date.seconds_since_unix_epoch,
+                end_date: if self.status.is_terminal() {
+                    Some(self.last_interest_calculation_date.seconds_since_unix_epoch)
+                } else {
+                    due_date.map(|(date, event_type)| match event_type {
+                        ScheduledEventType::Termination => date,
+                        _ => self.grace_period_end(date),
+                    })
+                },
+                interest_payment: self.interest_payment_cycle
+                    .zip(self.next_interest_payment_date)
+                    .map(|(cycle, date)| (date.seconds_since_unix_epoch, cycle)),
+                capitalization: self.capitalization_cycle
+                    .map(|cycle| (self.last_capitalization_date.seconds_since_unix_epoch + cycle, cycle)),
+                rate_reset: self.rate_reset
+                    .zip(self.next_rate_reset_date)
+                    .map(|(reset, date)| (date.seconds_since_unix_epoch, reset.cycle)),
+                rate_steps: self.rate_schedule.iter().map(|(date, _)| date.seconds_since_unix_epoch).collect(),
+                penalty_start: due_date
+                    .filter(|(_, event_type)| *event_type != ScheduledEventType::Termination)
+                    .map(|(date, _)| self.grace_period_end(date)),
+                due_date,
+                calendar: self.calendar.clone(),
+            }
+        }
+
+        /// Collects the contract's ACTUS state variables.
+        fn state_variables(&self) -> actus::StateVariables {
+            actus::StateVariables {
+                status_date: self.last_interest_calculation_date.seconds_since_unix_epoch,
+                nominal_value: self.principal,
+                accrued_interest: self.accrued_interest,
+                accrued_penalties: self.accrued_penalties,
+                fee_accrued: self.accrued_fees,
+                nominal_interest_rate: self.interest_rate,
+                maturity_date: self.maturity_date.or(self.call_due_date).map(|date| date.seconds_since_unix_epoch),
+            }
+        }
+
+        /// Collects the terms the ACTUS state-transition and payoff functions depend on.
+        fn actus_terms(&self) -> actus::ActusTerms {
+            actus::ActusTerms {
+                day_count: self.day_count,
+                rounding: self.rounding,
+            }
+        }
+
+        /// Applies an event to the balances through the ACTUS state-transition function.
+        /// A call records its due date; a term contract keeps its maturity date.
+        fn transition_state(&mut self, event: &actus::ActusEvent) {
+            let next = actus::state_transition(event, &self.state_variables(), &self.actus_terms());
+            self.last_interest_calculation_date = Self::to_instant(next.status_date);
+            self.principal = next.nominal_value;
+            self.accrued_interest = next.accrued_interest;
+            self.accrued_penalties = next.accrued_penalties;
+            self.accrued_fees = next.fee_accrued;
+            if let actus::ActusEvent::Call { .. } = event {
+                self.call_due_date = next.maturity_date.map(Self::to_instant);
+            }
+        }
+
+        /// Returns the total currently owed: principal, accrued interest, fees and penalties.
+        fn total_due(&self) -> Decimal {
+            self.principal + self.interest_due() + self.accrued_fees + self.penalties_due()
+        }
+
+        /// Rounds a precise balance to a payable amount under the rounding convention.
+        fn round(&self, value: PreciseDecimal) -> Decimal {
+            value.checked_truncate(self.rounding.mode()).unwrap()
+        }
+
+        /// Returns the accrued interest as a payable amount.
+        fn interest_due(&self) -> Decimal {
+            self.round(self.accrued_interest)
+        }
+
+        /// Returns the accrued penalties as a payable amount.
+        fn penalties_due(&self) -> Decimal {
+            self.round(self.accrued_penalties)
+        }
+
+        /// Reduces the accrued interest by a payment. Paying the whole rounded amount
+        /// settles the balance, sub-unit remainder included.
+        fn settle_interest(&mut self, paid: Decimal) {
+            if paid >= self.interest_due() {
+                self.accrued_interest = PreciseDecimal::ZERO;
+            } else {
+                self.accrued_interest -= PreciseDecimal::from(paid);
+            }
+        }
+
+        /// Reduces the accrued penalties by a payment. Paying the whole rounded amount
+        /// settles the balance, sub-unit remainder included.
+        fn settle_penalties(&mut self, paid: Decimal) {
+            if paid >= self.penalties_due() {
+                self.accrued_penalties = PreciseDecimal::ZERO;
+            } else {
+                self.accrued_penalties -= PreciseDecimal::from(paid);
+            }
+        }
+
+        /// Whether the amount due on call or at maturity is overdue past the grace period, not counting
+        /// time spent in dispute.
+        fn is_past_grace_period(&self, current_date: i64) -> bool {
+            self.call_due_date.or(self.maturity_date).is_some_and(|due_date| {
+                current_date > self.grace_period_end(due_date.seconds_since_unix_epoch) + self.disputed_seconds
+            })
+        }
+
+        /// Projects the interest accrued since the last calculation up to `to`,
+        /// limited by the total-cost cap and the interest ceiling.
+        fn projected_interest(&self, to: Instant) -> PreciseDecimal {
+            let interest = self.interest_between(self.last_interest_calculation_date, to);
+            let interest = core_math::clamp_to_headroom(interest, self.total_cost_headroom());
+            core_math::clamp_to_headroom(interest, self.interest_ceiling_headroom())
+        }
+
+        /// Projects the commitment fee on the undrawn credit line since the last
+        /// calculation up to `to`. The fee only runs while the facility is open.
+        fn projected_commitment_fee(&self, to: Instant) -> PreciseDecimal {
+            if self.status != ContractStatus::Active {
+                return PreciseDecimal::ZERO;
+            }
+            let undrawn = self.credit_limit.map_or(Decimal::ZERO, |limit| limit - self.principal);
+            ensure(
+                to >= self.last_interest_calculation_date,
+                CallMoneyError::BackdatedTimestamp {
+                    supplied: to.seconds_since_unix_epoch,
+                    minimum: self.last_interest_calculation_date.seconds_since_unix_epoch,
+                },
+            );
+            let fee = core_math::simple_interest(
+                undrawn,
+                self.commitment_fee_rate,
+                self.year_fraction(self.last_interest_calculation_date.seconds_since_unix_epoch, to.seconds_since_unix_epoch),
+            );
+            // The fee is carried in accrued interest, so it shares the interest ceiling, and
+            // counts toward the total cost
+            let interest = self.round(self.projected_interest(to));
+            let ceiling_headroom = self.interest_ceiling_headroom().map(|headroom| core_math::headroom(headroom, interest));
+            let cost_headroom = self.total_cost_headroom().map(|headroom| core_math::headroom(headroom, interest));
+            core_math::clamp_to_headroom(fee, core_math::tighter_headroom(ceiling_headroom, cost_headroom))
+        }
+
+        /// Lists the servicing fees due on each fee cycle date from the next one up to `to`,
+        /// while the contract is open. A fee on the notional is charged on the current
+        /// principal over the cycle. Fees stop once they reach the total-cost cap.
+        fn fee_charges(&self, to: i64) -> Vec<(Instant, Decimal)> {
+            let mut charges = Vec::new();
+            let (Some(fees), Some(next_fee_date)) = (self.fees, self.next_fee_date) else {
+                return charges;
+            };
+            if self.status.is_terminal() {
+                return charges;
+            }
+            let mut headroom = self.total_cost_headroom();
+            let mut fee_date = next_fee_date.seconds_since_unix_epoch;
+            while fee_date <= to {
+                let fee = match fees.fee_basis {
+                    FeeBasis::Absolute => fees.fee_rate,
+                    FeeBasis::Notional => self.round(core_math::simple_interest(
+                        self.principal,
+                        fees.fee_rate,
+                        self.year_fraction(fee_date - fees.fee_cycle, fee_date),
+                    )),
+                };
+                let fee = headroom.map_or(fee, |headroom| fee.min(headroom));
+                headroom = headroom.map(|headroom| headroom - fee);
+                if fee > Decimal::ZERO {
+                    charges.push((Self::to_instant(fee_date), fee));
+                }
+                fee_date += fees.fee_cycle;
+            }
+            charges
+        }
+
+        /// Projects the servicing fees owed at `to`: those charged and not yet paid, plus
+        /// those falling due by then.
+        fn projected_fees(&self, to: i64) -> Decimal {
+            self.fee_charges(to).iter().fold(self.accrued_fees, |total, (_, fee)| total + *fee)
+        }
+
+        /// Picks the penalty rate for the bracket the overdue duration falls into.
+        /// Below the first threshold, or without tiers, the flat penalty rate applies.
+        fn penalty_rate_for(&self, days_overdue: i64) -> Decimal {
+            core_math::tiered_rate(&self.penalty_tiers, self.penalty_rate, days_overdue)
+        }
+
+        /// The reference rate an indexed penalty is set off: the oracle benchmark when the
+        /// contract has a rate oracle, otherwise the contract's own current rate.
+        fn penalty_reference_rate(&self) -> Decimal {
+            match self.rate_oracle {
+                Some(_) => self.oracle_rate(),
+                None => self.interest_rate,
+            }
+        }
+
+        /// Clamps a newly set rate to the life floor and cap.
+        fn bounded_rate(&self, rate: Decimal) -> Decimal {
+            let rate = self.rate_floor.map_or(rate, |floor| rate.max(floor));
+            self.rate_cap.map_or(rate, |cap| rate.min(cap))
+        }
+
+        /// Logs when a newly set rate sits at the life cap or floor.
+        fn log_rate_bound(&mut self, timestamp: Instant, rate: Decimal) {
+            if self.rate_cap == Some(rate) {
+                self.log(timestamp, format!("RateCapped: rate held at the cap of {}", rate));
+            } else if self.rate_floor == Some(rate) {
+                self.log(timestamp, format!("RateFloored: rate held at the floor of {}", rate));
+            }
+        }
+
+        /// Averages the rates in the rate history over time, from the start date up to `to`.
+        fn weighted_average_rate(&self, to: Instant) -> Decimal {
+            let periods = core_math::rate_periods(
+                &self.rate_history,
+                self.start_date.seconds_since_unix_epoch,
+                to.seconds_since_unix_epoch,
+            );
+            core_math::time_weighted_rate(&periods).unwrap_or(self.interest_rate)
+        }
+
+        /// Returns how much more interest, penalties and fees may accrue under the total-cost cap.
+        fn total_cost_headroom(&self) -> Option<Decimal> {
+            self.total_cost_cap.map(|cap| {
+                core_math::headroom(cap, self.total_interest_accrued + self.total_penalties_accrued + self.total_fees_accrued)
+            })
+        }
+
+        /// Returns how much more interest may be outstanding under the interest ceiling.
+        ///
+        /// The ceiling bounds the accrued interest balance, not lifetime charges: paying
+        /// interest down frees headroom again. Penalties are a separate balance and do not
+        /// count toward it. Capitalized interest becomes principal, so it leaves the
+        /// interest balance and raises the ceiling with the principal.
+        fn interest_ceiling_headroom(&self) -> Option<Decimal> {
+            self.max_interest_multiple
+                .map(|multiple| core_math::headroom(self.principal * multiple, self.interest_due()))
+        }
+
+        /// Returns how much more penalty may accrue under both the penalty and total-cost caps.
+        fn penalty_headroom(&self) -> Option<Decimal> {
+            let penalty_headroom = self
+                .penalty_cap
+                .map(|cap| core_math::headroom(self.original_principal * cap, self.total_penalties_accrued));
+            core_math::tighter_headroom(penalty_headroom, self.total_cost_headroom())
+        }
+
+        /// Returns the balance penalties are charged on, per the configured penalty base.
+        fn penalty_base_amount(&self, accrued_interest: Decimal) -> Decimal {
+            self.penalty_base.amount(self.principal, accrued_interest)
+        }
+
+        /// Logs each cap and emits a `CapReached` event the first time it is reached.
+        fn log_caps_reached(&mut self) {
+            let timestamp = self.last_interest_calculation_date;
+            if !self.penalty_cap_reached {
+                if let Some(cap) = self.penalty_cap {
+                    let limit = self.original_principal * cap;
+                    if self.total_penalties_accrued >= limit {
+                        self.penalty_cap_reached = true;
+                        self.log(timestamp, "CapReached: penalty cap".to_string());
+                        Runtime::emit_event(CapReached { timestamp: timestamp.seconds_since_unix_epoch, cap: CostCap::Penalty, limit });
+                    }
+                }
+            }
+            if let Some(limit) = self.total_cost_cap {
+                if !self.total_cost_cap_reached && self.total_cost_headroom() == Some(Decimal::ZERO) {
+                    self.total_cost_cap_reached = true;
+                    self.log(timestamp, "CapReached: total cost cap".to_string());
+                    Runtime::emit_event(CapReached { timestamp: timestamp.seconds_since_unix_epoch, cap: CostCap::TotalCost, limit });
+                }
+            }
+        }
+
+        /// Calculates the interest accrued on the current principal between two dates.
+        /// Panics if `to` is earlier than `from`, so time can never run backwards.
+        /// Scheduled rate steps within the period are honoured: the current rate applies
+        /// up to the first step, each scheduled rate up to the next.
+        fn interest_between(&self, from: Instant, to: Instant) -> PreciseDecimal {
+            ensure(
+                to >= from,
+                CallMoneyError::BackdatedTimestamp {
+                    supplied: to.seconds_since_unix_epoch,
+                    minimum: from.seconds_since_unix_epoch,
+                },
+            );
+
+            // Regular interest stops at the due date unless it continues through the grace period
+            let (from, to) = self.grace_interest.accrual_window(self.maturity_date.or(self.call_due_date), from, to);
+            core_math::scheduled_periods(
+                self.interest_rate,
+                &self.rate_schedule,
+                from.seconds_since_unix_epoch,
+                to.seconds_since_unix_epoch,
+            )
+            .into_iter()
+            .fold(PreciseDecimal::ZERO, |interest, (period_start, period_end, rate)| {
+                interest + core_math::simple_interest(self.principal, rate, self.year_fraction(period_start, period_end))
+            })
+        }
+
+        /// Returns the date regular interest stops accruing at, if the whole loan has fallen due
+        /// and the grace-period treatment stops it there.
+        fn interest_stop_date(&self) -> Option<Instant> {
+            self.grace_interest.interest_stop_date(self.maturity_date.or(self.call_due_date))
+        }
+
+        /// Switches to every scheduled rate that took effect after the last calculation, up to `to`.
+        fn apply_scheduled_rates(&mut self, to: Instant) {
+            let steps: Vec<(Instant, Decimal)> = self.rate_schedule
+                .iter()
+                .filter(|(effective_date, _)| *effective_date > self.last_interest_calculation_date && *effective_date <= to)
+                .cloned()
+                .collect();
+            for (effective_date, rate) in steps {
+                self.interest_rate = rate;
+                self.rate_history.push((effective_date, rate));
+                self.settlement_quote = None;
+                self.log(effective_date, format!("Scheduled rate step to {}", rate));
+            }
+        }
+
+        /// Returns the year fraction interest accrues over between two dates under the
+        /// day count convention, to the second. When the calendar restricts accrual,
+        /// only business time is counted over the convention's annual basis.
+        fn year_fraction(&self, from: i64, to: i64) -> PreciseDecimal {
+            if self.calendar.accrue_business_days_only {
+                let business_seconds = self.calendar.business_seconds_between(from, to);
+                PreciseDecimal::from(business_seconds) / PreciseDecimal::from(self.day_count.basis() * SECONDS_PER_DAY)
+            } else {
+                core_math::year_fraction(from, to, self.day_count)
+            }
+        }
+
+        /// Returns when the grace period after a due date ends, counting business
+        /// days only when the calendar restricts accrual.
+        fn grace_period_end(&self, due_date: i64) -> i64 {
+            if self.calendar.accrue_business_days_only {
+                self.calendar.add_business_seconds(due_date, self.grace_period)
+            } else {
+                self.calendar.adjust(due_date + self.grace_period)
+            }
+        }
+
+        /// Records a balance change in the ledger, folding it into the replayed balances
+        /// and indexing its event by kind.
+        fn record(&mut self, entry: LedgerEntry) {
+            let id = self.ledger_count;
+            let event = self.replayed_balances.apply(id, &entry);
+            let of_kind = self.event_kind_counts.entry(event.kind).or_insert(0);
+            self.events_by_kind.insert((event.kind, *of_kind), id);
+            *of_kind += 1;
+            if let LedgerEntry::Payment { late, .. } = entry {
+                if late {
+                    self.late_payments += 1;
+                } else {
+                    self.on_time_payments += 1;
+                }
+                self.last_payment = Some(id);
+            }
+            self.contract_events.insert(id, event);
+            self.ledger.insert(id, entry);
+            self.ledger_count += 1;
+        }
+
+        /// Appends an entry to the transaction history.
+        fn log(&mut self, timestamp: Instant, description: String) {
+            self.transaction_history.insert(self.history_count, HistoryEntry { timestamp, description });
+            self.history_count += 1;
+        }
+
+        /// Asserts that the creditor note is present in the auth zone.
+        fn require_lender(&self) {
+            Runtime::assert_access_rule(rule!(require(self.creditor_note.clone())));
+        }
+
+        /// Asserts that the component owner's badge is present in the auth zone.
+        fn require_owner(&self) {
+            Runtime::assert_access_rule(rule!(require(self.owner_badge)));
+        }
+
+        /// Rejects state changes while the contract is paused.
+        fn ensure_not_paused(&self) {
+            ensure(!self.paused, CallMoneyError::Paused);
+        }
+
+        /// Asserts that both the creditor note and the borrower's badge are present in the auth zone.
+        fn require_both_parties(&self) {
+            Runtime::assert_access_rule(rule!(require(self.creditor_note.clone()) && require(self.borrower)));
+        }
+
+        /// Asserts that the borrower's badge is present in the auth zone.
+        fn require_borrower(&self) {
+            Runtime::assert_access_rule(rule!(require(self.borrower)));
+        }
+
+        /// Identifies which party a proof belongs to, rejecting anyone else.
+        fn party_of(&self, proof: Proof) -> ContractParty {
+            let resource = proof.skip_checking().resource_address();
+            if resource == self.creditor_note.resource_address() {
+                ContractParty::Lender
+            } else if resource == self.borrower {
+                ContractParty::Borrower
+            } else {
+                CallMoneyError::NotAParty { resource }.raise()
+            }
+        }
+
+        /// Describes who made a payment, for the transaction history.
+        fn payer_label(&self, payer: Option<Proof>) -> String {
+            match payer {
+                Some(proof) => {
+                    let resource = proof.skip_checking().resource_address();
+                    if resource == self.borrower {
+                        "borrower".to_string()
+                    } else {
+                        format!("third party {:?}", resource)
+                    }
+                }
+                None => "unidentified payer".to_string(),
+            }
+        }
+
+        /// Returns the start of the day containing `timestamp`.
+        fn day_boundary(timestamp: i64) -> Instant {
+            Instant::new(calendar::day_boundary(Self::to_instant(timestamp).seconds_since_unix_epoch))
+        }
+
+        /// Converts a Unix timestamp in seconds to an `Instant`, rejecting
+        /// negative values and values that look like milliseconds.
+        fn to_instant(timestamp: i64) -> Instant {
+            ensure(
+                (0..MAX_TIMESTAMP).contains(&timestamp),
+                CallMoneyError::InvalidTimestamp { supplied: timestamp },
+            );
+            Instant::new(timestamp)
+        }
+
+        /// Checks that a date is a valid timestamp no earlier than the last interest calculation.
+        fn check_date(&self, date: i64) -> Result<(), CallMoneyError> {
+            check_date(date, self.last_interest_calculation_date.seconds_since_unix_epoch)
+        }
+
+        /// Checks whether a repayment of `amount` could be accepted at `as_of`, without
+        /// the minimum-repayment check that depends on the balance at that date.
+        fn check_repay(&self, amount: Decimal, as_of: i64) -> Result<(), CallMoneyError> {
+            if self.status == ContractStatus::Repaid {
+                return Err(CallMoneyError::AlreadyRepaid);
+            }
+            self.check_open()?;
+            if amount <= Decimal::ZERO {
+                return Err(CallMoneyError::InvalidAmount { field: "amount".to_string() });
+            }
+            self.check_date(as_of)
+        }
+
+        /// The current time: the ledger clock, unless overridden in test mode.
+        fn now(&self) -> i64 {
+            self.time_override
+                .unwrap_or_else(Clock::current_time_rounded_to_seconds)
+                .seconds_since_unix_epoch
+        }
+
+        /// Moves the contract to `next`, rejecting moves the lifecycle does not allow.
+        fn transition(&mut self, next: ContractStatus) {
+            ensure(
+                self.status.can_transition_to(next),
+                CallMoneyError::InvalidTransition { from: self.status, to: next },
+            );
+            Runtime::emit_event(StatusChanged { from: self.status, to: next });
+            self.status = next;
+        }
+
+        /// Checks that the contract is in force: accepted and not yet in a terminal status.
+        fn check_open(&self) -> Result<(), CallMoneyError> {
+            self.status.check_open()
+        }
+
+        /// Checks whether the contract could be called at `as_of`.
+        fn check_call(&self, as_of: i64) -> Result<(), CallMoneyError> {
+            self.check_open()?;
+            if !matches!(self.status, ContractStatus::Active | ContractStatus::MarginCall) {
+                return Err(CallMoneyError::NotActive { current: self.status });
+            }
+            self.check_date(as_of)
+        }
+
+        /// Retrieves the current details of the contract.
+        ///
+        /// # Returns
+        /// A ContractDetails of the current state
+        pub fn get_details(&self) -> ContractDetails {
+            ContractDetails {
+                lender: self.lender,
+                borrower: self.borrower,
+                principal: self.principal,
+                interest_rate: self.interest_rate,
+                start_date: self.start_date.seconds_since_unix_epoch,
+                accrued_interest: self.interest_due(),
+                accrued_penalties: self.penalties_due(),
+                status: self.status,
+                due_date: self.call_due_date.or(self.maturity_date).map(|date| date.seconds_since_unix_epoch),
+                collateral: self.get_collateral(),
+            }
+        }
+
+        /// Retrieves the current details of the contract as a tuple.
+        ///
+        /// Deprecated: kept for callers of the original tuple form; use `get_details`.
+        ///
+        /// # Returns
+        /// A tuple of (lender, borrower, principal, interest_rate, start_date, accrued_interest, status, collateral)
+        pub fn get_details_tuple(&self) -> ContractDetailsTuple {
+            let details = self.get_details();
+            (
+                details.lender,
+                details.borrower,
+                details.principal,
+                details.interest_rate,
+                details.start_date,
+                details.accrued_interest,
+                details.status,
+                details.collateral,
+            )
+        }
+
+        /// Retrieves the accrued interest and penalties both at full precision and as
+        /// rounded under the contract's rounding convention. The rounded amounts are
+        /// what a payment settles.
+        ///
+        /// # Returns
+        /// A tuple of (raw_interest, rounded_interest, raw_penalties, rounded_penalties)
+        pub fn get_accrued_balances(&self) -> (PreciseDecimal, Decimal, PreciseDecimal, Decimal) {
+            (self.accrued_interest, self.interest_due(), self.accrued_penalties, self.penalties_due())
+        }
+
+        /// Retrieves a summary of the contract, with dates as `Instant`s.
+        ///
+        /// # Returns
+        /// A ContractSummary of the current state
+        pub fn get_summary(&self) -> ContractSummary {
+            ContractSummary {
+                principal: self.principal,
+                accrued_interest: self.interest_due(),
+                accrued_fees: self.accrued_fees,
+                accrued_penalties: self.penalties_due(),
+                interest_rate: self.interest_rate,
+                status: self.status,
+                start_date: self.start_date,
+                last_interest_calculation_date: self.last_interest_calculation_date,
+            }
+        }
+
+        /// Removes and returns the history entries older than `before`, up to the first
+        /// entry that is not, leaving a single marker entry in their place. The caller is
+        /// responsible for persisting the returned entries off-ledger.
+        ///
+        /// Entries from the current accrual period are always kept, so `before` may
+        /// not be later than the last interest calculation date.
+        ///
+        /// # Arguments
+        /// * `before` - Entries strictly older than this Unix timestamp are archived
+        ///
+        /// # Returns
+        /// The archived entries, oldest first
+        pub fn archive_history(&mut self, before: i64) -> Vec<HistoryEntry> {
+            self.ensure_not_paused();
+            self.require_lender();
+            let before = Self::to_instant(before);
+            ensure(
+                before <= self.last_interest_calculation_date,
+                frozen("entries from the current accrual period cannot be archived"),
+            );
+
+            let archived = entries_before(
+                (self.history_start..self.history_count)
+                    .map(|sequence| self.transaction_history.get(&sequence).unwrap().clone()),
+                before,
+            );
+            for _ in &archived {
+                self.transaction_history.remove(&self.history_start);
+                self.history_start += 1;
+            }
+
+            // The marker takes the place of the last archived entry
+            if !archived.is_empty() {
+                self.history_start -= 1;
+                self.transaction_history.insert(self.history_start, HistoryEntry::archive_marker(archived.len(), before));
+            }
+            archived
+        }
+
+        /// Sets the oracle benchmark rates are read from, by agreement of both parties.
+        /// While an oracle is set, rate resets can only be taken from it.
+        ///
+        /// # Arguments
+        /// * `oracle` - The oracle component, or `None` to go back to lender-supplied rates
+        /// * `benchmark` - The benchmark to read
+        /// * `max_rate_age` - The oldest rate accepted, in seconds
+        pub fn set_rate_oracle(&mut self, oracle: Option<ComponentAddress>, benchmark: String, max_rate_age: i64) {
+            self.ensure_not_paused();
+            self.require_both_parties();
+            ensure(max_rate_age >= 0, CallMoneyError::InvalidPeriod { field: "max_rate_age".to_string() });
+            ensure(oracle.is_none() || !benchmark.is_empty(), invalid_terms("an oracle requires a benchmark"));
+            self.rate_oracle = oracle.map(Global::<AnyComponent>::from);
+            self.log(self.last_interest_calculation_date, match oracle {
+                Some(oracle) => format!("Rate oracle set: {} ({})", oracle, benchmark),
+                None => "Rate oracle removed".to_string(),
+            });
+            self.oracle_benchmark = benchmark;
+            self.max_rate_age = max_rate_age;
+        }
+
+        /// Registers the component notified of lifecycle transitions.
+        ///
+        /// Notifications are queued for the observer to pull by default. With `push`
+        /// set they are delivered by calling `on_clm_event` on the observer instead; a
+        /// failing observer then fails the transition, so only enable it for trusted
+        /// observers.
+        ///
+        /// # Arguments
+        /// * `observer` - The observer component, or `None` to stop notifying
+        /// * `push` - Whether to call the observer directly
+        pub fn set_observer(&mut self, observer: Option<ComponentAddress>, push: bool) {
+            self.ensure_not_paused();
+            self.require_lender();
+            self.observer = observer;
+            self.push_to_observer = observer.is_some() && push;
+            self.pending_notifications.clear();
+            self.log(self.last_interest_calculation_date, match observer {
+                Some(observer) => format!("Observer set: {}", observer),
+                None => "Observer removed".to_string(),
+            });
+        }
+
+        /// Retrieves the notifications queued for the observer, oldest first.
+        pub fn pending_notifications(&self) -> Vec<(Instant, ObserverEvent)> {
+            self.pending_notifications.clone()
+        }
+
+        /// Clears the queued notifications once the observer has processed them.
+        /// Only the observer component itself may clear the queue.
+        pub fn clear_notifications(&mut self) {
+            self.ensure_not_paused();
+            let Some(observer) = self.observer else { invalid_terms("no observer is registered").raise() };
+            Runtime::assert_access_rule(rule!(require(global_caller(observer))));
+            self.pending_notifications.clear();
+        }
+
+        /// Retrieves the terms currently in force, in the form the contract is instantiated
+        /// from. Terms changed over the life of the contract, such as the rate, are reported
+        /// as they stand now; the interest payment anchor is the next payment date.
+        pub fn get_terms(&self) -> ContractTerms {
+            ContractTerms {
+                contract_id: Some(self.contract_id.clone()),
+                contract_role: self.contract_role,
+                currency: self.repayment_vault.resource_address(),
+                lender: self.lender,
+                borrower: self.borrower,
+                reference: self.reference.clone(),
+                interest_rate: self.interest_rate,
+                allow_negative_rate: self.allow_negative_rate,
+                day_count: self.day_count,
+                rounding: self.rounding,
+                capitalization_cycle: self.capitalization_cycle,
+                interest_payment_cycle: self.interest_payment_cycle,
+                interest_payment_anchor: self.next_interest_payment_date.map(|date| date.seconds_since_unix_epoch),
+                rate_reset: self.rate_reset,
+                rate_cap: self.rate_cap,
+                rate_floor: self.rate_floor,
+                rate_schedule: self.rate_schedule.iter().map(|(date, rate)| (date.seconds_since_unix_epoch, *rate)).collect(),
+                start_date: self.start_date.seconds_since_unix_epoch,
+                notice_period: self.notice_period,
+                grace_period: self.grace_period,
+                grace_interest: self.grace_interest,
+                notice_start: self.notice_start,
+                calendar: self.calendar.clone(),
+                penalty_type: self.penalty_type,
+                penalty_rate: self.penalty_rate,
+                penalty_tiers: self.penalty_tiers.clone(),
+                penalty_base: self.penalty_base,
+                prepayment: self.prepayment,
+                fees: self.fees,
+                withholding: self.withholding,
+                keeper_reward: self.keeper_reward,
+                min_partial_repayment: self.min_partial_repayment,
+                settlement_resource: self.settlement_resource,
+                accepted_collateral: self.accepted_collateral.clone(),
+                min_collateral_ratio: self.min_collateral_ratio,
+                max_ltv: self.max_ltv,
+                close_factor: self.close_factor,
+                liquidation_bonus: self.liquidation_bonus,
+                penalty_cap: self.penalty_cap,
+                total_cost_cap: self.total_cost_cap,
+                max_interest_multiple: self.max_interest_multiple,
+                royalty_config: Some(self.royalty_config.clone()),
+                credit_limit: self.credit_limit,
+                commitment_fee_rate: self.commitment_fee_rate,
+                test_mode: self.test_mode,
+            }
+        }
+
+        /// Retrieves the creditor note whose holder acts as the lender.
+        pub fn get_creditor_note(&self) -> NonFungibleGlobalId {
+            self.creditor_note.clone()
+        }
+
+        /// Retrieves the contract's stable identifier.
+        pub fn get_contract_id(&self) -> String {
+            self.contract_id.clone()
+        }
+
+        /// Retrieves the deal and counterparty reference data.
+        pub fn get_reference_data(&self) -> ContractReference {
+            self.reference.clone()
+        }
+
+        /// Updates one of the reference data fields that may change over the life of
+        /// the deal. Only `booking_entity` may be changed; the deal id, LEIs and
+        /// currency are fixed at instantiation.
+        ///
+        /// # Arguments
+        /// * `key` - The name of the field to update
+        /// * `value` - The new value
+        pub fn update_reference_data(&mut self, key: String, value: String) {
+            self.ensure_not_paused();
+            self.require_lender();
+            match key.as_str() {
+                "booking_entity" => {
+                    ensure(!value.is_empty(), invalid_terms("the booking entity is required"));
+                    self.reference.booking_entity = value.clone();
+                }
+                _ => invalid_terms(&format!("reference data field cannot be updated: {}", key)).raise(),
+            }
+            self.log(self.last_interest_calculation_date, format!("Reference data updated: {} = {}", key, value));
+        }
+
+        /// Retrieves the royalties charged on the high-value methods.
+        ///
+        /// # Returns
+        /// The royalty configuration set at instantiation
+        pub fn get_royalty_config(&self) -> RoyaltyConfig {
+            self.royalty_config.clone()
+        }
+
+        /// Compares the balances re-derived from the ledger, folded in entry by entry from
+        /// the initiation entry as it was recorded, with the live balances. A mismatch is
+        /// reported rather than causing a panic, so monitoring can alert on it.
+        ///
+        /// # Returns
+        /// The replayed and stored balances, and whether they match
+        pub fn replay_check(&self) -> ReplayResult {
+            let replayed = self.replayed_balances;
+            let stored = core_math::Balances {
+                principal: self.principal,
+                accrued_interest: self.interest_due(),
+                accrued_fees: self.accrued_fees,
+                accrued_penalties: self.penalties_due(),
+            };
+
+            // Differences this small are rounding noise
+            ReplayResult {
+                matches: replayed.matches(&stored, dec!("0.000000001")),
+                replayed_principal: replayed.principal,
+                stored_principal: self.principal,
+                replayed_accrued_interest: replayed.accrued_interest,
+                stored_accrued_interest: self.interest_due(),
+                replayed_accrued_penalties: replayed.accrued_penalties,
+                stored_accrued_penalties: self.penalties_due(),
+            }
+        }
+
+        /// Retrieves the balance-changing events as typed records, so integrators can
+        /// reconcile cash flows programmatically. The transaction history remains the
+        /// human-readable log.
+        ///
+        /// # Returns
+        /// A vector of contract events, oldest first
+        pub fn get_events(&self) -> Vec<ContractEvent> {
+            self.get_history_page(0, self.ledger_count)
+        }
+
+        /// Retrieves one page of the contract events, so long-lived contracts can be
+        /// loaded lazily.
+        ///
+        /// # Arguments
+        /// * `offset` - The number of events to skip, oldest first
+        /// * `limit` - The largest number of events to return
+        ///
+        /// # Returns
+        /// A vector of at most `limit` contract events, oldest first
+        pub fn get_history_page(&self, offset: u64, limit: u64) -> Vec<ContractEvent> {
+            (offset..offset.saturating_add(limit).min(self.ledger_count))
+                .map(|id| self.contract_events.get(&id).unwrap().clone())
+                .collect()
+        }
+
+        /// Retrieves one page of the contract events of a single kind.
+        ///
+        /// # Arguments
+        /// * `kind` - The kind of event to return
+        /// * `offset` - The number of matching events to skip, oldest first
+        /// * `limit` - The largest number of events to return
+        ///
+        /// # Returns
+        /// A vector of at most `limit` contract events of that kind, oldest first
+        pub fn get_history_by_kind(&self, kind: EventKind, offset: u64, limit: u64) -> Vec<ContractEvent> {
+            let of_kind = self.event_kind_counts.get(&kind).copied().unwrap_or(0);
+            (offset..offset.saturating_add(limit).min(of_kind))
+                .map(|position| {
+                    let id = *self.events_by_kind.get(&(kind, position)).unwrap();
+                    self.contract_events.get(&id).unwrap().clone()
+                })
+                .collect()
+        }
+
+        /// Retrieves the balance ledger the replay check folds over.
+        ///
+        /// # Returns
+        /// A vector of ledger entries, oldest first
+        pub fn get_ledger(&self) -> Vec<LedgerEntry> {
+            (0..self.ledger_count).map(|id| self.ledger.get(&id).unwrap().clone()).collect()
+        }
+
+        /// Retrieves the terms frozen at the most recent call. Later changes to the
+        /// contract leave the snapshot untouched; only a new call replaces it.
+        ///
+        /// # Returns
+        /// The call snapshot, or None if the contract has never been called
+        pub fn get_call_snapshot(&self) -> Option<CallSnapshot> {
+            self.call_snapshot.clone()
+        }
+
+        /// Retrieves every interest rate applied to the contract.
+        ///
+        /// # Returns
+        /// A vector of (effective date, rate) pairs in the order they were applied
+        pub fn get_rate_history(&self) -> Vec<(Instant, Decimal)> {
+            self.rate_history.clone()
+        }
+
+        /// Retrieves the full transaction history of the contract.
+        ///
+        /// # Returns
+        /// A vector of entries, each representing a transaction or status change
+        pub fn get_transaction_history(&self) -> Vec<HistoryEntry> {
+            (self.history_start..self.history_count)
+                .filter_map(|sequence| self.transaction_history.get(&sequence).map(|entry| entry.clone()))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal() -> Decimal {
+        dec!(100)
+    }
+
+    const START: i64 = 1_700_000_000;
+
+    /// Valid terms every test starts from.
+    fn terms() -> ContractTerms {
+        ContractTerms {
+            contract_id: None,
+            contract_role: ContractRole::RealPositionAsset,
+            currency: XRD,
+            lender: XRD,
+            borrower: XRD,
+            reference: ContractReference {
+                deal_id: "DEAL-1".to_string(),
+                lender_lei: "5493001KJTIIGC8Y1R12".to_string(),
+                borrower_lei: "529900T8BM49AURSDO55".to_string(),
+                booking_entity: "LDN".to_string(),
+                currency_code: "USD".to_string(),
+            },
+            interest_rate: dec!("0.05"),
+            allow_negative_rate: false,
+            day_count: DayCountConvention::Actual365,
+            rounding: RoundingConvention::Down,
+            capitalization_cycle: None,
+            interest_payment_cycle: None,
+            interest_payment_anchor: None,
+            rate_reset: None,
+            rate_cap: None,
+            rate_floor: None,
+            rate_schedule: vec![],
+            start_date: START,
+            notice_period: 2 * SECONDS_PER_DAY,
+            grace_period: SECONDS_PER_DAY,
+            grace_interest: GraceInterest::Continue,
+            notice_start: NoticeStart::Call,
+            calendar: BusinessDayCalendar::default(),
+            penalty_type: PenaltyType::DailyRate,
+            penalty_rate: dec!("0.1"),
+            penalty_tiers: vec![],
+            penalty_base: PenaltyBase::PrincipalOnly,
+            prepayment: None,
+            fees: None,
+            withholding: None,
+            keeper_reward: None,
+            min_partial_repayment: Decimal::ZERO,
+            settlement_resource: None,
+            accepted_collateral: vec![],
+            min_collateral_ratio: Decimal::ZERO,
+            max_ltv: Decimal::ZERO,
+            close_factor: Decimal::ONE,
+            liquidation_bonus: Decimal::ZERO,
+            penalty_cap: None,
+            total_cost_cap: None,
+            max_interest_multiple: None,
+            royalty_config: None,
+            credit_limit: None,
+            commitment_fee_rate: Decimal::ZERO,
+            test_mode: false,
+        }
+    }
+
+    #[test]
+    fn default_terms_are_valid() {
+        terms().validate(principal());
+    }
+
+    #[test]
+    fn a_partial_haircut_is_accepted() {
+        ContractTerms { accepted_collateral: vec![(XRD, dec!("0.3"))], ..terms() }.validate(principal());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidRate(field=accepted_collateral)")]
+    fn a_full_haircut_is_rejected() {
+        ContractTerms { accepted_collateral: vec![(XRD, Decimal::ONE)], ..terms() }.validate(principal());
+    }
+
+    #[test]
+    #[should_panic(expected = "collateral resource listed more than once")]
+    fn a_collateral_resource_is_listed_once() {
+        ContractTerms { accepted_collateral: vec![(XRD, dec!("0.1")), (XRD, dec!("0.2"))], ..terms() }.validate(principal());
+    }
+
+    #[test]
+    fn performance_follows_the_status() {
+        assert_eq!(ContractStatus::Active.performance(), ContractPerformance::Performant);
+        assert_eq!(ContractStatus::Called.performance(), ContractPerformance::Performant);
+        assert_eq!(ContractStatus::Delinquent.performance(), ContractPerformance::Delayed);
+        assert_eq!(ContractStatus::Defaulted.performance(), ContractPerformance::Default);
+        assert_eq!(ContractStatus::Repaid.performance(), ContractPerformance::Performant);
+    }
+
+    #[test]
+    fn stale_timestamps_are_rejected() {
+        assert_eq!(check_date(START, START), Ok(()));
+        assert_eq!(check_date(START + 1, START), Ok(()));
+        assert_eq!(
+            check_date(START - 1, START),
+            Err(CallMoneyError::BackdatedTimestamp { supplied: START - 1, minimum: START })
+        );
+        assert_eq!(check_date(-1, 0), Err(CallMoneyError::InvalidTimestamp { supplied: -1 }));
+        // A millisecond timestamp is out of range rather than far in the future
+        assert_eq!(check_date(START * 1_000, START), Err(CallMoneyError::InvalidTimestamp { supplied: START * 1_000 }));
+    }
+
+    #[test]
+    fn the_penalty_base_decides_what_is_penalized() {
+        // 1,000 principal with 50 of interest accrued, ten days overdue at 10% a year
+        let (principal, accrued_interest) = (dec!(1000), dec!(50));
+        let penalty = |base: PenaltyBase| {
+            let balance = base.amount(principal, accrued_interest);
+            core_math::compute_interest(balance, dec!("0.1"), START, START + 10 * SECONDS_PER_DAY, DayCountConvention::Actual365)
+        };
+        assert_eq!(PenaltyBase::default(), PenaltyBase::PrincipalOnly);
+        assert_eq!(PenaltyBase::PrincipalOnly.amount(principal, accrued_interest), dec!(1000));
+        assert_eq!(PenaltyBase::TotalOutstanding.amount(principal, accrued_interest), dec!(1050));
+        assert!(penalty(PenaltyBase::TotalOutstanding) > penalty(PenaltyBase::PrincipalOnly));
+    }
+
+    #[test]
+    fn a_rolled_over_contract_can_be_called_again_and_repaid() {
+        use ContractStatus::*;
+        for (from, to) in [(Active, Called), (Called, Active), (Active, Called), (Called, Repaid)] {
+            assert!(from.can_transition_to(to), "{} -> {}", from, to);
+        }
+        assert!(!Defaulted.can_transition_to(Active));
+
+        // Interest is capitalized on rollover, so the second period accrues on the larger principal
+        let day_count = DayCountConvention::Actual365;
+        let rolled_over_at = START + 30 * SECONDS_PER_DAY;
+        let repaid_at = rolled_over_at + 30 * SECONDS_PER_DAY;
+        let first = core_math::compute_interest(principal(), dec!("0.05"), START, rolled_over_at, day_count);
+        let first = first.checked_truncate(RoundingMode::ToZero).unwrap();
+        let second = core_math::compute_interest(principal() + first, dec!("0.06"), rolled_over_at, repaid_at, day_count);
+        let second = second.checked_truncate(RoundingMode::ToZero).unwrap();
+        let ledger = [
+            LedgerEntry::Initiated { timestamp: Instant::new(START), principal: principal(), accrued_interest: Decimal::ZERO },
+            LedgerEntry::InterestAccrued { timestamp: Instant::new(rolled_over_at), rate: dec!("0.05"), amount: first },
+            LedgerEntry::InterestCapitalized { timestamp: Instant::new(rolled_over_at), amount: first },
+            LedgerEntry::InterestAccrued { timestamp: Instant::new(repaid_at), rate: dec!("0.06"), amount: second },
+            LedgerEntry::Payment {
+                timestamp: Instant::new(repaid_at),
+                penalties_paid: Decimal::ZERO,
+                fees_paid: Decimal::ZERO,
+                interest_paid: second,
+                principal_paid: principal() + first,
+                late: false,
+            },
+        ];
+        let mut balances = core_math::Balances::zero();
+        let totals: Vec<Decimal> = ledger.iter().enumerate().map(|(id, entry)| balances.apply(id as u64, entry).balance_after).collect();
+        assert_eq!(totals[2], principal() + first);
+        assert_eq!(balances.principal, Decimal::ZERO);
+        assert_eq!(balances.total(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn archiving_splits_the_history_at_the_cutoff() {
+        let entry = |timestamp: i64, description: &str| HistoryEntry {
+            timestamp: Instant::new(timestamp),
+            description: description.to_string(),
+        };
+        let history = vec![
+            entry(START, "Initiated"),
+            entry(START + 10, "Interest accrued"),
+            entry(START + 20, "Called"),
+            entry(START + 30, "Repaid"),
+        ];
+        let archived = entries_before(history.clone().into_iter(), Instant::new(START + 20));
+        let descriptions: Vec<&str> = archived.iter().map(|entry| entry.description.as_str()).collect();
+        assert_eq!(descriptions, ["Initiated", "Interest accrued"]);
+        // A cutoff at the first entry archives nothing, one past the last archives everything
+        assert!(entries_before(history.clone().into_iter(), Instant::new(START)).is_empty());
+        assert_eq!(entries_before(history.into_iter(), Instant::new(START + 31)).len(), 4);
+
+        let marker = HistoryEntry::archive_marker(2, Instant::new(START + 20));
+        assert_eq!(marker.timestamp, Instant::new(START + 20));
+        assert_eq!(marker.description, format!("Archived 2 entries up to {}", START + 20));
+    }
+
+    #[test]
+    fn partial_repayments_respect_the_minimum() {
+        let (total_due, minimum) = (dec!(1000), dec!(50));
+        assert_eq!(
+            check_minimum_repayment(dec!(49), total_due, minimum),
+            Err(CallMoneyError::BelowMinimumRepayment { amount: dec!(49), minimum })
+        );
+        assert_eq!(check_minimum_repayment(dec!(50), total_due, minimum), Ok(()));
+        // A full payoff below the minimum is accepted, as is an overpayment
+        assert_eq!(check_minimum_repayment(dec!(30), dec!(30), minimum), Ok(()));
+        assert_eq!(check_minimum_repayment(dec!(40), dec!(30), minimum), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidAmount(field=min_partial_repayment)")]
+    fn a_negative_minimum_repayment_is_rejected() {
+        ContractTerms { min_partial_repayment: dec!(-1), ..terms() }.validate(principal());
+    }
+
+    #[test]
+    fn zero_royalties_are_free() {
+        assert_eq!(RoyaltyConfig::royalty(Decimal::ZERO), RoyaltyAmount::Free);
+        assert_eq!(RoyaltyConfig::royalty(dec!(5)), RoyaltyAmount::Xrd(dec!(5)));
+        ContractTerms { royalty_config: Some(RoyaltyConfig { call_money: dec!(5), ..Default::default() }), ..terms() }
+            .validate(principal());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidAmount(field=royalty_config)")]
+    fn a_negative_royalty_is_rejected() {
+        ContractTerms { royalty_config: Some(RoyaltyConfig { split: dec!(-1), ..Default::default() }), ..terms() }
+            .validate(principal());
+    }
+
+    #[test]
+    fn negative_rates_need_the_flag_and_stay_above_minus_one() {
+        assert!(!interest_rate_in_range(dec!("-0.01"), false));
+        assert!(interest_rate_in_range(dec!("-0.01"), true));
+        assert!(interest_rate_in_range(dec!("-0.99"), true));
+        assert!(!interest_rate_in_range(dec!(-1), true));
+        assert!(!interest_rate_in_range(Decimal::ONE, true));
+        ContractTerms { interest_rate: dec!("-0.02"), allow_negative_rate: true, ..terms() }.validate(principal());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidRate(field=interest_rate)")]
+    fn a_negative_rate_is_rejected_by_default() {
+        ContractTerms { interest_rate: dec!("-0.02"), ..terms() }.validate(principal());
+    }
+
+    #[test]
+    fn zero_interest_and_zero_penalty_rates_are_valid() {
+        assert!(interest_rate_in_range(Decimal::ZERO, false));
+        ContractTerms { interest_rate: Decimal::ZERO, ..terms() }.validate(principal());
+        ContractTerms { penalty_rate: Decimal::ZERO, ..terms() }.validate(principal());
+    }
+
+    #[test]
+    fn a_zero_rate_loan_owes_only_principal_and_penalties() {
+        let day_count = DayCountConvention::Actual365;
+        let due_at = START + 30 * SECONDS_PER_DAY;
+        let repaid_at = due_at + 10 * SECONDS_PER_DAY;
+        assert_eq!(core_math::compute_interest(principal(), Decimal::ZERO, START, due_at, day_count), PreciseDecimal::ZERO);
+
+        // Repaid ten days late: the penalty is the only charge on top of the principal
+        let penalty = core_math::compute_interest(principal(), dec!("0.1"), due_at, repaid_at, day_count);
+        let penalty = penalty.checked_truncate(RoundingMode::ToZero).unwrap();
+        let mut balances = core_math::Balances::zero();
+        balances.apply(0, &LedgerEntry::Initiated { timestamp: Instant::new(START), principal: principal(), accrued_interest: Decimal::ZERO });
+        balances.apply(1, &LedgerEntry::PenaltyApplied { timestamp: Instant::new(repaid_at), rate: dec!("0.1"), amount: penalty });
+        assert_eq!(balances.total(), principal() + penalty);
+
+        let allocation = core_math::allocate_payment(balances.total(), penalty, Decimal::ZERO, Decimal::ZERO, principal());
+        assert_eq!((allocation.penalties_paid, allocation.interest_paid, allocation.principal_paid), (penalty, Decimal::ZERO, principal()));
+        assert_eq!(allocation.excess, Decimal::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "penalty tier thresholds must be ascending")]
+    fn penalty_tiers_must_be_ascending() {
+        ContractTerms {
+            penalty_tiers: vec![(30, dec!("0.2")), (5, dec!("0.1"))],
+            ..terms()
+        }
+        .validate(principal());
+    }
+
+    #[test]
+    fn a_term_conversion_stops_penalties_until_maturity() {
+        use ContractStatus::*;
+        assert!(Called.can_transition_to(Term));
+        assert!(!Active.can_transition_to(Term));
+        assert!(!Term.can_transition_to(Called) && !Term.can_transition_to(Active));
+
+        // Converted five days after the call fell due, with a maturity thirty days later
+        let call_due_date = START;
+        let converted_at = call_due_date + 5 * SECONDS_PER_DAY;
+        let maturity_date = converted_at + 30 * SECONDS_PER_DAY;
+        assert!(core_math::penalty_period(call_due_date, 0, None, converted_at).is_some());
+        // The maturity replaces the call's due date: nothing is overdue before it, interest still accrues
+        let before_maturity = maturity_date - SECONDS_PER_DAY;
+        assert_eq!(core_math::penalty_period(maturity_date, 0, Some(converted_at), before_maturity), None);
+        let interest = core_math::compute_interest(principal(), dec!("0.05"), converted_at, before_maturity, DayCountConvention::Actual365);
+        assert!(interest > PreciseDecimal::ZERO);
+        // Past maturity, penalties run from the maturity date
+        let after_maturity = maturity_date + SECONDS_PER_DAY;
+        assert_eq!(core_math::penalty_period(maturity_date, 0, Some(converted_at), after_maturity), Some((maturity_date, maturity_date)));
+    }
+
+    #[test]
+    fn default_contract_ids_are_deterministic() {
+        let id = default_contract_id(XRD, XRD, START);
+        assert_eq!(id, default_contract_id(XRD, XRD, START));
+        assert_ne!(id, default_contract_id(XRD, XRD, START + 1));
+        assert_eq!(id.len(), 64);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn error_codes_are_stable_and_unique() {
+        let field = || "principal".to_string();
+        let errors = [
+            CallMoneyError::InvalidPrincipal,
+            CallMoneyError::InvalidRate { field: field() },
+            CallMoneyError::InvalidPeriod { field: field() },
+            CallMoneyError::InvalidAmount { field: field() },
+            CallMoneyError::InvalidTerms { reason: field() },
+            CallMoneyError::InvalidTimestamp { supplied: -1 },
+            CallMoneyError::BackdatedTimestamp { supplied: 1, minimum: 2 },
+            CallMoneyError::NotActive { current: ContractStatus::Called },
+            CallMoneyError::NotCalled { current: ContractStatus::Active },
+            CallMoneyError::AlreadyRepaid,
+            CallMoneyError::BelowMinimumRepayment { amount: Decimal::ONE, minimum: dec!(2) },
+            CallMoneyError::DueDatePassed { due_date: START },
+            CallMoneyError::PenaltiesApplied,
+            CallMoneyError::ContractClosed { status: ContractStatus::Repaid },
+            CallMoneyError::InvalidTransition { from: ContractStatus::Repaid, to: ContractStatus::Active },
+            CallMoneyError::WrongResource { expected: XRD, got: XRD },
+            CallMoneyError::UnacceptedCollateral { resource: XRD },
+            CallMoneyError::CollateralLocked,
+            CallMoneyError::RateResetNotDue { next_reset: START },
+            CallMoneyError::StaleOracleData { published_at: START, max_age: 60 },
+            CallMoneyError::RepaymentNoticeGiven { termination_date: START },
+            CallMoneyError::NotOverdue,
+            CallMoneyError::Paused,
+            CallMoneyError::Frozen { reason: field() },
+            CallMoneyError::NotAParty { resource: XRD },
+        ];
+        let codes: Vec<u16> = errors.iter().map(CallMoneyError::code).collect();
+        assert_eq!(codes, [1, 2, 3, 4, 5, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29]);
+        for error in &errors {
+            let message = error.to_string();
+            assert!(message.starts_with(&format!("CLM_ERR[{}]: ", error.code())), "{}", message);
+        }
+    }
+
+    #[test]
+    fn errors_format_as_machine_parseable_messages() {
+        assert_eq!(CallMoneyError::NotActive { current: ContractStatus::Called }.to_string(), "CLM_ERR[12]: NotActive(current=Called)");
+        assert_eq!(
+            CallMoneyError::BackdatedTimestamp { supplied: 5, minimum: 7 }.to_string(),
+            "CLM_ERR[11]: BackdatedTimestamp(supplied=5, minimum=7)"
+        );
+        assert_eq!(CallMoneyError::Paused.to_string(), "CLM_ERR[27]: Paused");
+        assert_eq!(frozen("a dispute is open").to_string(), "CLM_ERR[28]: Frozen(reason=a dispute is open)");
+    }
+
+    #[test]
+    #[should_panic(expected = "CLM_ERR[14]: AlreadyRepaid")]
+    fn raising_an_error_panics_with_its_message() {
+        ensure(false, CallMoneyError::AlreadyRepaid);
+    }
+
+    #[test]
+    fn a_call_can_be_cancelled_only_before_it_falls_due() {
+        assert!(ContractStatus::Called.can_transition_to(ContractStatus::Active));
+        let due_date = START + 2 * SECONDS_PER_DAY;
+        assert_eq!(check_call_cancellable(Some(due_date), false, due_date - 1), Ok(()));
+        assert_eq!(check_call_cancellable(None, false, due_date + 1), Ok(()));
+        assert_eq!(check_call_cancellable(Some(due_date), false, due_date), Err(CallMoneyError::DueDatePassed { due_date }));
+        assert_eq!(check_call_cancellable(Some(due_date), true, due_date - 1), Err(CallMoneyError::PenaltiesApplied));
+    }
+
+    #[test]
+    #[should_panic(expected = "rate schedule dates must be ascending")]
+    fn rate_schedule_dates_must_be_ascending() {
+        ContractTerms {
+            rate_schedule: vec![(START + 20 * SECONDS_PER_DAY, dec!("0.06")), (START + 10 * SECONDS_PER_DAY, dec!("0.07"))],
+            ..terms()
+        }
+        .validate(principal());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidAmount(field=max_interest_multiple)")]
+    fn the_interest_ceiling_multiple_must_be_positive() {
+        ContractTerms { max_interest_multiple: Some(Decimal::ZERO), ..terms() }.validate(principal());
+    }
+
+    #[test]
+    fn payments_after_the_due_date_are_late() {
+        let payment = |day: i64, interest_paid: Decimal, penalties_paid: Decimal, late: bool| LedgerEntry::Payment {
+            timestamp: Instant::new(START + day * SECONDS_PER_DAY),
+            penalties_paid,
+            fees_paid: Decimal::ZERO,
+            interest_paid,
+            principal_paid: dec!(10),
+            late,
+        };
+        let ledger = [
+            LedgerEntry::Initiated { timestamp: Instant::new(START), principal: principal(), accrued_interest: Decimal::ZERO },
+            payment(30, dec!(2), Decimal::ZERO, false),
+            payment(60, dec!(3), Decimal::ZERO, false),
+            LedgerEntry::PenaltyApplied { timestamp: Instant::new(START + 95 * SECONDS_PER_DAY), rate: dec!("0.1"), amount: dec!(1) },
+            payment(95, dec!(4), dec!(1), true),
+        ];
+        assert_eq!(ledger[4].kind_and_amount(), (EventKind::Repayment, dec!(15)));
+        assert_eq!(ledger[3].kind_and_amount(), (EventKind::Penalty, dec!(1)));
+
+        // Only the payment after the due date on day 90 is late
+        let due_date = Some(Instant::new(START + 90 * SECONDS_PER_DAY));
+        for entry in &ledger {
+            if let LedgerEntry::Payment { timestamp, late, .. } = entry {
+                assert_eq!(paid_late(due_date, *timestamp), *late);
+            }
+        }
+        assert!(!paid_late(due_date, Instant::new(START + 90 * SECONDS_PER_DAY)));
+        assert!(!paid_late(None, Instant::new(START + 900 * SECONDS_PER_DAY)));
+    }
+
+    #[test]
+    fn closed_contracts_cannot_be_repaid_or_called_again() {
+        use ContractStatus::*;
+        for status in [Repaid, Defaulted, Terminated] {
+            assert!(status.is_terminal());
+            assert_eq!(status.check_open(), Err(CallMoneyError::ContractClosed { status }));
+            for next in [Offered, Active, Called, Term, Delinquent, MarginCall, Repaid, Defaulted, Terminated] {
+                assert!(!status.can_transition_to(next), "{} -> {}", status, next);
+            }
+        }
+        assert_eq!(Offered.check_open(), Err(CallMoneyError::NotActive { current: Offered }));
+        for status in [Active, Called, Term, Delinquent, MarginCall] {
+            assert!(!status.is_terminal());
+            assert_eq!(status.check_open(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn keepers_are_paid_only_when_a_penalty_is_applied() {
+        let reward = dec!(5);
+        assert_eq!(keeper_payout(reward, Decimal::ZERO, dec!(100)), Decimal::ZERO);
+        assert_eq!(keeper_payout(reward, dec!(12), dec!(100)), reward);
+        // The reward never exceeds the penalty it was earned on, nor the funded pool
+        assert_eq!(keeper_payout(reward, dec!(2), dec!(100)), dec!(2));
+        assert_eq!(keeper_payout(reward, dec!(12), dec!(3)), dec!(3));
+        assert_eq!(keeper_payout(reward, dec!(12), Decimal::ZERO), Decimal::ZERO);
+        ContractTerms { keeper_reward: Some(reward), ..terms() }.validate(principal());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidAmount(field=keeper_reward)")]
+    fn a_keeper_reward_as_large_as_the_principal_is_rejected() {
+        ContractTerms { keeper_reward: Some(principal()), ..terms() }.validate(principal());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidAmount(field=keeper_reward)")]
+    fn a_zero_keeper_reward_is_rejected() {
+        ContractTerms { keeper_reward: Some(Decimal::ZERO), ..terms() }.validate(principal());
+    }
+
+    #[test]
+    fn grace_interest_modes_decide_where_interest_stops_and_penalties_start() {
+        let day = |days: i64| Instant::new(START + days * SECONDS_PER_DAY);
+        let due_date = Some(day(30));
+        let interest = |(from, to): (Instant, Instant)| {
+            core_math::compute_interest(
+                principal(),
+                dec!("0.0365"),
+                from.seconds_since_unix_epoch,
+                to.seconds_since_unix_epoch,
+                DayCountConvention::Actual365,
+            )
+        };
+
+        // Interest continues through the grace period and beyond
+        let window = GraceInterest::Continue.accrual_window(due_date, day(0), day(40));
+        assert_eq!(window, (day(0), day(40)));
+        assert_eq!(interest(window), PreciseDecimal::from(dec!("0.4")));
+        assert!(!GraceInterest::Continue.penalties_skip_grace());
+
+        // Interest stops at the due date, but penalties still wait out the grace period
+        let window = GraceInterest::StopAtDueDate.accrual_window(due_date, day(0), day(40));
+        assert_eq!(window, (day(0), day(30)));
+        assert_eq!(interest(window), PreciseDecimal::from(dec!("0.3")));
+        assert!(!GraceInterest::StopAtDueDate.penalties_skip_grace());
+
+        // The penalty rate replaces interest from the due date itself
+        let window = GraceInterest::PenaltyRate.accrual_window(due_date, day(0), day(40));
+        assert_eq!(window, (day(0), day(30)));
+        assert!(GraceInterest::PenaltyRate.penalties_skip_grace());
+
+        // A period wholly after the due date accrues nothing once interest stops
+        assert_eq!(interest(GraceInterest::StopAtDueDate.accrual_window(due_date, day(35), day(40))), PreciseDecimal::ZERO);
+        // Until the whole loan has fallen due, every mode accrues as usual
+        assert_eq!(GraceInterest::PenaltyRate.interest_stop_date(None), None);
+        assert_eq!(GraceInterest::PenaltyRate.accrual_window(None, day(0), day(40)), (day(0), day(40)));
     }
-}
\ No newline at end of file
+}
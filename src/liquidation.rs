@@ -0,0 +1,58 @@
+use scrypto::prelude::*;
+
+// This module holds the collateral and liquidation arithmetic: what pledged collateral
+// is worth, how much debt a single liquidation may repay and how much collateral it
+// releases in return. Like `core_math`, it is free of ledger state.
+
+/// Values an amount of collateral at a price, less its haircut.
+pub fn haircut_value(amount: Decimal, price: Decimal, haircut: Decimal) -> Decimal {
+    amount * price * (Decimal::ONE - haircut)
+}
+
+/// Whether collateral worth `collateral_value` still covers `amount_owed` at the minimum
+/// collateral ratio.
+pub fn covers_minimum_ratio(collateral_value: Decimal, amount_owed: Decimal, min_ratio: Decimal) -> bool {
+    collateral_value >= amount_owed * min_ratio
+}
+
+/// Limits a single liquidation to the close factor's share of the amount owed, so a
+/// breach can be cured by partial liquidations rather than a full close-out.
+pub fn max_repayable(total_due: Decimal, close_factor: Decimal) -> Decimal {
+    total_due * close_factor
+}
+
+/// Returns the share of every collateral vault released for repaying `repaid`: the
+/// repaid amount plus the liquidation bonus, over the market value of the collateral
+/// before haircuts, capped at all of the collateral.
+pub fn collateral_share(repaid: Decimal, market_value: Decimal, bonus: Decimal) -> Decimal {
+    if market_value == Decimal::ZERO {
+        return Decimal::ONE;
+    }
+    (repaid * (Decimal::ONE + bonus) / market_value).min(Decimal::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn haircuts_reduce_the_collateral_value() {
+        assert_eq!(haircut_value(Decimal::from(100), dec!("2.5"), Decimal::ZERO), Decimal::from(250));
+        assert_eq!(haircut_value(Decimal::from(100), dec!("2.5"), dec!("0.2")), Decimal::from(200));
+        assert_eq!(haircut_value(Decimal::ZERO, dec!("2.5"), dec!("0.2")), Decimal::ZERO);
+    }
+
+    #[test]
+    fn releases_must_leave_the_minimum_ratio_covered() {
+        // 1,000 owed at a 150% minimum ratio, against 100 units priced at 20 with a 10% haircut
+        let (owed, min_ratio, price, haircut) = (Decimal::from(1_000), dec!("1.5"), Decimal::from(20), dec!("0.1"));
+        let after_release = |released: Decimal| haircut_value(Decimal::from(100) - released, price, haircut);
+        // Releasing 10 units leaves 1,620, above the 1,500 required
+        assert!(covers_minimum_ratio(after_release(Decimal::from(10)), owed, min_ratio));
+        // Releasing 40 units leaves 1,080
+        assert!(!covers_minimum_ratio(after_release(Decimal::from(40)), owed, min_ratio));
+        // Exactly at the ratio is enough, and nothing owed frees everything
+        assert!(covers_minimum_ratio(Decimal::from(1_500), owed, min_ratio));
+        assert!(covers_minimum_ratio(Decimal::ZERO, Decimal::ZERO, min_ratio));
+    }
+}
@@ -0,0 +1,80 @@
+use scrypto::prelude::*;
+
+// This module defines a reference-rate and price oracle blueprint. A trusted publisher
+// posts benchmark rates and collateral prices, and contracts read them instead of
+// taking a number from either party.
+#[blueprint]
+mod reference_rate_oracle {
+    /// The ReferenceRateOracle struct holds the latest published value of each benchmark and price.
+    struct ReferenceRateOracle {
+        publisher: ResourceAddress,        // Badge allowed to publish rates and prices
+        rates: KeyValueStore<String, (Decimal, Instant)>, // Latest rate per benchmark, with when it was published
+        prices: KeyValueStore<ResourceAddress, (Decimal, Instant)>, // Latest price per resource, with when it was published
+    }
+
+    impl ReferenceRateOracle {
+        /// Instantiates a new oracle with no published rates or prices.
+        ///
+        /// # Arguments
+        /// * `publisher` - ResourceAddress of the badge allowed to publish rates and prices
+        ///
+        /// # Returns
+        /// The globalized oracle component
+        pub fn instantiate_oracle(publisher: ResourceAddress) -> Global<ReferenceRateOracle> {
+            Self {
+                publisher,
+                rates: KeyValueStore::new(),
+                prices: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        /// Publishes the current value of a benchmark, replacing the previous one.
+        ///
+        /// # Arguments
+        /// * `benchmark` - The name of the benchmark, e.g. "SOFR"
+        /// * `rate` - The annual rate, as a decimal
+        pub fn publish_rate(&mut self, benchmark: String, rate: Decimal) {
+            Runtime::assert_access_rule(rule!(require(self.publisher)));
+            assert!(rate > -Decimal::ONE && rate < Decimal::ONE, "Rate must be above -1 and below 1");
+            self.rates.insert(benchmark, (rate, Clock::current_time_rounded_to_seconds()));
+        }
+
+        /// Reads the latest value of a benchmark.
+        ///
+        /// # Arguments
+        /// * `benchmark` - The name of the benchmark
+        ///
+        /// # Returns
+        /// The (rate, published_at) pair, with the publication time as a Unix timestamp
+        pub fn get_rate(&self, benchmark: String) -> (Decimal, i64) {
+            let (rate, published_at) = *self.rates.get(&benchmark).expect("No rate published for this benchmark");
+            (rate, published_at.seconds_since_unix_epoch)
+        }
+
+        /// Publishes the current price of a resource, replacing the previous one.
+        ///
+        /// # Arguments
+        /// * `resource` - The priced resource
+        /// * `price` - The price of one unit, in the currency loans are denominated in
+        pub fn publish_price(&mut self, resource: ResourceAddress, price: Decimal) {
+            Runtime::assert_access_rule(rule!(require(self.publisher)));
+            assert!(price >= Decimal::ZERO, "Price cannot be negative");
+            self.prices.insert(resource, (price, Clock::current_time_rounded_to_seconds()));
+        }
+
+        /// Reads the latest price of a resource.
+        ///
+        /// # Arguments
+        /// * `resource` - The priced resource
+        ///
+        /// # Returns
+        /// The (price, published_at) pair, with the publication time as a Unix timestamp
+        pub fn get_price(&self, resource: ResourceAddress) -> (Decimal, i64) {
+            let (price, published_at) = *self.prices.get(&resource).expect("No price published for this resource");
+            (price, published_at.seconds_since_unix_epoch)
+        }
+    }
+}
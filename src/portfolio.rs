@@ -0,0 +1,98 @@
+use scrypto::prelude::*;
+use crate::call_money::CallMoney;
+use crate::registry::ContractRegistry;
+use crate::ScheduledEventType;
+
+// This module defines a portfolio blueprint that aggregates a treasury's Call Money
+// positions. It reports book-level balances, rates and upcoming events on-ledger for
+// the contracts tracked by a registry, which manages what is in the book.
+#[blueprint]
+mod portfolio {
+    /// The Portfolio struct reports on the contracts in a treasury book.
+    struct Portfolio {
+        registry: Global<ContractRegistry>, // Registry tracking the contracts in the book
+    }
+
+    impl Portfolio {
+        /// Instantiates a portfolio reporting on the contracts a registry tracks.
+        /// Contracts are added and removed through the registry.
+        ///
+        /// # Arguments
+        /// * `registry` - The registry tracking the contracts in the book
+        ///
+        /// # Returns
+        /// The globalized portfolio component
+        pub fn instantiate_portfolio(registry: Global<ContractRegistry>) -> Global<Portfolio> {
+            Self { registry }
+                .instantiate()
+                .prepare_to_globalize(OwnerRole::None)
+                .globalize()
+        }
+
+        /// Sums the outstanding principal across the portfolio.
+        ///
+        /// # Returns
+        /// The total outstanding principal
+        pub fn total_outstanding_principal(&self) -> Decimal {
+            self.held()
+                .into_iter()
+                .fold(Decimal::ZERO, |total, (_, contract)| total + contract.get_summary().principal)
+        }
+
+        /// Sums the accrued interest across the portfolio, projected to a given date.
+        ///
+        /// # Arguments
+        /// * `as_of` - The date to project interest to, as a Unix timestamp
+        ///
+        /// # Returns
+        /// The total accrued interest
+        pub fn total_accrued_interest(&self, as_of: i64) -> Decimal {
+            self.held()
+                .into_iter()
+                .fold(Decimal::ZERO, |total, (_, contract)| total + contract.actus_state(as_of).accrued_interest)
+        }
+
+        /// Averages the interest rates across the portfolio, weighted by outstanding principal.
+        ///
+        /// # Returns
+        /// The weighted average rate, or zero when no principal is outstanding
+        pub fn weighted_average_rate(&self) -> Decimal {
+            let (weighted, principal) = self.held()
+                .into_iter()
+                .map(|(_, contract)| contract.get_summary())
+                .fold((Decimal::ZERO, Decimal::ZERO), |(weighted, principal), summary| {
+                    (weighted + summary.principal * summary.interest_rate, principal + summary.principal)
+                });
+            if principal == Decimal::ZERO {
+                return Decimal::ZERO;
+            }
+            weighted / principal
+        }
+
+        /// Lists the next scheduled events across the portfolio, earliest first.
+        ///
+        /// # Arguments
+        /// * `count` - The maximum number of events to return
+        ///
+        /// # Returns
+        /// A vector of (registry id, date, event_type) rows
+        pub fn next_events(&self, count: u32) -> Vec<(u64, i64, ScheduledEventType)> {
+            let mut events: Vec<(u64, i64, ScheduledEventType)> = self.held()
+                .into_iter()
+                .flat_map(|(id, contract)| {
+                    contract.upcoming_events()
+                        .into_iter()
+                        .map(move |(date, event_type)| (id, date, event_type))
+                })
+                .collect();
+            events.sort_by_key(|(id, date, _)| (*date, *id));
+            events.truncate(count as usize);
+            events
+        }
+
+        /// Collects the contracts the registry tracks.
+        fn held(&self) -> Vec<(u64, Global<CallMoney>)> {
+            self.registry.contracts()
+        }
+    }
+}
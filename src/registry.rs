@@ -0,0 +1,132 @@
+use scrypto::prelude::*;
+use crate::call_money::CallMoney;
+use crate::ContractStatus;
+
+// This module defines a registry blueprint that tracks a book of Call Money contracts.
+// It gives operators book-level reporting without querying each contract separately.
+
+pub use contract_registry::ContractRegistry;
+
+/// Sums the amount due across the rows of a portfolio summary that are not yet repaid.
+fn book_exposure(summary: &[(u64, Decimal, ContractStatus)]) -> Decimal {
+    summary
+        .iter()
+        .filter(|(_, _, status)| *status != ContractStatus::Repaid)
+        .fold(Decimal::ZERO, |total, (_, amount_due, _)| total + *amount_due)
+}
+
+#[blueprint]
+mod contract_registry {
+    /// The ContractRegistry struct tracks the contracts in a book.
+    struct ContractRegistry {
+        operator: ResourceAddress,         // Badge allowed to register and deregister contracts
+        contracts: KeyValueStore<u64, Global<CallMoney>>, // Tracked contracts keyed by registry id
+        next_id: u64,                      // Id assigned to the next registered contract
+    }
+
+    impl ContractRegistry {
+        /// Instantiates a new, empty registry.
+        ///
+        /// # Arguments
+        /// * `operator` - ResourceAddress of the badge allowed to manage the registry
+        ///
+        /// # Returns
+        /// The globalized registry component
+        pub fn instantiate_registry(operator: ResourceAddress) -> Global<ContractRegistry> {
+            Self {
+                operator,
+                contracts: KeyValueStore::new(),
+                next_id: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        /// Adds a contract to the book.
+        ///
+        /// # Arguments
+        /// * `contract` - The contract to track
+        ///
+        /// # Returns
+        /// The registry id assigned to the contract
+        pub fn register(&mut self, contract: Global<CallMoney>) -> u64 {
+            self.require_operator();
+            let id = self.next_id;
+            self.contracts.insert(id, contract);
+            self.next_id += 1;
+            id
+        }
+
+        /// Removes a contract from the book, e.g. one whose calls fail.
+        ///
+        /// # Arguments
+        /// * `id` - The registry id of the contract
+        pub fn deregister(&mut self, id: u64) {
+            self.require_operator();
+            assert!(self.contracts.remove(&id).is_some(), "No contract is registered under this id");
+        }
+
+        /// Reports every tracked contract as of a given date.
+        ///
+        /// A failing call to a contract aborts the whole transaction in Scrypto, so it
+        /// cannot be caught here; deregister such a contract to keep the report working.
+        /// Ids left free by deregistered contracts are skipped.
+        ///
+        /// # Arguments
+        /// * `current_date` - The date to project balances to, as a Unix timestamp
+        ///
+        /// # Returns
+        /// A vector of (id, amount_due, status) rows
+        pub fn portfolio_summary(&self, current_date: i64) -> Vec<(u64, Decimal, ContractStatus)> {
+            self.contracts()
+                .into_iter()
+                .map(|(id, contract)| (id, contract.total_amount_due(current_date), contract.get_details().status))
+                .collect()
+        }
+
+        /// Sums the amount due across all contracts that are not yet repaid.
+        ///
+        /// # Arguments
+        /// * `current_date` - The date to project balances to, as a Unix timestamp
+        ///
+        /// # Returns
+        /// The total outstanding exposure of the book
+        pub fn total_book_exposure(&self, current_date: i64) -> Decimal {
+            book_exposure(&self.portfolio_summary(current_date))
+        }
+
+        /// Lists the tracked contracts, skipping ids left free by deregistered contracts.
+        ///
+        /// # Returns
+        /// A vector of (id, contract) rows, by id
+        pub fn contracts(&self) -> Vec<(u64, Global<CallMoney>)> {
+            (0..self.next_id)
+                .filter_map(|id| self.contracts.get(&id).map(|contract| (id, contract.clone())))
+                .collect()
+        }
+
+        /// Ensures the caller holds the operator badge.
+        fn require_operator(&self) {
+            Runtime::assert_access_rule(rule!(require(self.operator)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repaid_contracts_leave_the_book_exposure() {
+        let summary = [
+            (0, Decimal::from(1_000), ContractStatus::Active),
+            (1, Decimal::from(250), ContractStatus::Called),
+            (2, Decimal::ZERO, ContractStatus::Repaid),
+            (4, Decimal::from(75), ContractStatus::Delinquent),
+        ];
+        assert_eq!(book_exposure(&summary), Decimal::from(1_325));
+        assert_eq!(book_exposure(&summary[2..3]), Decimal::ZERO);
+        assert_eq!(book_exposure(&[]), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{BusinessDayCalendar, ScheduledEvent, ScheduledEventType};
+
+// This module generates a contract's ACTUS event schedule from its terms. Like
+// `core_math`, it is free of ledger state, so the schedule an integrator projects
+// matches the one the contract reports.
+
+/// The terms that determine when a contract's events fall.
+#[derive(Clone, Debug, Default)]
+pub struct ScheduleTerms {
+    pub start_date: i64,               // IED: initial exchange date
+    pub end_date: Option<i64>,         // Date after which no events occur, e.g. once the contract is closed
+    pub interest_payment: Option<(i64, i64)>, // (any interest payment date, cycle)
+    pub capitalization: Option<(i64, i64)>, // (any capitalization date, cycle)
+    pub rate_reset: Option<(i64, i64)>, // (any rate reset date, cycle)
+    pub rate_steps: Vec<i64>,          // Effective dates of pre-agreed rate steps
+    pub penalty_start: Option<i64>,    // When penalties start accruing on an overdue balance
+    pub due_date: Option<(i64, ScheduledEventType)>, // When the balance falls due, with how it was set
+    pub calendar: BusinessDayCalendar, // Calendar interest payment dates are shifted by
+}
+
+/// Generates the events falling between `from` and `to`, inclusive, earliest first.
+/// Events on the same date are ordered initial exchange, rate changes, capitalization,
+/// interest payment, due date, penalty. Principal prepayments and purchases happen on
+/// demand, so they are never scheduled.
+pub fn generate(terms: &ScheduleTerms, from: i64, to: i64) -> Vec<ScheduledEvent> {
+    let until = terms.end_date.map_or(to, |end_date| end_date.min(to));
+    let mut events = Vec::new();
+    let mut push = |date: i64, event_type: ScheduledEventType| {
+        if date >= from && date <= until {
+            events.push(ScheduledEvent { date, event_type });
+        }
+    };
+
+    push(terms.start_date, ScheduledEventType::InitialExchange);
+    for date in &terms.rate_steps {
+        push(*date, ScheduledEventType::RateStep);
+    }
+    let cycles = [
+        (terms.rate_reset, ScheduledEventType::RateReset),
+        (terms.capitalization, ScheduledEventType::Capitalization),
+        (terms.interest_payment, ScheduledEventType::InterestPayment),
+    ];
+    for (cycle, event_type) in cycles {
+        if let Some((anchor, cycle)) = cycle {
+            for date in cycle_dates(anchor, cycle, terms.start_date, from, until) {
+                // Interest payments fall due on business days
+                let date = if event_type == ScheduledEventType::InterestPayment { terms.calendar.adjust(date) } else { date };
+                push(date, event_type);
+            }
+        }
+    }
+    if let Some((due_date, event_type)) = terms.due_date {
+        push(due_date, event_type);
+    }
+    if let Some(penalty_start) = terms.penalty_start {
+        push(penalty_start, ScheduledEventType::Penalty);
+    }
+
+    // Stable, so same-date events keep the order they were generated in
+    events.sort_by_key(|event| event.date);
+    events
+}
+
+/// Lists the dates `anchor + k * cycle`, for any integer `k`, that fall after `after`
+/// and between `from` and `until`, inclusive.
+fn cycle_dates(anchor: i64, cycle: i64, after: i64, from: i64, until: i64) -> Vec<i64> {
+    let lower = from.max(after + 1);
+    if cycle <= 0 || lower > until {
+        return Vec::new();
+    }
+    let mut date = anchor + (lower - anchor).div_euclid(cycle) * cycle;
+    if date < lower {
+        date += cycle;
+    }
+    let mut dates = Vec::new();
+    while date <= until {
+        dates.push(date);
+        date += cycle;
+    }
+    dates
+}
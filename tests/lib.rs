@@ -0,0 +1,398 @@
+use call_money::call_money_test::*;
+use call_money::*;
+use scrypto_test::prelude::*;
+
+const START: i64 = 1_700_000_000;
+const DAY: i64 = 86_400;
+
+/// A contract lent in a fresh currency, with the rest of the currency minted for repayments.
+struct Loan {
+    contract: CallMoney,
+    funds: Bucket,
+}
+
+/// Mints `amount` of a fresh fungible resource.
+fn mint(amount: Decimal, env: &mut TestEnvironment<InMemorySubstateDatabase>) -> Result<Bucket, RuntimeError> {
+    Ok(ResourceBuilder::new_fungible(OwnerRole::None).divisibility(DIVISIBILITY_MAXIMUM).mint_initial_supply(amount, env)?.into())
+}
+
+/// Valid terms every test starts from: 1,000 lent at 5% from `START`.
+fn terms(currency: ResourceAddress, lender: ResourceAddress, borrower: ResourceAddress) -> ContractTerms {
+    ContractTerms {
+        contract_id: None,
+        contract_role: ContractRole::RealPositionAsset,
+        currency,
+        lender,
+        borrower,
+        reference: ContractReference {
+            deal_id: "DEAL-1".to_string(),
+            lender_lei: "5493001KJTIIGC8Y1R12".to_string(),
+            borrower_lei: "529900T8BM49AURSDO55".to_string(),
+            booking_entity: "LDN".to_string(),
+            currency_code: "USD".to_string(),
+        },
+        interest_rate: dec!("0.05"),
+        allow_negative_rate: false,
+        day_count: DayCountConvention::Actual365,
+        rounding: RoundingConvention::Down,
+        capitalization_cycle: None,
+        interest_payment_cycle: None,
+        interest_payment_anchor: None,
+        rate_reset: None,
+        rate_cap: None,
+        rate_floor: None,
+        rate_schedule: vec![],
+        start_date: START,
+        notice_period: 2 * DAY,
+        grace_period: DAY,
+        grace_interest: GraceInterest::Continue,
+        notice_start: NoticeStart::Call,
+        calendar: BusinessDayCalendar::default(),
+        penalty_type: PenaltyType::DailyRate,
+        penalty_rate: dec!("0.1"),
+        penalty_tiers: vec![],
+        penalty_base: PenaltyBase::PrincipalOnly,
+        prepayment: None,
+        fees: None,
+        withholding: None,
+        keeper_reward: None,
+        min_partial_repayment: Decimal::ZERO,
+        settlement_resource: None,
+        accepted_collateral: vec![],
+        min_collateral_ratio: Decimal::ZERO,
+        max_ltv: Decimal::ZERO,
+        close_factor: Decimal::ONE,
+        liquidation_bonus: Decimal::ZERO,
+        penalty_cap: None,
+        total_cost_cap: None,
+        max_interest_multiple: None,
+        royalty_config: None,
+        credit_limit: None,
+        commitment_fee_rate: Decimal::ZERO,
+        test_mode: false,
+    }
+}
+
+/// Publishes the package and lends 1,000 at `START` on the terms `customize` makes
+/// of the default ones. Auth is disabled, so either party can act.
+fn lend(
+    customize: impl FnOnce(ContractTerms) -> ContractTerms,
+    env: &mut TestEnvironment<InMemorySubstateDatabase>,
+) -> Result<Loan, RuntimeError> {
+    env.disable_auth_module();
+    env.set_current_time(Instant::new(START));
+    let package_address = PackageFactory::compile_and_publish(this_package!(), env, CompileProfile::Fast)?;
+    let mut funds = mint(dec!(1_000_000), env)?;
+    let principal_funds = funds.take(dec!(1000), env)?;
+    let lender = mint(Decimal::ONE, env)?.resource_address(env)?;
+    let borrower = mint(Decimal::ONE, env)?.resource_address(env)?;
+    let currency = funds.resource_address(env)?;
+    let (contract, _creditor_note, _owner_badge) =
+        CallMoney::instantiate_call_money(customize(terms(currency, lender, borrower)), principal_funds, package_address, env)?;
+    Ok(Loan { contract, funds })
+}
+
+/// A small seeded generator, so every run exercises the same sequences.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // xorshift64*
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    /// An amount between 0 and `bound`, in cents.
+    fn amount(&mut self, bound: u64) -> Decimal {
+        Decimal::from(self.below(bound * 100 + 1)) / Decimal::from(100)
+    }
+}
+
+/// Asserts that a call failed with the contract error of the given code.
+fn assert_fails_with<T>(result: Result<T, RuntimeError>, code: u16) {
+    match result.err() {
+        Some(RuntimeError::ApplicationError(ApplicationError::PanicMessage(message))) => {
+            assert!(message.contains(&format!("CLM_ERR[{}]", code)), "expected CLM_ERR[{}], got {}", code, message)
+        }
+        error => panic!("expected CLM_ERR[{}], got {:?}", code, error),
+    }
+}
+
+/// Simple interest on `principal` at `rate` over `days` under Actual/365, rounded down like the contract.
+fn interest(principal: Decimal, rate: Decimal, days: i64) -> Decimal {
+    principal * rate * Decimal::from(days) / dec!(365)
+}
+
+#[test]
+fn the_actus_state_projects_interest_and_fees_to_the_status_date() -> Result<(), RuntimeError> {
+    let mut env = TestEnvironment::new();
+    let fees = FeeTerms { fee_basis: FeeBasis::Absolute, origination_fee: Decimal::ZERO, fee_rate: dec!(2), fee_cycle: 30 * DAY };
+    let Loan { mut contract, .. } = lend(|terms| ContractTerms { fees: Some(fees), ..terms }, &mut env)?;
+
+    // Nothing has accrued yet: the state projects 45 days of interest and the fee charged on day 30
+    let state = contract.actus_state(START + 45 * DAY, &mut env)?;
+    assert_eq!(state.status_date, START + 45 * DAY);
+    assert_eq!(state.contract_performance, ContractPerformance::Performant);
+    assert_eq!(state.nominal_value, dec!(1000));
+    assert_eq!(state.nominal_interest_rate, dec!("0.05"));
+    assert_eq!(state.accrued_interest, interest(dec!(1000), dec!("0.05"), 45));
+    assert_eq!(state.fee_accrued, dec!(2));
+
+    // Once 40 days are accrued, the state adds the projected part on top: the day 30 fee
+    // is charged, the day 60 one is projected
+    env.set_current_time(Instant::new(START + 40 * DAY));
+    contract.update_accrued_interest(&mut env)?;
+    assert_eq!(contract.get_summary(&mut env)?.accrued_fees, dec!(2));
+    let state = contract.actus_state(START + 70 * DAY, &mut env)?;
+    assert_eq!(state.accrued_interest, interest(dec!(1000), dec!("0.05"), 70));
+    assert_eq!(state.fee_accrued, dec!(4));
+
+    // Projecting leaves the contract untouched
+    assert_eq!(contract.get_summary(&mut env)?.accrued_fees, dec!(2));
+    Ok(())
+}
+
+#[test]
+fn fees_count_toward_the_total_cost_cap() -> Result<(), RuntimeError> {
+    // Interest runs at about 4.11 and fees at 2 per 30 days; 30 days in, 6.11 has been charged
+    let fees = FeeTerms { fee_basis: FeeBasis::Absolute, origination_fee: Decimal::ZERO, fee_rate: dec!(2), fee_cycle: 30 * DAY };
+    for (cap, fees_charged) in [(dec!(10), dec!(2)), (dec!(5), dec!(5) - interest(dec!(1000), dec!("0.05"), 30))] {
+        let mut env = TestEnvironment::new();
+        let Loan { mut contract, .. } = lend(|terms| ContractTerms { fees: Some(fees), total_cost_cap: Some(cap), ..terms }, &mut env)?;
+        for day in [30, 60, 90] {
+            env.set_current_time(Instant::new(START + day * DAY));
+            contract.update_accrued_interest(&mut env)?;
+        }
+
+        // Interest accrues first each period, fees take what is left under the cap
+        let economics = contract.get_economics(START + 90 * DAY, &mut env)?;
+        assert_eq!(economics.total_interest_accrued + economics.total_penalties_accrued + economics.total_fees_accrued, cap);
+        assert_eq!(economics.total_fees_accrued, fees_charged);
+        assert_eq!(contract.actus_state(START + 120 * DAY, &mut env)?.fee_accrued, fees_charged);
+    }
+    Ok(())
+}
+
+#[test]
+fn a_rolled_over_contract_accrues_at_the_new_rate_and_can_be_called_again() -> Result<(), RuntimeError> {
+    let mut env = TestEnvironment::new();
+    let Loan { mut contract, mut funds } = lend(|terms| terms, &mut env)?;
+
+    // Called on day 72, rolled over on day 73 once 73 days at 5% have accrued: exactly 10
+    env.set_current_time(Instant::new(START + 72 * DAY));
+    let (_, first_due_date) = contract.call_money(None, &mut env)?;
+    env.set_current_time(Instant::new(START + 73 * DAY));
+    contract.rollover(dec!("0.06"), &mut env)?;
+
+    let summary = contract.get_summary(&mut env)?;
+    assert_eq!(summary.status, ContractStatus::Active);
+    assert_eq!(summary.principal, dec!(1010));
+    assert_eq!(summary.accrued_interest, Decimal::ZERO);
+    assert_eq!(summary.interest_rate, dec!("0.06"));
+    assert_eq!(contract.get_rate_history(&mut env)?.last(), Some(&(Instant::new(START + 73 * DAY), dec!("0.06"))));
+
+    // The capitalized principal accrues at the new rate: 73 days at 6% on 1,010
+    env.set_current_time(Instant::new(START + 146 * DAY));
+    contract.update_accrued_interest(&mut env)?;
+    assert_eq!(contract.get_summary(&mut env)?.accrued_interest, dec!("12.12"));
+
+    // A new call gets a full notice period of its own
+    let (called, due_date) = contract.call_money(None, &mut env)?;
+    assert_eq!(called, dec!("1022.12"));
+    assert!(due_date >= START + 148 * DAY && due_date > first_due_date);
+    let payment = funds.take(called, &mut env)?;
+    contract.repay(payment, None, &mut env)?;
+    assert_eq!(contract.get_summary(&mut env)?.status, ContractStatus::Repaid);
+    Ok(())
+}
+
+#[test]
+fn a_guarantee_is_drawn_into_the_repayments() -> Result<(), RuntimeError> {
+    let mut env = TestEnvironment::new();
+    let Loan { mut contract, mut funds } = lend(|terms| terms, &mut env)?;
+
+    // Only the principal currency is accepted, so worthless tokens cannot stand in for it
+    let worthless = mint(dec!(2000), &mut env)?;
+    assert_fails_with(contract.set_guarantor(worthless, &mut env), 20);
+    let guarantee = funds.take(dec!(2000), &mut env)?;
+    contract.set_guarantor(guarantee, &mut env)?;
+
+    // Not drawable until the called amount is overdue past the grace period
+    env.set_current_time(Instant::new(START + 10 * DAY));
+    let (_, due_date) = contract.call_money(None, &mut env)?;
+    assert_fails_with(contract.draw_guarantee(&mut env), 26);
+    env.set_current_time(Instant::new(due_date + 2 * DAY));
+    let total_due = contract.total_amount_due(due_date + 2 * DAY, &mut env)?;
+
+    // The guarantee repays the loan and the lender collects it like any repayment
+    let drawn = contract.draw_guarantee(&mut env)?;
+    assert_eq!(drawn, total_due);
+    assert_eq!(contract.get_summary(&mut env)?.status, ContractStatus::Repaid);
+    let repayments = contract.collect_repayments(&mut env)?.amount(&mut env)?;
+    let interest = contract.collect_interest(&mut env)?.amount(&mut env)?;
+    assert_eq!(repayments + interest, drawn);
+    assert_eq!(contract.release_guarantee(&mut env)?.amount(&mut env)?, dec!(2000) - drawn);
+    Ok(())
+}
+
+#[test]
+fn replaying_the_ledger_reproduces_the_live_balances() -> Result<(), RuntimeError> {
+    let fees = FeeTerms { fee_basis: FeeBasis::Absolute, origination_fee: dec!(5), fee_rate: dec!(1), fee_cycle: 20 * DAY };
+    for seed in [1, 42, 2024, 0x9e37_79b9_7f4a_7c15] {
+        let mut rng = Rng(seed);
+        let mut env = TestEnvironment::new();
+        let customize = |terms| ContractTerms { capitalization_cycle: Some(45 * DAY), fees: Some(fees), ..terms };
+        let Loan { mut contract, mut funds } = lend(customize, &mut env)?;
+        let mut now = START;
+        let mut due_date = None;
+
+        // Drive accrual, fees, capitalization, calls, penalties, payments and rollovers
+        // through the contract, checking the ledger against the live balances after each
+        for _ in 0..80 {
+            now += rng.below(6 * DAY as u64) as i64;
+            env.set_current_time(Instant::new(now));
+            match (rng.below(5), due_date) {
+                (0, _) => contract.update_accrued_interest(&mut env)?,
+                (1, _) => {
+                    let payment = funds.take(rng.amount(30), &mut env)?;
+                    let (change, _) = contract.repay(payment, None, &mut env)?;
+                    funds.put(change, &mut env)?;
+                }
+                (2, None) => due_date = Some(contract.call_money(None, &mut env)?.1),
+                (2 | 3, Some(due)) if now > due + DAY => {
+                    contract.apply_penalty(&mut env)?;
+                }
+                (4, Some(due)) if now > due => {
+                    contract.rollover(Decimal::from(rng.below(10)) / dec!(100), &mut env)?;
+                    due_date = None;
+                }
+                _ => contract.update_accrued_interest(&mut env)?,
+            }
+            let replay = contract.replay_check(&mut env)?;
+            assert!(replay.matches, "seed {}: {:?}", seed, replay);
+        }
+
+        // Every kind of balance change was exercised
+        let ledger = contract.get_ledger(&mut env)?;
+        assert!(ledger.iter().any(|entry| matches!(entry, LedgerEntry::InterestCapitalized { .. })));
+        assert!(ledger.iter().any(|entry| matches!(entry, LedgerEntry::PenaltyApplied { .. })));
+        assert!(ledger.iter().any(|entry| matches!(entry, LedgerEntry::FeeAccrued { .. })));
+        assert!(ledger.iter().any(|entry| matches!(entry, LedgerEntry::Payment { .. })));
+    }
+    Ok(())
+}
+
+#[test]
+fn a_settlement_quote_forgives_interest_but_not_fees_charged_since() -> Result<(), RuntimeError> {
+    let fees = FeeTerms { fee_basis: FeeBasis::Absolute, origination_fee: Decimal::ZERO, fee_rate: dec!(2), fee_cycle: 30 * DAY };
+    for (paid_on, still_owed) in [(28, Decimal::ZERO), (31, dec!(2))] {
+        let mut env = TestEnvironment::new();
+        let Loan { mut contract, mut funds } = lend(|terms| ContractTerms { fees: Some(fees), ..terms }, &mut env)?;
+        env.set_current_time(Instant::new(START + 25 * DAY));
+        let (quoted, _) = contract.request_settlement_quote(10 * DAY, &mut env)?;
+
+        // Paid within the quote's validity: the interest accrued since is forgiven, while the
+        // fee falling due on day 30 is still owed
+        env.set_current_time(Instant::new(START + paid_on * DAY));
+        let simulated = contract.simulate_repayment(quoted, START + paid_on * DAY, &mut env)?;
+        assert_eq!(simulated.1 + simulated.2 + simulated.3 + simulated.4, quoted);
+        assert_eq!(simulated.4, dec!(1000) - still_owed);
+        let payment = funds.take(quoted, &mut env)?;
+        contract.repay(payment, None, &mut env)?;
+
+        let summary = contract.get_summary(&mut env)?;
+        assert_eq!(summary.accrued_interest, Decimal::ZERO);
+        assert_eq!(summary.principal, still_owed);
+        assert_eq!(contract.total_amount_due(START + paid_on * DAY, &mut env)?, still_owed);
+        assert!(contract.replay_check(&mut env)?.matches);
+    }
+    Ok(())
+}
+
+#[test]
+fn collateral_is_only_released_against_oracle_prices() -> Result<(), RuntimeError> {
+    let mut env = TestEnvironment::new();
+    let collateral = mint(dec!(500), &mut env)?;
+    let resource = collateral.resource_address(&mut env)?;
+    let customize = |terms| ContractTerms { accepted_collateral: vec![(resource, dec!("0.1"))], min_collateral_ratio: dec!("1.2"), ..terms };
+    let Loan { mut contract, .. } = lend(customize, &mut env)?;
+    contract.add_collateral(collateral, &mut env)?;
+
+    // Nobody can name their own prices: without a collateral oracle nothing is released early
+    assert_fails_with(contract.release_collateral(resource, dec!(100), &mut env), 22);
+    Ok(())
+}
+
+#[test]
+fn a_tokenized_loan_rejects_the_lender_acting_alone_with_coded_errors() -> Result<(), RuntimeError> {
+    let mut env = TestEnvironment::new();
+    let Loan { mut contract, .. } = lend(|terms| terms, &mut env)?;
+    contract.tokenize(dec!("0.5"), &mut env)?;
+
+    assert_fails_with(contract.tokenize(dec!("0.5"), &mut env), 28);
+    assert_fails_with(contract.collect_repayments(&mut env), 28);
+    assert_fails_with(contract.call_money(None, &mut env), 5);
+    Ok(())
+}
+
+#[test]
+fn redeeming_against_empty_repayments_leaves_the_interest_to_the_remaining_tokens() -> Result<(), RuntimeError> {
+    let mut env = TestEnvironment::new();
+    let Loan { mut contract, mut funds } = lend(|terms| terms, &mut env)?;
+    let mut debt_tokens = contract.tokenize(Decimal::ONE, &mut env)?;
+    env.set_current_time(Instant::new(START + 73 * DAY));
+    let payment = funds.take(dec!(100), &mut env)?;
+    let (change, _receipt) = contract.pay_interest(payment, &mut env)?;
+    let interest_paid = dec!(100) - change.amount(&mut env)?;
+    assert_eq!(interest_paid, dec!(10));
+
+    // Nothing has been repaid, so no token is burned and none is paid interest, however often it is presented
+    for _ in 0..2 {
+        let mut payouts = contract.redeem(debt_tokens, &mut env)?;
+        debt_tokens = payouts.pop().unwrap();
+        assert_eq!(debt_tokens.amount(&mut env)?, dec!(1000));
+        for payout in &payouts {
+            assert_eq!(payout.amount(&mut env)?, Decimal::ZERO);
+        }
+    }
+
+    // Once repaid, the tokens receive the principal and all the interest paid
+    let repayment = funds.take(dec!(1000), &mut env)?;
+    contract.repay(repayment, None, &mut env)?;
+    let payouts = contract.redeem(debt_tokens, &mut env)?;
+    assert_eq!(payouts.len(), 2);
+    assert_eq!(payouts[0].amount(&mut env)?, dec!(1000));
+    assert_eq!(payouts[1].amount(&mut env)?, interest_paid);
+    Ok(())
+}
+
+#[test]
+fn the_call_snapshot_keeps_only_the_rate_steps_still_to_take_effect() -> Result<(), RuntimeError> {
+    let mut env = TestEnvironment::new();
+    let schedule = vec![(START + 10 * DAY, dec!("0.06")), (START + 20 * DAY, dec!("0.07")), (START + 30 * DAY, dec!("0.08"))];
+    let Loan { mut contract, .. } = lend(|terms| ContractTerms { rate_schedule: schedule, ..terms }, &mut env)?;
+
+    // Called on the day of the second step: it and the first are already in force
+    env.set_current_time(Instant::new(START + 20 * DAY));
+    contract.call_money(None, &mut env)?;
+    let pending = vec![(Instant::new(START + 30 * DAY), dec!("0.08"))];
+    let snapshot = contract.get_call_snapshot(&mut env)?.unwrap();
+    assert_eq!(snapshot.interest_rate, dec!("0.07"));
+    assert_eq!(snapshot.rate_schedule, pending);
+
+    // The last step taking effect moves the live rate but leaves the evidence as it was
+    env.set_current_time(Instant::new(START + 31 * DAY));
+    contract.update_accrued_interest(&mut env)?;
+    assert_eq!(contract.get_summary(&mut env)?.interest_rate, dec!("0.08"));
+    let snapshot = contract.get_call_snapshot(&mut env)?.unwrap();
+    assert_eq!(snapshot.timestamp, Instant::new(START + 20 * DAY));
+    assert_eq!(snapshot.interest_rate, dec!("0.07"));
+    assert_eq!(snapshot.rate_schedule, pending);
+    Ok(())
+}